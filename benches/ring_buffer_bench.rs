@@ -2,8 +2,12 @@
 //!
 //! Run dengan: cargo bench
 
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
-use hermes::core::RingBuffer;
+use hermes::core::{MpmcRingBuffer, MpscRingBuffer, RingBuffer};
 
 fn bench_push_pop(c: &mut Criterion) {
     let mut group = c.benchmark_group("ring_buffer");
@@ -11,12 +15,13 @@ fn bench_push_pop(c: &mut Criterion) {
 
     // Benchmark push
     group.bench_function("push", |b| {
-        let rb: RingBuffer<u64, 65536> = RingBuffer::new();
+        let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
         let mut i = 0u64;
         b.iter(|| {
-            if !rb.push(black_box(i)) {
-                rb.pop();
-                rb.push(black_box(i));
+            if !tx.push(black_box(i)) {
+                rx.pop();
+                tx.push(black_box(i));
             }
             i = i.wrapping_add(1);
         });
@@ -24,25 +29,39 @@ fn bench_push_pop(c: &mut Criterion) {
 
     // Benchmark pop
     group.bench_function("pop", |b| {
-        let rb: RingBuffer<u64, 65536> = RingBuffer::new();
+        let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
         // Pre-fill
         for i in 0..32768 {
-            rb.push(i);
+            tx.push(i);
         }
         b.iter(|| {
-            if let Some(v) = rb.pop() {
-                rb.push(black_box(v));
+            if let Some(v) = rx.pop() {
+                tx.push(black_box(v));
             }
         });
     });
 
     // Benchmark push+pop cycle
     group.bench_function("push_pop_cycle", |b| {
-        let rb: RingBuffer<u64, 65536> = RingBuffer::new();
+        let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
         let mut i = 0u64;
         b.iter(|| {
-            rb.push(black_box(i));
-            let _ = rb.pop();
+            tx.push(black_box(i));
+            let _ = rx.pop();
+            i = i.wrapping_add(1);
+        });
+    });
+
+    // Push+pop cycle dengan kapasitas runtime (bandingkan dengan const N)
+    group.bench_function("push_pop_cycle_runtime_capacity", |b| {
+        let mut rb: RingBuffer<u64> = RingBuffer::with_capacity(65536);
+        let (mut tx, mut rx) = rb.split();
+        let mut i = 0u64;
+        b.iter(|| {
+            tx.push(black_box(i));
+            let _ = rx.pop();
             i = i.wrapping_add(1);
         });
     });
@@ -57,13 +76,40 @@ fn bench_throughput(c: &mut Criterion) {
     for batch_size in [100, 1000, 10000].iter() {
         group.throughput(Throughput::Elements(*batch_size as u64));
         group.bench_function(format!("batch_{}", batch_size), |b| {
-            let rb: RingBuffer<u64, 65536> = RingBuffer::new();
+            let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+            let (mut tx, mut rx) = rb.split();
+            b.iter(|| {
+                for i in 0..*batch_size {
+                    tx.push(black_box(i as u64));
+                }
+                for _ in 0..*batch_size {
+                    black_box(rx.pop());
+                }
+            });
+        });
+
+        // Batch API: satu Release store per batch
+        group.bench_function(format!("slice_{}", batch_size), |b| {
+            let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+            let (mut tx, mut rx) = rb.split();
+            let input: Vec<u64> = (0..*batch_size as u64).collect();
+            let mut output = vec![0u64; *batch_size];
+            b.iter(|| {
+                tx.push_slice(black_box(&input));
+                black_box(rx.pop_into(&mut output));
+            });
+        });
+
+        // Closure API: bangun/inspect nilai di dalam slot
+        group.bench_function(format!("with_closure_{}", batch_size), |b| {
+            let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+            let (mut tx, mut rx) = rb.split();
             b.iter(|| {
                 for i in 0..*batch_size {
-                    rb.push(black_box(i as u64));
+                    tx.write_with(|slot| slot.write(black_box(i as u64)));
                 }
                 for _ in 0..*batch_size {
-                    black_box(rb.pop());
+                    black_box(rx.read_with(|v| *v));
                 }
             });
         });
@@ -72,5 +118,132 @@ fn bench_throughput(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_push_pop, bench_throughput);
+fn bench_multi_producer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multi_producer");
+    group.throughput(Throughput::Elements(1));
+
+    // Uncontended push+pop cycle: biaya CAS vs plain store
+    group.bench_function("spsc_push_pop_cycle", |b| {
+        let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
+        let mut i = 0u64;
+        b.iter(|| {
+            tx.push(black_box(i));
+            let _ = rx.pop();
+            i = i.wrapping_add(1);
+        });
+    });
+
+    group.bench_function("mpsc_push_pop_cycle", |b| {
+        let rb: MpscRingBuffer<u64, 65536> = MpscRingBuffer::new();
+        let mut i = 0u64;
+        b.iter(|| {
+            rb.push(black_box(i));
+            let _ = rb.pop();
+            i = i.wrapping_add(1);
+        });
+    });
+
+    group.bench_function("mpmc_push_pop_cycle", |b| {
+        let rb: MpmcRingBuffer<u64, 65536> = MpmcRingBuffer::new();
+        let mut i = 0u64;
+        b.iter(|| {
+            rb.push(black_box(i));
+            let _ = rb.pop();
+            i = i.wrapping_add(1);
+        });
+    });
+
+    // Cross-thread transfer: SPSC (1 producer) vs MPSC (N producers)
+    group.bench_function("spsc_1_producer", |b| {
+        b.iter_custom(|iters| {
+            let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+            let (mut tx, mut rx) = rb.split();
+            thread::scope(|s| {
+                let start = Instant::now();
+                s.spawn(move || {
+                    for i in 0..iters {
+                        while !tx.push(i) {
+                            std::hint::spin_loop();
+                        }
+                    }
+                });
+                receive(iters, || rx.pop());
+                start.elapsed()
+            })
+        });
+    });
+
+    for producers in [1usize, 2, 4] {
+        group.bench_function(format!("mpsc_{}_producers", producers), |b| {
+            b.iter_custom(|iters| {
+                let rb: Arc<MpscRingBuffer<u64, 65536>> = Arc::new(MpscRingBuffer::new());
+                run_transfer(
+                    iters,
+                    producers,
+                    {
+                        let rb = Arc::clone(&rb);
+                        move |v| rb.push(v)
+                    },
+                    move || rb.pop(),
+                )
+            });
+        });
+    }
+
+    group.finish();
+}
+
+/// Kirim `iters` elemen dari `producers` thread ke consumer di thread saat ini
+fn run_transfer<P, C>(iters: u64, producers: usize, push: P, pop: C) -> Duration
+where
+    P: Fn(u64) -> bool + Clone + Send + 'static,
+    C: FnMut() -> Option<u64>,
+{
+    let per_producer = iters / producers as u64;
+    let total = per_producer * producers as u64;
+
+    let start = Instant::now();
+    let handles: Vec<_> = (0..producers)
+        .map(|_| {
+            let push = push.clone();
+            thread::spawn(move || {
+                for i in 0..per_producer {
+                    while !push(i) {
+                        std::hint::spin_loop();
+                    }
+                }
+            })
+        })
+        .collect();
+
+    receive(total, pop);
+    let elapsed = start.elapsed();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    elapsed
+}
+
+/// Consume `total` elemen di thread saat ini
+fn receive<C: FnMut() -> Option<u64>>(total: u64, mut pop: C) {
+    let mut received = 0;
+    while received < total {
+        match pop() {
+            Some(v) => {
+                black_box(v);
+                received += 1;
+            }
+            None => std::hint::spin_loop(),
+        }
+    }
+}
+
+criterion_group!(
+    benches,
+    bench_push_pop,
+    bench_throughput,
+    bench_multi_producer
+);
 criterion_main!(benches);
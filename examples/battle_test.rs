@@ -354,23 +354,17 @@ fn parse_args() -> BattleConfig {
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
-            "--tokens" | "-t" => {
-                if i + 1 < args.len() {
-                    config.tokens = args[i + 1].parse().unwrap_or(1000);
-                    i += 1;
-                }
+            "--tokens" | "-t" if i + 1 < args.len() => {
+                config.tokens = args[i + 1].parse().unwrap_or(1000);
+                i += 1;
             }
-            "--rate" | "-r" => {
-                if i + 1 < args.len() {
-                    config.rate = args[i + 1].parse().unwrap_or(100);
-                    i += 1;
-                }
+            "--rate" | "-r" if i + 1 < args.len() => {
+                config.rate = args[i + 1].parse().unwrap_or(100);
+                i += 1;
             }
-            "--host" | "-h" => {
-                if i + 1 < args.len() {
-                    config.host = args[i + 1].clone();
-                    i += 1;
-                }
+            "--host" | "-h" if i + 1 < args.len() => {
+                config.host = args[i + 1].clone();
+                i += 1;
             }
             "--verbose" | "-v" => {
                 config.verbose = true;
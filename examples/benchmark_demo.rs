@@ -6,7 +6,7 @@
 //! - No-Allocation: Pre-allocated buffers
 //! - Binary Protocol: SBE-inspired flat encoding
 
-use hermes::core::{MmapStorage, RingBuffer};
+use hermes::core::{HugePages, MmapStorage, RingBuffer, StorageOptions};
 use hermes::protocol::{Decoder, Encoder, MessageType};
 use std::time::Instant;
 
@@ -20,6 +20,9 @@ fn main() {
     // Benchmark Mmap Storage
     benchmark_mmap_storage();
 
+    // Benchmark Mmap Tuning (tail latency)
+    benchmark_mmap_tuning();
+
     // Benchmark Protocol Encoding
     benchmark_protocol();
 
@@ -32,36 +35,37 @@ fn benchmark_ring_buffer() {
     println!("-----------------------------------------");
 
     const ITERATIONS: usize = 1_000_000;
-    let rb: RingBuffer<u64, 65536> = RingBuffer::new();
+    let mut rb: RingBuffer<u64, 65536> = RingBuffer::new();
+    let (mut tx, mut rx) = rb.split();
 
     // Warm up
     for i in 0..1000 {
-        rb.push(i);
+        tx.push(i);
     }
     for _ in 0..1000 {
-        rb.pop();
+        rx.pop();
     }
 
     // Benchmark push
     let start = Instant::now();
     for i in 0..ITERATIONS {
-        while !rb.push(i as u64) {
-            rb.pop();
+        while !tx.push(i as u64) {
+            rx.pop();
         }
     }
     let push_duration = start.elapsed();
 
     // Drain
-    while rb.pop().is_some() {}
+    while rx.pop().is_some() {}
 
     // Benchmark pop
     for i in 0..ITERATIONS {
-        rb.push(i as u64);
+        tx.push(i as u64);
     }
 
     let start = Instant::now();
     for _ in 0..ITERATIONS {
-        rb.pop();
+        rx.pop();
     }
     let pop_duration = start.elapsed();
 
@@ -93,7 +97,8 @@ fn benchmark_mmap_storage() {
     const MSG_SIZE: usize = 64;
 
     let path = "hermes_bench.dat";
-    let mut storage = MmapStorage::open(path, 64 * 1024 * 1024).unwrap();
+    let mut storage =
+        MmapStorage::open_raw(path, 64 * 1024 * 1024, &StorageOptions::new()).unwrap();
 
     let msg = [0u8; MSG_SIZE];
 
@@ -135,6 +140,88 @@ fn benchmark_mmap_storage() {
     std::fs::remove_file(path).ok();
 }
 
+/// Tail latency write ke storage baru: setiap page pertama kali disentuh
+/// memicu page fault kecuali sudah di-prefault saat open
+fn benchmark_mmap_tuning() {
+    println!("📊 Mmap Tuning Benchmark (First-Touch Tail Latency)");
+    println!("---------------------------------------------------");
+
+    const CAPACITY: usize = 32 * 1024 * 1024;
+    const MSG_SIZE: usize = 256;
+    const ITERATIONS: usize = CAPACITY / MSG_SIZE;
+
+    let path = "hermes_tuning_bench.dat";
+    let msg = [0xABu8; MSG_SIZE];
+    let configs = [
+        ("default", StorageOptions::new()),
+        ("populate", StorageOptions::new().populate(true)),
+        ("prefault", StorageOptions::new().prefault(true)),
+        (
+            "prefault+mlock",
+            StorageOptions::new().prefault(true).lock(true),
+        ),
+        (
+            "thp",
+            StorageOptions::new().huge_pages(HugePages::Transparent),
+        ),
+        (
+            "thp+prefault",
+            StorageOptions::new()
+                .huge_pages(HugePages::Transparent)
+                .prefault(true),
+        ),
+        ("madv-seq", StorageOptions::new().sequential(true)),
+        ("madv-willneed", StorageOptions::new().will_need(true)),
+    ];
+
+    println!(
+        "  {} writes of {} bytes into a fresh {} MB file",
+        ITERATIONS,
+        MSG_SIZE,
+        CAPACITY / 1024 / 1024
+    );
+    println!(
+        "  {:<16} {:>10} {:>8} {:>8} {:>9} {:>10}",
+        "options", "open", "p50", "p99", "p99.9", "max"
+    );
+
+    for (name, options) in configs {
+        std::fs::remove_file(path).ok();
+
+        let start = Instant::now();
+        let mut storage = match MmapStorage::open_raw(path, CAPACITY, &options) {
+            Ok(storage) => storage,
+            Err(e) => {
+                println!("  {:<16} skipped: {}", name, e);
+                continue;
+            }
+        };
+        let open_duration = start.elapsed();
+
+        let mut latencies = Vec::with_capacity(ITERATIONS);
+        for _ in 0..ITERATIONS {
+            let start = Instant::now();
+            storage.write(&msg);
+            latencies.push(start.elapsed().as_nanos() as u64);
+        }
+        latencies.sort_unstable();
+
+        let percentile = |p: f64| latencies[((latencies.len() - 1) as f64 * p) as usize];
+        println!(
+            "  {:<16} {:>8.2}ms {:>6}ns {:>6}ns {:>7}ns {:>8}ns",
+            name,
+            open_duration.as_secs_f64() * 1000.0,
+            percentile(0.50),
+            percentile(0.99),
+            percentile(0.999),
+            latencies[latencies.len() - 1]
+        );
+    }
+    println!();
+
+    std::fs::remove_file(path).ok();
+}
+
 fn benchmark_protocol() {
     println!("📊 Protocol Benchmark (Binary Encoding)");
     println!("---------------------------------------");
@@ -166,7 +253,7 @@ fn benchmark_protocol() {
     let start = Instant::now();
     for _ in 0..100 {
         let mut decoder = Decoder::new(&encoded_data);
-        while decoder.next().is_some() {}
+        while decoder.next().is_ok() {}
     }
     let decode_duration = start.elapsed();
 
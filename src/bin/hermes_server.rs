@@ -16,23 +16,121 @@
 //! cargo run --release --bin hermes_server
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::io::{self, Read, Write};
 use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::ops::Range;
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
 use std::time::{Duration, Instant};
 
-#[cfg(unix)]
-use std::os::unix::io::AsRawFd;
+use hermes::core::{
+    BusySpin, ByteProducer, ByteRing, Compactor, FsyncPolicy, HugePages, MmapStorage, PayloadRange,
+    RetentionPolicy, RetiredSegment, SegmentedLog, SpinThenYield, StorageOptions, TopicRegistry,
+    TopicRouter, WaitStrategy,
+};
+use hermes::protocol::{
+    encode_topic_ids, frame_topic, is_wildcard_pattern, parse_topic_names, parse_topic_patterns,
+    split_topic, DecodeError, Decoder, Encoder, ErrorCode, MessageType, TopicId, ERROR_CODE_SIZE,
+    HEADER_SIZE, NO_TOPIC,
+};
 
-use hermes::core::MmapStorage;
-use hermes::protocol::{Decoder, MessageType, HEADER_SIZE};
+/// Kapasitas fan-out ring (bytes) - menampung burst frame per iterasi loop
+const FANOUT_RING_SIZE: usize = 4 * 1024 * 1024;
+/// Antrean subscriber terkecil: satu frame maksimum harus selalu muat
+const MIN_QUEUE_BYTES: usize = 256 * 1024;
+/// Prefix sender id pada setiap record fan-out
+const FANOUT_TAG_SIZE: usize = 4;
+/// Interval pengecekan retention policy
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Interval job compaction (hanya memproses segment yang sudah ditutup)
+const COMPACTION_INTERVAL: Duration = Duration::from_secs(10);
+/// File registry topic di dalam direktori storage
+const TOPIC_REGISTRY_FILE: &str = "topics";
+
+/// Idle strategy saat tidak ada I/O (trade-off latency vs CPU)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WaitMode {
+    Spin,  // Busy-poll: latency minimum, 100% CPU
+    Yield, // Spin singkat lalu yield ke scheduler
+}
+
+impl WaitMode {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "spin" => Some(Self::Spin),
+            "yield" => Some(Self::Yield),
+            _ => None,
+        }
+    }
+
+    fn strategy(self) -> Box<dyn WaitStrategy> {
+        match self {
+            Self::Spin => Box::new(BusySpin),
+            // Tanpa `park`: event loop non-blocking tidak punya readiness
+            // notification, jadi tidak ada yang bisa memanggil signal()
+            Self::Yield => Box::new(SpinThenYield::new(1_000)),
+        }
+    }
+}
+
+/// Parse durability mode: `none`, `ms:<N>`, `msgs:<N>` atau `ack`
+fn parse_fsync(s: &str) -> Option<FsyncPolicy> {
+    match s.split_once(':') {
+        None if s == "none" => Some(FsyncPolicy::None),
+        None if s == "ack" => Some(FsyncPolicy::BeforeAck),
+        Some(("ms", n)) => Some(FsyncPolicy::Interval(Duration::from_millis(
+            n.parse().ok()?,
+        ))),
+        Some(("msgs", n)) => Some(FsyncPolicy::EveryMessages(n.parse().ok()?)),
+        _ => None,
+    }
+}
+
+/// Parse key range compaction: `<START>..<END>` (offset di dalam frame)
+fn parse_key_range(s: &str) -> Option<Range<usize>> {
+    let (start, end) = s.split_once("..")?;
+    let range = start.parse().ok()?..end.parse().ok()?;
+    (range.start < range.end).then_some(range)
+}
+
+/// Parse huge page mode: `off`, `thp` atau `explicit`
+fn parse_huge_pages(s: &str) -> Option<HugePages> {
+    match s {
+        "off" => Some(HugePages::Off),
+        "thp" => Some(HugePages::Transparent),
+        "explicit" => Some(HugePages::Explicit),
+        _ => None,
+    }
+}
+
+/// Terapkan hint madvise dari list `sequential,willneed`
+fn parse_madvise(s: &str, options: StorageOptions) -> Option<StorageOptions> {
+    s.split(',').try_fold(options, |options, hint| match hint {
+        "sequential" | "seq" => Some(options.sequential(true)),
+        "willneed" => Some(options.will_need(true)),
+        _ => None,
+    })
+}
 
 /// Server configuration
 struct ServerConfig {
     bind_addr: String,
-    storage_path: String,
-    storage_size_mb: usize,
+    storage_dir: String,
+    segment_size_mb: usize,
+    fsync: FsyncPolicy,
+    retention: RetentionPolicy,
+    compact_key: Option<Range<usize>>,
+    storage_options: StorageOptions,
+    wait_mode: WaitMode,
+    queue_depth: usize,
+    // Kapasitas antrean per subscriber lambat (dibulatkan ke power of 2)
+    queue_bytes: usize,
+    // Batas registry topic (total dan nama baru per koneksi)
+    max_topics: usize,
+    client_topics: usize,
     verbose: bool,
 }
 
@@ -40,8 +138,17 @@ impl Default for ServerConfig {
     fn default() -> Self {
         Self {
             bind_addr: "0.0.0.0:9999".to_string(),
-            storage_path: "hermes_data.dat".to_string(),
-            storage_size_mb: 64,
+            storage_dir: "hermes_data".to_string(),
+            segment_size_mb: 64,
+            fsync: FsyncPolicy::None,
+            retention: RetentionPolicy::default(),
+            compact_key: None,
+            storage_options: StorageOptions::new(),
+            wait_mode: WaitMode::Spin,
+            queue_depth: 8192,
+            queue_bytes: 4 * 1024 * 1024,
+            max_topics: 65536,
+            client_topics: 1024,
             verbose: false,
         }
     }
@@ -57,6 +164,7 @@ struct ServerStats {
     connections_total: AtomicU64,
     connections_active: AtomicU64,
     broadcast_errors: AtomicU64,
+    storage_errors: AtomicU64,
 }
 
 impl ServerStats {
@@ -70,6 +178,7 @@ impl ServerStats {
             connections_total: AtomicU64::new(0),
             connections_active: AtomicU64::new(0),
             broadcast_errors: AtomicU64::new(0),
+            storage_errors: AtomicU64::new(0),
         }
     }
 
@@ -81,6 +190,7 @@ impl ServerStats {
         let bytes_out = self.bytes_sent.load(Ordering::Relaxed);
         let conns = self.connections_active.load(Ordering::Relaxed);
         let errors = self.broadcast_errors.load(Ordering::Relaxed);
+        let storage_errors = self.storage_errors.load(Ordering::Relaxed);
 
         let rate_in = msgs_in as f64 / uptime.as_secs_f64();
         let rate_out = msgs_out as f64 / uptime.as_secs_f64();
@@ -95,7 +205,71 @@ impl ServerStats {
         if errors > 0 {
             println!("   Send errors:   {} ⚠️", errors);
         }
+        if storage_errors > 0 {
+            println!("   Storage errors: {} ⚠️", storage_errors);
+        }
+    }
+}
+
+/// Balasan yang dikirim setelah read buffer selesai di-decode
+enum Reply {
+    Ack(u64),
+    /// Ack berisi topic id untuk `Subscribe` / `Register`
+    TopicIds(u64, Vec<TopicId>),
+    Nack(u64, ErrorCode, &'static str),
+    /// Data rusak dilewati sampai header valid berikutnya
+    Error(DecodeError),
+}
+
+// Alasan Nack / Error (untuk manusia; client membaca `ErrorCode`)
+const TRUNCATED_TOPIC: &str = "FLAG_TOPIC set but payload is shorter than a topic id";
+const UNKNOWN_TOPIC: &str = "topic id is not registered";
+const INVALID_PATTERN: &str = "invalid topic pattern";
+const INVALID_NAME: &str = "invalid topic name";
+const REGISTRY_FAILED: &str = "topic registry write failed";
+const REGISTRY_FULL: &str = "topic registry is full";
+const CLIENT_TOPIC_QUOTA: &str = "too many new topics from this connection";
+const STORAGE_APPEND_FAILED: &str = "storage append failed";
+const STORAGE_SYNC_FAILED: &str = "storage sync failed";
+const SERVER_ONLY: &str = "Ack, Nack and Error frames are sent by the server only";
+const UNSUPPORTED_TYPE: &str = "message type is not supported by the server";
+
+/// Daftarkan nama topic untuk satu frame `Register` / `Subscribe`
+///
+/// Semua nama didaftarkan dengan satu `fsync`. Nama baru mengurangi `quota`
+/// koneksi; kuota habis atau registry penuh ditolak tanpa mengubah registry.
+fn register_topics(
+    id: usize,
+    registry: &mut TopicRegistry,
+    quota: &mut usize,
+    names: &[&str],
+) -> Result<Vec<TopicId>, (ErrorCode, &'static str)> {
+    let mut fresh: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| registry.id(name).is_none())
+        .collect();
+    fresh.sort_unstable();
+    fresh.dedup();
+    if fresh.len() > *quota {
+        return Err((ErrorCode::LimitExceeded, CLIENT_TOPIC_QUOTA));
+    }
+    if fresh.len() > registry.remaining() {
+        return Err((ErrorCode::LimitExceeded, REGISTRY_FULL));
     }
+
+    let ids = registry.register_all(names).map_err(|e| {
+        eprintln!("⚠️ [{}] Topic registry write failed: {}", id, e);
+        (ErrorCode::Storage, REGISTRY_FAILED)
+    })?;
+    *quota -= fresh.len();
+    Ok(ids)
+}
+
+/// Returns `true` jika frame dengan `topic` boleh di-publish
+#[inline(always)]
+fn is_known_topic(registry: &TopicRegistry, topic: TopicId) -> bool {
+    topic == NO_TOPIC || registry.name(topic).is_some()
 }
 
 /// Client connection with role
@@ -112,14 +286,31 @@ struct ClientHandler {
     addr: SocketAddr,
     role: ClientRole,
     read_buffer: Vec<u8>,
+    // Sisa frame yang sedang dikirim (partial write)
     write_buffer: Vec<u8>,
+    // Frame yang menunggu giliran saat subscriber lambat; dialokasikan saat
+    // pertama dibutuhkan lalu dipakai ulang (dibatasi bytes + jumlah frame)
+    pending: Option<ByteRing>,
+    pending_frames: usize,
+    queue_depth: usize,
+    queue_bytes: usize,
+    // Sisa topic baru yang boleh didaftarkan koneksi ini
+    topic_quota: usize,
+    // Buffer encode frame Ack (FsyncPolicy::BeforeAck)
+    ack_encoder: Encoder,
+    // Terima semua frame (client lama / Subscribe kosong); selain itu lewat TopicRouter
+    all_topics: bool,
     read_pos: usize,
     messages_sent: u64,
     messages_received: u64,
+    // Frame yang di-Nack (hanya yang pertama di-log)
+    rejected: u64,
+    // Sedang melewati data rusak; Error hanya dikirim sekali per rentang
+    resyncing: bool,
 }
 
 impl ClientHandler {
-    fn new(stream: TcpStream, addr: SocketAddr) -> io::Result<Self> {
+    fn new(stream: TcpStream, addr: SocketAddr, config: &ServerConfig) -> io::Result<Self> {
         // CRITICAL: TCP_NODELAY untuk low latency
         stream.set_nodelay(true)?;
         stream.set_nonblocking(true)?;
@@ -155,9 +346,18 @@ impl ClientHandler {
             role: ClientRole::Unknown,
             read_buffer: vec![0u8; 128 * 1024], // 128KB read buffer
             write_buffer: Vec::with_capacity(128 * 1024),
+            pending: None,
+            pending_frames: 0,
+            queue_depth: config.queue_depth,
+            queue_bytes: config.queue_bytes,
+            topic_quota: config.client_topics,
+            ack_encoder: Encoder::new(HEADER_SIZE),
+            all_topics: true,
             read_pos: 0,
             messages_sent: 0,
             messages_received: 0,
+            rejected: 0,
+            resyncing: false,
         })
     }
 
@@ -165,8 +365,9 @@ impl ClientHandler {
     #[inline(always)]
     fn try_read(&mut self) -> io::Result<usize> {
         if self.read_pos >= self.read_buffer.len() {
-            // Buffer full - should not happen with proper consumption
-            return Ok(0);
+            // Buffer penuh (fan-out ring penuh): tunggu process_messages
+            // mengosongkannya, data baru tertahan di socket (backpressure)
+            return Err(io::ErrorKind::WouldBlock.into());
         }
 
         match self.stream.read(&mut self.read_buffer[self.read_pos..]) {
@@ -180,59 +381,211 @@ impl ClientHandler {
         }
     }
 
-    /// Process received messages, returns list of messages to broadcast
+    /// Process received messages, queue Publish frames ke fan-out ring
+    ///
+    /// Setiap frame ditulis langsung ke ring sebagai `[sender_id: u32][frame]`
+    /// tanpa alokasi heap. Jika ring penuh, sisa data tetap di read buffer
+    /// dan diproses pada iterasi berikutnya (backpressure, bukan drop).
+    ///
+    /// Dengan `FsyncPolicy::BeforeAck`, log di-sync lalu publisher menerima
+    /// satu Ack (sequence frame tersimpan terakhir) per batch. Frame yang gagal
+    /// disimpan tidak di-fan-out dan di-Nack sebelum Ack tersebut.
+    ///
+    /// `Subscribe` / `Register` dibalas Ack berisi topic id dari `registry`;
+    /// pattern `Subscribe` / `Unsubscribe` diteruskan ke `router`. Frame yang
+    /// ditolak dibalas `Nack`; data yang tidak bisa di-decode dilewati sampai
+    /// header valid berikutnya (`Decoder::resync`) dan dilaporkan dengan `Error`.
     fn process_messages(
         &mut self,
-        storage: &mut MmapStorage,
+        id: usize,
+        storage: &mut SegmentedLog,
+        registry: &mut TopicRegistry,
+        router: &mut TopicRouter<usize>,
+        fanout: &mut ByteProducer<'_>,
         stats: &ServerStats,
-    ) -> Vec<(usize, Vec<u8>)> {
-        let mut broadcasts = Vec::with_capacity(16); // Pre-allocate for typical batch
-
+    ) {
         if self.read_pos < HEADER_SIZE {
-            return broadcasts;
+            return;
         }
 
         let mut decoder = Decoder::new(&self.read_buffer[..self.read_pos]);
         let mut consumed = 0;
         let mut msg_count = 0u64;
         let mut bytes_count = 0u64;
+        // Sequence Publish terakhir yang tersimpan dan belum di-ack
+        let mut ack_sequence = None;
+        let ack_mode = storage.fsync_policy() == FsyncPolicy::BeforeAck;
+        // Dikirim setelah decode selesai (read buffer masih dipinjam decoder)
+        let mut replies: Vec<Reply> = Vec::new();
 
-        while let Some((header, payload)) = decoder.next() {
+        loop {
+            let (header, payload) = match decoder.next() {
+                Ok(frame) => frame,
+                Err(DecodeError::Incomplete) => break,
+                Err(e) => {
+                    // Batas frame tidak bisa dipercaya: cari header valid berikutnya
+                    consumed += decoder.resync();
+                    if !self.resyncing {
+                        self.resyncing = true;
+                        replies.push(Reply::Error(e));
+                    }
+                    continue;
+                }
+            };
+            self.resyncing = false;
             let msg_size = HEADER_SIZE + payload.len();
+            let sequence = header.sequence;
 
-            // Extract full message BEFORE updating consumed
+            // Full message tetap di read buffer - tidak ada copy ke Vec
             let msg_start = consumed;
             let msg_end = consumed + msg_size;
-            let full_msg = self.read_buffer[msg_start..msg_end].to_vec();
-
-            consumed = msg_end;
-
-            msg_count += 1;
-            bytes_count += msg_size as u64;
-            self.messages_received += 1;
+            let full_msg = &self.read_buffer[msg_start..msg_end];
 
             match MessageType::from_u8(header.msg_type) {
+                Some(MessageType::Publish) if split_topic(&header, payload).is_none() => {
+                    replies.push(Reply::Nack(sequence, ErrorCode::Malformed, TRUNCATED_TOPIC));
+                }
+                Some(MessageType::Publish) if !is_known_topic(registry, frame_topic(full_msg)) => {
+                    replies.push(Reply::Nack(
+                        sequence,
+                        ErrorCode::UnknownTopic,
+                        UNKNOWN_TOPIC,
+                    ));
+                }
                 Some(MessageType::Publish) => {
+                    // Claim dulu: jika ring penuh, berhenti tanpa consume frame ini
+                    let Some(mut slot) = fanout.claim(FANOUT_TAG_SIZE + msg_size) else {
+                        break;
+                    };
+
                     // This client is a Publisher
                     if self.role == ClientRole::Unknown {
                         self.role = ClientRole::Publisher;
                     }
 
-                    // Store to mmap for persistence
-                    storage.write(&full_msg);
+                    // Store to segmented log for persistence (rolls when segment full)
+                    let stored = match storage.append(full_msg) {
+                        Ok(_) => {
+                            ack_sequence = Some(sequence);
+                            true
+                        }
+                        Err(e) => {
+                            if ack_mode {
+                                replies.push(Reply::Nack(
+                                    sequence,
+                                    ErrorCode::Storage,
+                                    STORAGE_APPEND_FAILED,
+                                ));
+                            }
+                            if stats.storage_errors.fetch_add(1, Ordering::Relaxed) == 0 {
+                                eprintln!("⚠️ [{}] Storage append failed: {}", id, e);
+                            }
+                            false
+                        }
+                    };
 
-                    // Queue for broadcast (include message size for stats)
-                    broadcasts.push((msg_size, full_msg));
-                }
-                Some(MessageType::Subscribe) => {
-                    // This client wants to receive messages
-                    self.role = ClientRole::Subscriber;
+                    // Queue for broadcast; frame yang di-Nack tidak dikirim (claim dibatalkan)
+                    if stored || !ack_mode {
+                        slot[..FANOUT_TAG_SIZE].copy_from_slice(&(id as u32).to_le_bytes());
+                        slot[FANOUT_TAG_SIZE..].copy_from_slice(full_msg);
+                        slot.commit();
+                    }
                 }
+                Some(MessageType::Subscribe) => match parse_topic_patterns(payload) {
+                    // Subscribe kosong (client lama): semua frame, tidak dibalas
+                    Some(patterns) if patterns.is_empty() => {
+                        // This client wants to receive messages
+                        self.role = ClientRole::Subscriber;
+                        self.all_topics = true;
+                        router.remove_subscriber(id);
+                    }
+                    Some(patterns) => {
+                        // Nama exact didaftarkan dulu; ditolak = subscription tidak berubah
+                        let names: Vec<&str> = patterns
+                            .iter()
+                            .copied()
+                            .filter(|pattern| !is_wildcard_pattern(pattern))
+                            .collect();
+                        match register_topics(id, registry, &mut self.topic_quota, &names) {
+                            Ok(registered) => {
+                                self.role = ClientRole::Subscriber;
+                                self.all_topics = false;
+                                let mut registered = registered.into_iter();
+                                let mut ids = Vec::with_capacity(patterns.len());
+                                for pattern in patterns {
+                                    // Pattern sudah divalidasi parse_topic_patterns
+                                    router.subscribe(pattern, id).ok();
+                                    ids.push(if is_wildcard_pattern(pattern) {
+                                        NO_TOPIC
+                                    } else {
+                                        registered.next().unwrap_or(NO_TOPIC)
+                                    });
+                                }
+                                replies.push(Reply::TopicIds(sequence, ids));
+                            }
+                            Err((code, reason)) => {
+                                replies.push(Reply::Nack(sequence, code, reason))
+                            }
+                        }
+                    }
+                    None => replies.push(Reply::Nack(
+                        sequence,
+                        ErrorCode::InvalidTopic,
+                        INVALID_PATTERN,
+                    )),
+                },
+                Some(MessageType::Unsubscribe) => match parse_topic_patterns(payload) {
+                    // Unsubscribe kosong: berhenti menerima semua frame
+                    Some(patterns) if patterns.is_empty() => {
+                        self.all_topics = false;
+                        router.remove_subscriber(id);
+                        replies.push(Reply::Ack(sequence));
+                    }
+                    Some(patterns) => {
+                        for pattern in patterns {
+                            router.unsubscribe(pattern, id);
+                        }
+                        replies.push(Reply::Ack(sequence));
+                    }
+                    None => replies.push(Reply::Nack(
+                        sequence,
+                        ErrorCode::InvalidTopic,
+                        INVALID_PATTERN,
+                    )),
+                },
+                Some(MessageType::Register) => match parse_topic_names(payload) {
+                    Some(names) => {
+                        match register_topics(id, registry, &mut self.topic_quota, &names) {
+                            Ok(ids) => replies.push(Reply::TopicIds(sequence, ids)),
+                            Err((code, reason)) => {
+                                replies.push(Reply::Nack(sequence, code, reason))
+                            }
+                        }
+                    }
+                    None => {
+                        replies.push(Reply::Nack(sequence, ErrorCode::InvalidTopic, INVALID_NAME))
+                    }
+                },
                 Some(MessageType::Heartbeat) => {
                     // Just acknowledge - client is alive
                 }
-                _ => {}
+                Some(MessageType::Ack | MessageType::Nack | MessageType::Error) => {
+                    replies.push(Reply::Nack(sequence, ErrorCode::Unauthorized, SERVER_ONLY));
+                }
+                Some(MessageType::Batch) | None => {
+                    replies.push(Reply::Nack(
+                        sequence,
+                        ErrorCode::UnsupportedType,
+                        UNSUPPORTED_TYPE,
+                    ));
+                }
             }
+
+            consumed = msg_end;
+
+            msg_count += 1;
+            bytes_count += msg_size as u64;
+            self.messages_received += 1;
         }
 
         // Batch update stats (reduces atomic contention)
@@ -245,6 +598,48 @@ impl ClientHandler {
                 .fetch_add(bytes_count, Ordering::Relaxed);
         }
 
+        for reply in replies {
+            match reply {
+                Reply::Ack(sequence) => self.send_ack(sequence),
+                Reply::TopicIds(sequence, ids) => self.send_topic_ids(sequence, &ids),
+                Reply::Nack(sequence, code, reason) => {
+                    if self.rejected == 0 {
+                        eprintln!(
+                            "⚠️ [{}] Rejected frame {}: {:?} ({})",
+                            id, sequence, code, reason
+                        );
+                    }
+                    self.rejected += 1;
+                    self.send_error(MessageType::Nack, sequence, code, reason);
+                }
+                Reply::Error(error) => {
+                    let reason = error.to_string();
+                    eprintln!("⚠️ [{}] Undecodable data ({}), resyncing", id, reason);
+                    let code = error.code().unwrap_or(ErrorCode::Malformed);
+                    self.send_error(MessageType::Error, 0, code, &reason);
+                }
+            }
+        }
+
+        // Ack setelah frame tersimpan ada di disk, sesudah Nack untuk append
+        // yang gagal (Ack bersifat kumulatif sampai `sequence`)
+        if let (true, Some(sequence)) = (ack_mode, ack_sequence) {
+            match storage.flush() {
+                Ok(()) => self.send_ack(sequence),
+                Err(e) => {
+                    self.send_error(
+                        MessageType::Nack,
+                        sequence,
+                        ErrorCode::Storage,
+                        STORAGE_SYNC_FAILED,
+                    );
+                    if stats.storage_errors.fetch_add(1, Ordering::Relaxed) == 0 {
+                        eprintln!("⚠️ [{}] Storage sync failed: {}", id, e);
+                    }
+                }
+            }
+        }
+
         // Shift remaining data to front of buffer
         if consumed > 0 {
             if consumed < self.read_pos {
@@ -254,8 +649,33 @@ impl ClientHandler {
                 self.read_pos = 0;
             }
         }
+    }
+
+    /// Kirim Ack untuk frame sampai `sequence`
+    fn send_ack(&mut self, sequence: u64) {
+        let mut frame = [0u8; HEADER_SIZE];
+        if let Some(encoded) = self.ack_encoder.encode(MessageType::Ack, sequence, &[]) {
+            frame.copy_from_slice(encoded);
+        }
+        self.ack_encoder.reset();
+        self.send(&frame).ok();
+    }
 
-        broadcasts
+    /// Kirim Ack berisi topic id untuk `Subscribe` / `Register` (jalur jarang)
+    fn send_topic_ids(&mut self, sequence: u64, ids: &[TopicId]) {
+        let payload = encode_topic_ids(ids);
+        let mut encoder = Encoder::new(HEADER_SIZE + payload.len());
+        if let Some(frame) = encoder.encode(MessageType::Ack, sequence, &payload) {
+            self.send(frame).ok();
+        }
+    }
+
+    /// Kirim `Nack` / `Error` (jalur jarang)
+    fn send_error(&mut self, msg_type: MessageType, sequence: u64, code: ErrorCode, reason: &str) {
+        let mut encoder = Encoder::new(HEADER_SIZE + ERROR_CODE_SIZE + reason.len());
+        if let Some(frame) = encoder.encode_error(msg_type, sequence, code, reason) {
+            self.send(frame).ok();
+        }
     }
 
     /// Send data to client (with buffering for WouldBlock)
@@ -264,20 +684,17 @@ impl ClientHandler {
         // First try to flush any pending data
         self.flush_pending()?;
 
-        // If we still have pending data, buffer this too
+        // If we still have pending data, queue this frame behind it
         if !self.write_buffer.is_empty() {
-            if self.write_buffer.len() + data.len() > 1024 * 1024 {
-                // Buffer too large - drop message
-                return Ok(false);
-            }
-            self.write_buffer.extend_from_slice(data);
-            return Ok(true);
+            return Ok(self.enqueue(data));
         }
 
         // Try direct send
-        match self.stream.write_all(data) {
-            Ok(_) => {
+        match self.stream.write(data) {
+            Ok(n) => {
                 self.messages_sent += 1;
+                // Partial write: sisa frame harus terkirim utuh sebelum frame lain
+                self.write_buffer.extend_from_slice(&data[n..]);
                 Ok(true)
             }
             Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
@@ -289,22 +706,49 @@ impl ClientHandler {
         }
     }
 
-    /// Flush pending write buffer
+    /// Antrekan frame di belakang data yang belum terkirim
+    ///
+    /// Returns `false` (frame di-drop) jika subscriber terlalu lambat: antrean
+    /// sudah `queue_depth` frame atau tidak ada ruang dalam `queue_bytes`.
+    fn enqueue(&mut self, data: &[u8]) -> bool {
+        if self.pending_frames >= self.queue_depth {
+            return false;
+        }
+        let bytes = self.queue_bytes;
+        let ring = self.pending.get_or_insert_with(|| ByteRing::new(bytes));
+        let (mut producer, _) = ring.split();
+        if !producer.push(data) {
+            return false;
+        }
+        self.pending_frames += 1;
+        true
+    }
+
+    /// Flush pending write buffer, lalu frame yang mengantri
     #[inline(always)]
     fn flush_pending(&mut self) -> io::Result<()> {
-        if self.write_buffer.is_empty() {
-            return Ok(());
-        }
+        loop {
+            if self.write_buffer.is_empty() {
+                let Some(ring) = self.pending.as_mut() else {
+                    return Ok(());
+                };
+                let (_, mut consumer) = ring.split();
+                match consumer.read() {
+                    Some(frame) => self.write_buffer.extend_from_slice(&frame),
+                    None => return Ok(()),
+                }
+                self.pending_frames -= 1;
+            }
 
-        match self.stream.write(&self.write_buffer) {
-            Ok(n) => {
-                if n > 0 {
+            match self.stream.write(&self.write_buffer) {
+                Ok(n) if n == self.write_buffer.len() => self.write_buffer.clear(),
+                Ok(n) => {
                     self.write_buffer.drain(..n);
+                    return Ok(());
                 }
-                Ok(())
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) => return Err(e),
             }
-            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
-            Err(e) => Err(e),
         }
     }
 
@@ -321,26 +765,189 @@ impl ClientHandler {
     }
 }
 
+/// Pindahkan frame dari file `MmapStorage` server lama ke log (sekali jalan)
+///
+/// Server lama menyimpan frame di `<storage>.dat`. File hanya di-import ke log
+/// yang masih kosong lalu di-rename ke `.dat.imported`; frame lama tidak
+/// dicampur ke log yang sudah berisi data.
+fn import_legacy_storage(dir: &str, storage: &mut SegmentedLog) -> io::Result<()> {
+    let legacy = format!("{}.dat", dir.trim_end_matches('/'));
+    if !Path::new(&legacy).is_file() {
+        return Ok(());
+    }
+    if storage.next_offset() != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "legacy storage file {} exists next to non-empty log {}/; \
+                 export it with `hermes-storage export` and move it away",
+                legacy, dir
+            ),
+        ));
+    }
+
+    let old = MmapStorage::open_read_only(&legacy)?;
+    let mut record = Vec::new();
+    for msg in old.messages(old.read_pos()) {
+        record.clear();
+        record.extend_from_slice(msg.header.as_bytes());
+        record.extend_from_slice(&msg.payload);
+        storage.append(&record)?;
+    }
+    // Log harus di disk sebelum file lama disingkirkan
+    storage.flush()?;
+
+    let imported = format!("{}.imported", legacy);
+    fs::rename(&legacy, &imported)?;
+    println!(
+        "📦 Imported {} frames from {} (renamed to {})",
+        storage.next_offset(),
+        legacy,
+        imported
+    );
+    Ok(())
+}
+
 /// Main server loop
 fn run_server(config: ServerConfig) -> io::Result<()> {
     println!("🚀 HERMES SERVER v2 - Fixed Broadcast");
     println!("=====================================\n");
 
     // Initialize storage
-    let storage_size = config.storage_size_mb * 1024 * 1024;
-    let mut storage = MmapStorage::open(&config.storage_path, storage_size)?;
+    if Path::new(&config.storage_dir).is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "--storage {} is a file (MmapStorage from an older server); \
+                 pass a log directory, `<DIR>.dat` is imported into it on first start",
+                config.storage_dir
+            ),
+        ));
+    }
+    let segment_size = config.segment_size_mb * 1024 * 1024;
+    let mut storage =
+        SegmentedLog::open_with(&config.storage_dir, segment_size, config.storage_options)?;
+    if let Some(pos) = storage.recovery().truncated_at {
+        println!(
+            "🩹 Storage: discarded torn tail of the active segment at byte {}",
+            pos
+        );
+    }
+    for (base, pos) in &storage.recovery().corrupt {
+        eprintln!(
+            "⚠️ Storage: segment {} is corrupt at byte {}; later records in it are unreadable \
+             (see `hermes-storage verify`)",
+            base, pos
+        );
+    }
+    if !storage.recovery().rebuilt_indexes.is_empty() {
+        println!(
+            "🩹 Storage: rebuilt index of segments {:?}",
+            storage.recovery().rebuilt_indexes
+        );
+    }
+    import_legacy_storage(&config.storage_dir, &mut storage)?;
+    storage.set_fsync_policy(config.fsync);
+    println!(
+        "💾 Storage: {}/ ({} MB segments, next offset {})",
+        config.storage_dir,
+        config.segment_size_mb,
+        storage.next_offset()
+    );
+    let registry_path = Path::new(&config.storage_dir).join(TOPIC_REGISTRY_FILE);
+    let mut registry = TopicRegistry::open(&registry_path)?;
+    registry.set_limit(config.max_topics);
+    let mut router = TopicRouter::new();
     println!(
-        "💾 Storage: {} ({} MB)",
-        config.storage_path, config.storage_size_mb
+        "🏷️  Topics: {} registered ({}), max {} / {} per connection",
+        registry.len(),
+        registry_path.display(),
+        config.max_topics,
+        config.client_topics
     );
+    println!("🔒 Durability: {:?}", config.fsync);
+    if config.storage_options != StorageOptions::new() {
+        println!("⚡ Mmap tuning: {:?}", config.storage_options);
+    }
+    if !config.retention.is_unbounded() {
+        println!("🧹 Retention: {:?}", config.retention);
+    }
+
+    // Unmap + hapus segment lama di thread terpisah (tidak menahan event loop)
+    let (retired_tx, retired_rx) = mpsc::channel::<RetiredSegment>();
+    std::thread::Builder::new()
+        .name("hermes-retention".to_string())
+        .spawn(move || {
+            for segment in retired_rx {
+                let (base, records) = (segment.base_offset(), segment.records());
+                match segment.remove() {
+                    Ok(()) => println!("🧹 Removed segment {} ({} records)", base, records),
+                    Err(e) => eprintln!("⚠️ Failed to remove segment {}: {}", base, e),
+                }
+            }
+        })?;
+    let mut last_retention_check = Instant::now();
+    // Segment yang index-nya gagal ditulis dan sudah dilaporkan
+    let mut stale_indexes = HashSet::new();
+
+    // Offset pertama yang belum dikompaksi: retention tidak melepas segment
+    // di atasnya (u64::MAX = compaction tidak aktif)
+    let compacted = Arc::new(AtomicU64::new(u64::MAX));
+
+    // Compaction last-value-per-key membaca segment tertutup secara read-only
+    if let Some(range) = config.compact_key.clone() {
+        println!(
+            "🗜️  Compaction: key payload bytes {:?} every {:?}",
+            range, COMPACTION_INTERVAL
+        );
+        let mut compactor = Compactor::open(&config.storage_dir, PayloadRange(range))?;
+        compacted.store(compactor.next_offset(), Ordering::Release);
+        let compacted = Arc::clone(&compacted);
+        std::thread::Builder::new()
+            .name("hermes-compaction".to_string())
+            .spawn(move || loop {
+                let missed = compactor.missed();
+                match compactor.run_once() {
+                    Ok(0) => {}
+                    Ok(n) => println!(
+                        "🗜️  Compacted {} records -> {} keys (up to offset {})",
+                        n,
+                        compactor.key_count(),
+                        compactor.next_offset()
+                    ),
+                    Err(e) => eprintln!("⚠️ Compaction failed: {}", e),
+                }
+                if compactor.missed() > missed {
+                    eprintln!(
+                        "⚠️ Compaction skipped {} records whose segments were removed first",
+                        compactor.missed() - missed
+                    );
+                }
+                compacted.store(compactor.next_offset(), Ordering::Release);
+                std::thread::sleep(COMPACTION_INTERVAL);
+            })?;
+    }
 
     // Bind listener with reuse
     let listener = TcpListener::bind(&config.bind_addr)?;
     listener.set_nonblocking(true)?;
     println!("🔌 Listening on {}", config.bind_addr);
     println!("⚡ TCP_NODELAY: ENABLED");
+    println!("⏳ Wait strategy: {:?}", config.wait_mode);
+    println!(
+        "📬 Subscriber queue: {} frames / {} KB",
+        config.queue_depth,
+        config.queue_bytes / 1024
+    );
     println!("\n📡 Waiting for connections...\n");
 
+    // Fan-out ring: frame Publish lewat sini tanpa alokasi per pesan
+    let mut fanout = ByteRing::new(FANOUT_RING_SIZE);
+    let (mut fanout_tx, mut fanout_rx) = fanout.split();
+
+    let wait = config.wait_mode.strategy();
+    let mut idle_attempts = 0u32;
+
     let stats = ServerStats::new();
     let start_time = Instant::now();
     let mut last_stats_print = Instant::now();
@@ -353,13 +960,15 @@ fn run_server(config: ServerConfig) -> io::Result<()> {
 
     loop {
         let _loop_start = Instant::now();
+        let mut did_work = false;
 
         // === PHASE 1: Accept new connections ===
         loop {
             match listener.accept() {
                 Ok((stream, addr)) => {
-                    match ClientHandler::new(stream, addr) {
+                    match ClientHandler::new(stream, addr, &config) {
                         Ok(handler) => {
+                            did_work = true;
                             let id = next_client_id;
                             next_client_id += 1;
 
@@ -386,10 +995,24 @@ fn run_server(config: ServerConfig) -> io::Result<()> {
         }
 
         // === PHASE 2: Read from all clients ===
-        let mut all_broadcasts: Vec<(usize, usize, Vec<u8>)> = Vec::new(); // (sender_id, msg_size, data)
         let mut disconnected: Vec<usize> = Vec::new();
 
         for (&id, client) in clients.iter_mut() {
+            // Frame yang tertahan karena fan-out ring penuh diproses dulu,
+            // walaupun client tidak mengirim data baru
+            if client.read_pos >= HEADER_SIZE {
+                let backlog = client.read_pos;
+                client.process_messages(
+                    id,
+                    &mut storage,
+                    &mut registry,
+                    &mut router,
+                    &mut fanout_tx,
+                    &stats,
+                );
+                did_work |= client.read_pos != backlog;
+            }
+
             // Try to read
             match client.try_read() {
                 Ok(0) => {
@@ -397,15 +1020,20 @@ fn run_server(config: ServerConfig) -> io::Result<()> {
                     // Only mark as disconnected if we get explicit EOF
                 }
                 Ok(n) => {
+                    did_work = true;
                     if config.verbose {
                         println!("   [{}] Read {} bytes", id, n);
                     }
 
                     // Process messages
-                    let msgs = client.process_messages(&mut storage, &stats);
-                    for (msg_size, msg_data) in msgs {
-                        all_broadcasts.push((id, msg_size, msg_data));
-                    }
+                    client.process_messages(
+                        id,
+                        &mut storage,
+                        &mut registry,
+                        &mut router,
+                        &mut fanout_tx,
+                        &stats,
+                    );
                 }
                 Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
                     // Normal for non-blocking - no data available
@@ -435,10 +1063,20 @@ fn run_server(config: ServerConfig) -> io::Result<()> {
         let mut dropped_count = 0u64;
         let mut error_count = 0u64;
 
-        for (_sender_id, _msg_size, msg_data) in &all_broadcasts {
+        let mut had_broadcasts = false;
+
+        while let Some(record) = fanout_rx.read() {
+            had_broadcasts = true;
+            let (tag, msg_data) = record.split_at(FANOUT_TAG_SIZE);
+            let sender_id = u32::from_le_bytes([tag[0], tag[1], tag[2], tag[3]]) as usize;
+            // Subscriber dengan pattern yang cocok (terurut)
+            let route = router.route(frame_topic(msg_data), &registry);
+
             for (&client_id, client) in clients.iter_mut() {
-                // Skip sender - don't echo back
-                if client_id == *_sender_id {
+                // Skip sender - don't echo back; hanya topic yang di-subscribe
+                if client_id == sender_id
+                    || !(client.all_topics || route.binary_search(&client_id).is_ok())
+                {
                     continue;
                 }
 
@@ -492,10 +1130,35 @@ fn run_server(config: ServerConfig) -> io::Result<()> {
                 );
                 stats.connections_active.fetch_sub(1, Ordering::Relaxed);
                 subscriber_ids.retain(|&x| x != id);
+                router.remove_subscriber(id);
+            }
+        }
+
+        // === PHASE 6: Periodic storage flush (FsyncPolicy::Interval) ===
+        if let Err(e) = storage.tick() {
+            if stats.storage_errors.fetch_add(1, Ordering::Relaxed) == 0 {
+                eprintln!("⚠️ Storage flush failed: {}", e);
+            }
+        }
+
+        // === PHASE 7: Retention (hanya melepas segment; hapus file di thread lain) ===
+        if last_retention_check.elapsed() > RETENTION_CHECK_INTERVAL {
+            let before = compacted.load(Ordering::Acquire);
+            for segment in storage.detach_expired_before(&config.retention, before) {
+                retired_tx.send(segment).ok();
+            }
+            for (base, e) in storage.stale_indexes() {
+                if stale_indexes.insert(base) {
+                    eprintln!(
+                        "⚠️ Index write failed for segment {}: {} (rebuilt on next start)",
+                        base, e
+                    );
+                }
             }
+            last_retention_check = Instant::now();
         }
 
-        // === PHASE 6: Print stats periodically ===
+        // === PHASE 8: Print stats periodically ===
         if last_stats_print.elapsed() > Duration::from_secs(5) {
             stats.print_stats(start_time.elapsed());
             last_stats_print = Instant::now();
@@ -503,12 +1166,16 @@ fn run_server(config: ServerConfig) -> io::Result<()> {
 
         // === Adaptive sleep for CPU efficiency ===
         // ULTRA LOW LATENCY MODE: No sleep when active
-        // Only yield briefly when completely idle
-        if all_broadcasts.is_empty() && clients.is_empty() {
+        if did_work || had_broadcasts {
+            idle_attempts = 0;
+        } else if clients.is_empty() {
             // No clients, no work - sleep to save CPU
             std::thread::sleep(Duration::from_micros(50));
+        } else {
+            // Clients connected but idle: wait strategy menentukan spin/yield
+            wait.idle(idle_attempts);
+            idle_attempts = idle_attempts.saturating_add(1);
         }
-        // When processing messages or clients connected: NO SLEEP - busy poll for minimum latency
     }
 }
 
@@ -519,23 +1186,97 @@ fn parse_args() -> ServerConfig {
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
-            "--bind" | "-b" => {
-                if i + 1 < args.len() {
-                    config.bind_addr = args[i + 1].clone();
-                    i += 1;
-                }
+            "--bind" | "-b" if i + 1 < args.len() => {
+                config.bind_addr = args[i + 1].clone();
+                i += 1;
             }
-            "--storage" | "-s" => {
-                if i + 1 < args.len() {
-                    config.storage_path = args[i + 1].clone();
-                    i += 1;
-                }
+            "--storage" | "-s" if i + 1 < args.len() => {
+                config.storage_dir = args[i + 1].clone();
+                i += 1;
+            }
+            "--segment-size" | "--size" if i + 1 < args.len() => {
+                config.segment_size_mb = args[i + 1].parse().unwrap_or(64).max(1);
+                i += 1;
+            }
+            "--fsync" if i + 1 < args.len() => {
+                config.fsync = parse_fsync(&args[i + 1]).unwrap_or_else(|| {
+                    eprintln!("Invalid --fsync mode: {}", args[i + 1]);
+                    std::process::exit(2);
+                });
+                i += 1;
+            }
+            "--retain-age" if i + 1 < args.len() => {
+                config.retention.max_age = args[i + 1].parse().ok().map(Duration::from_secs);
+                i += 1;
             }
-            "--size" => {
-                if i + 1 < args.len() {
-                    config.storage_size_mb = args[i + 1].parse().unwrap_or(64);
-                    i += 1;
+            "--retain-bytes" if i + 1 < args.len() => {
+                config.retention.max_bytes =
+                    args[i + 1].parse::<u64>().ok().map(|mb| mb * 1024 * 1024);
+                i += 1;
+            }
+            "--retain-count" if i + 1 < args.len() => {
+                config.retention.max_messages = args[i + 1].parse().ok();
+                i += 1;
+            }
+            "--compact-key" if i + 1 < args.len() => {
+                config.compact_key = parse_key_range(&args[i + 1]);
+                if config.compact_key.is_none() {
+                    eprintln!("Invalid --compact-key range: {}", args[i + 1]);
+                    std::process::exit(2);
                 }
+                i += 1;
+            }
+            "--prefault" => {
+                config.storage_options = config.storage_options.prefault(true);
+            }
+            "--populate" => {
+                config.storage_options = config.storage_options.populate(true);
+            }
+            "--huge-pages" if i + 1 < args.len() => {
+                let mode = parse_huge_pages(&args[i + 1]).unwrap_or_else(|| {
+                    eprintln!("Invalid --huge-pages mode: {}", args[i + 1]);
+                    std::process::exit(2);
+                });
+                config.storage_options = config.storage_options.huge_pages(mode);
+                i += 1;
+            }
+            "--madvise" if i + 1 < args.len() => {
+                config.storage_options = parse_madvise(&args[i + 1], config.storage_options)
+                    .unwrap_or_else(|| {
+                        eprintln!("Invalid --madvise hints: {}", args[i + 1]);
+                        std::process::exit(2);
+                    });
+                i += 1;
+            }
+            "--mlock" => {
+                config.storage_options = config.storage_options.lock(true);
+            }
+            "--wait" if i + 1 < args.len() => {
+                config.wait_mode = WaitMode::parse(&args[i + 1]).unwrap_or_else(|| {
+                    eprintln!("Invalid --wait mode: {}", args[i + 1]);
+                    std::process::exit(2);
+                });
+                i += 1;
+            }
+            "--queue-depth" if i + 1 < args.len() => {
+                config.queue_depth = args[i + 1].parse().unwrap_or(8192).max(1);
+                i += 1;
+            }
+            "--max-topics" if i + 1 < args.len() => {
+                config.max_topics = args[i + 1].parse().unwrap_or(65536);
+                i += 1;
+            }
+            "--client-topics" if i + 1 < args.len() => {
+                config.client_topics = args[i + 1].parse().unwrap_or(1024);
+                i += 1;
+            }
+            "--queue-bytes" if i + 1 < args.len() => {
+                let mb: usize = args[i + 1].parse().unwrap_or(4);
+                config.queue_bytes = mb
+                    .saturating_mul(1024 * 1024)
+                    .clamp(MIN_QUEUE_BYTES, 1 << 30)
+                    .next_power_of_two();
+                i += 1;
             }
             "--verbose" | "-v" => {
                 config.verbose = true;
@@ -544,11 +1285,41 @@ fn parse_args() -> ServerConfig {
                 println!("Hermes Server v2 - Ultra Low-Latency Message Broker\n");
                 println!("Usage: hermes_server [OPTIONS]\n");
                 println!("Options:");
-                println!("  -b, --bind <ADDR>     Bind address (default: 0.0.0.0:9999)");
-                println!("  -s, --storage <PATH>  Storage file path (default: hermes_data.dat)");
-                println!("      --size <MB>       Storage size in MB (default: 64)");
-                println!("  -v, --verbose         Verbose output");
-                println!("  -h, --help            Show this help");
+                println!("  -b, --bind <ADDR>         Bind address (default: 0.0.0.0:9999)");
+                println!("  -s, --storage <DIR>       Log directory (default: hermes_data)");
+                println!(
+                    "                            <DIR>.dat from an older server is imported once"
+                );
+                println!("      --segment-size <MB>   Log segment size in MB (default: 64)");
+                println!("      --fsync <MODE>        Durability: none|ms:<N>|msgs:<N>|ack (default: none)");
+                println!(
+                    "      --retain-age <SECS>   Delete segments older than SECS (default: keep)"
+                );
+                println!(
+                    "      --retain-bytes <MB>   Keep about MB of log on disk (default: keep)"
+                );
+                println!("      --retain-count <N>    Keep about N messages (default: keep)");
+                println!(
+                    "      --compact-key <A..B>  Compact log by payload bytes A..B (after the topic id), e.g. 0..32"
+                );
+                println!("      --prefault            Fault in every page of the active segment on open/roll");
+                println!("      --populate            Map segments with MAP_POPULATE");
+                println!(
+                    "      --huge-pages <MODE>   off|thp|explicit (explicit: storage on hugetlbfs)"
+                );
+                println!("      --madvise <HINTS>     Comma list of sequential,willneed");
+                println!("      --mlock               Lock the active segment in memory");
+                println!("      --wait <MODE>         Idle strategy: spin|yield (default: spin)");
+                println!("      --queue-depth <N>     Pending frames per slow subscriber (default: 8192)");
+                println!(
+                    "      --queue-bytes <MB>    Pending bytes per slow subscriber (default: 4)"
+                );
+                println!("      --max-topics <N>      Topic registry size limit (default: 65536)");
+                println!(
+                    "      --client-topics <N>   New topics one connection may register (default: 1024)"
+                );
+                println!("  -v, --verbose             Verbose output");
+                println!("  -h, --help                Show this help");
                 std::process::exit(0);
             }
             _ => {}
@@ -0,0 +1,541 @@
+//! Hermes Storage Tool - inspeksi dan repair file `MmapStorage` dan
+//! direktori `SegmentedLog`
+//!
+//! Semua perintah kecuali `repair`/`migrate` membuka file read-only (copy-on-write),
+//! jadi aman dijalankan terhadap storage yang sedang dipakai. Jika `<FILE>`
+//! adalah direktori, perintah bekerja per segment dan `--from`/`--to` berupa
+//! offset log; segment aktif bisa berubah selama dibaca, dan `repair` hanya
+//! boleh dijalankan saat server berhenti.
+//!
+//! Usage:
+//!
+//! ```text
+//! cargo run --release --bin hermes-storage -- header hermes_data.dat
+//! cargo run --release --bin hermes-storage -- dump hermes_data.dat --limit 20
+//! cargo run --release --bin hermes-storage -- verify hermes_data.dat
+//! cargo run --release --bin hermes-storage -- export hermes_data.dat frames.bin --from 0
+//! cargo run --release --bin hermes-storage -- repair hermes_data.dat
+//! cargo run --release --bin hermes-storage -- migrate hermes_data.dat
+//! cargo run --release --bin hermes-storage -- verify hermes_data
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use hermes::core::{
+    MmapStorage, SegmentFile, SegmentedLog, StoredMessage, FLAG_FRAMES, MIN_SEGMENT_SIZE,
+};
+use hermes::protocol::{Decoder, MessageHeader, MessageType, FLAG_CRC32C, HEADER_SIZE};
+
+/// Jumlah byte payload yang ditampilkan `dump --payload`
+const PAYLOAD_PREVIEW: usize = 32;
+
+/// Opsi bersama untuk perintah yang membaca frame
+struct RangeArgs {
+    from: Option<usize>,
+    to: Option<usize>,
+    limit: Option<usize>,
+    payload: bool,
+}
+
+impl RangeArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut range = Self {
+            from: None,
+            to: None,
+            limit: None,
+            payload: false,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| format!("{} needs a numeric value", arg))
+            };
+            match arg.as_str() {
+                "--from" => range.from = Some(value()?),
+                "--to" => range.to = Some(value()?),
+                "--limit" | "-n" => range.limit = Some(value()?),
+                "--payload" | "-p" => range.payload = true,
+                other => return Err(format!("unknown option: {}", other)),
+            }
+        }
+        Ok(range)
+    }
+
+    /// Frame dalam `[from, to)`, default mulai dari `read_pos`
+    fn frames<'a>(&self, storage: &'a MmapStorage) -> impl Iterator<Item = StoredMessage<'a>> {
+        let to = self.to;
+        storage
+            .messages(self.from.unwrap_or_else(|| storage.read_pos()))
+            .take_while(move |msg| to.map_or(true, |to| msg.position < to))
+            .take(self.limit.unwrap_or(usize::MAX))
+    }
+
+    /// Record log dengan offset dalam `[from, to)`, default mulai dari segment pertama
+    fn records<'a>(&self, segments: &'a [SegmentFile]) -> impl Iterator<Item = (u64, &'a [u8])> {
+        let from = self.from.unwrap_or(0) as u64;
+        let to = self.to.map_or(u64::MAX, |to| to as u64);
+        segments
+            .iter()
+            .flat_map(|segment| segment.records())
+            .skip_while(move |&(offset, _)| offset < from)
+            .take_while(move |&(offset, _)| offset < to)
+            .take(self.limit.unwrap_or(usize::MAX))
+    }
+}
+
+fn print_header(storage: &MmapStorage) {
+    let info = storage.info();
+    let magic = info.magic.to_be_bytes();
+    println!(
+        "magic:      0x{:016X} ({})",
+        info.magic,
+        String::from_utf8_lossy(&magic)
+    );
+    match storage.migrated_from() {
+        Some(old) => println!(
+            "version:    {} (outdated, current is {}; run `hermes-storage migrate`)",
+            old, info.version
+        ),
+        None => println!("version:    {}", info.version),
+    }
+    if info.flags & FLAG_FRAMES != 0 {
+        println!("flags:      frames (torn tail recovered on open)");
+    } else {
+        println!("flags:      none (raw bytes, no frame recovery)");
+    }
+    println!("capacity:   {} bytes", info.capacity);
+    println!("write_pos:  {}", info.write_pos);
+    println!("read_pos:   {}", info.read_pos);
+    println!(
+        "unread:     {} bytes",
+        info.write_pos.wrapping_sub(info.read_pos)
+    );
+}
+
+fn dump(storage: &MmapStorage, range: &RangeArgs) {
+    println!(
+        "{:>12}  {:<10} {:>12} {:>20} {:>8} {:>10}",
+        "POSITION", "TYPE", "SEQUENCE", "TIMESTAMP_NS", "LEN", "CHECKSUM"
+    );
+    for msg in range.frames(storage) {
+        print_frame(
+            msg.position as u64,
+            &msg.header,
+            &msg.payload,
+            range.payload,
+        );
+    }
+}
+
+fn print_frame(position: u64, header: &MessageHeader, payload: &[u8], show_payload: bool) {
+    // Header packed: salin field sebelum diformat
+    let (msg_type, flags) = (header.msg_type, header.flags);
+    let (sequence, timestamp_ns, checksum) =
+        (header.sequence, header.timestamp_ns, header.checksum);
+
+    let algorithm = if flags & FLAG_CRC32C != 0 {
+        "crc32c"
+    } else {
+        "legacy"
+    };
+    let kind = MessageType::from_u8(msg_type)
+        .map(|t| format!("{:?}", t))
+        .unwrap_or_else(|| format!("?{}", msg_type));
+    println!(
+        "{:>12}  {:<10} {:>12} {:>20} {:>8} 0x{:08X} {}",
+        position,
+        kind,
+        sequence,
+        timestamp_ns,
+        payload.len(),
+        checksum,
+        algorithm
+    );
+    if show_payload {
+        print_payload(payload);
+    }
+}
+
+fn print_payload(payload: &[u8]) {
+    let preview = &payload[..payload.len().min(PAYLOAD_PREVIEW)];
+    let hex: Vec<String> = preview.iter().map(|b| format!("{:02x}", b)).collect();
+    println!("{:>12}  {}", "", hex.join(" "));
+}
+
+/// Returns `true` jika semua data di `[read_pos, write_pos)` valid
+fn verify(storage: &MmapStorage) -> bool {
+    let (read_pos, write_pos) = (storage.read_pos(), storage.write_pos());
+    let mut messages = storage.messages(read_pos);
+    let frames = messages.by_ref().count();
+    let end = messages.position();
+
+    println!(
+        "frames:     {} ({} bytes)",
+        frames,
+        end.wrapping_sub(read_pos)
+    );
+    if end == write_pos {
+        println!(
+            "✅ OK: all frames in [{}, {}) are valid",
+            read_pos, write_pos
+        );
+        true
+    } else {
+        println!(
+            "❌ CORRUPT: invalid or truncated frame at position {} ({} bytes up to write_pos {})",
+            end,
+            write_pos.wrapping_sub(end),
+            write_pos
+        );
+        if storage.is_framed() || frames > 0 {
+            println!("   Run `hermes-storage repair` to truncate the tail.");
+        } else {
+            println!("   The file is not marked as holding frames and none were found; it may hold raw bytes.");
+        }
+        false
+    }
+}
+
+fn export(storage: &MmapStorage, out: &str, range: &RangeArgs) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(out)?);
+    let (mut frames, mut bytes) = (0usize, 0usize);
+    let mut first = None;
+    let mut end = None;
+
+    for msg in range.frames(storage) {
+        writer.write_all(msg.header.as_bytes())?;
+        writer.write_all(&msg.payload)?;
+        first.get_or_insert(msg.position);
+        end = Some(msg.position + HEADER_SIZE + msg.payload.len());
+        frames += 1;
+        bytes += HEADER_SIZE + msg.payload.len();
+    }
+    writer.flush()?;
+
+    match (first, end) {
+        (Some(first), Some(end)) => println!(
+            "Exported {} frames ({} bytes) from [{}, {}) to {}",
+            frames, bytes, first, end, out
+        ),
+        _ => println!("No frames in range; wrote empty {}", out),
+    }
+    Ok(())
+}
+
+/// Returns `false` jika file tidak bisa di-repair (bytes mentah tanpa frame)
+fn repair(path: &str) -> io::Result<bool> {
+    let mut storage = MmapStorage::open_existing(path)?;
+    if let Some(old) = storage.migrated_from() {
+        println!(
+            "Header migrated from version {} to {}",
+            old,
+            storage.info().version
+        );
+    }
+
+    let report = if storage.is_framed() {
+        storage.recovery()
+    } else {
+        let unread = storage.write_pos() != storage.read_pos();
+        if unread && storage.messages(storage.read_pos()).next().is_none() {
+            println!(
+                "{} is not marked as holding frames and has no valid frame at read_pos {}; left unchanged",
+                path,
+                storage.read_pos()
+            );
+            return Ok(false);
+        }
+        println!(
+            "{} was not marked as holding frames; flagged and scanned",
+            path
+        );
+        storage.mark_framed()
+    };
+    // Pastikan header hasil repair sampai ke disk
+    storage.flush()?;
+
+    println!(
+        "Recovered {} frames ({} bytes), discarded {} bytes",
+        report.frames, report.bytes, report.discarded_bytes
+    );
+    println!("write_pos is now {}", storage.write_pos());
+    if let Some(pos) = report.corrupt_at {
+        println!(
+            "⚠️ Corrupt frame at position {} is followed by valid frames; data was kept",
+            pos
+        );
+    }
+    Ok(true)
+}
+
+fn migrate(path: &str) -> io::Result<()> {
+    let storage = MmapStorage::open_existing(path)?;
+    let version = storage.info().version;
+    match storage.migrated_from() {
+        Some(old) => println!("Migrated {} from version {} to {}", path, old, version),
+        None => println!("{} is already at version {}", path, version),
+    }
+    Ok(())
+}
+
+fn log_header(segments: &[SegmentFile]) {
+    let next = segments
+        .last()
+        .map_or(0, |s| s.base_offset() + s.scan().records);
+    println!("segments:   {}", segments.len());
+    println!(
+        "offsets:    [{}, {})",
+        segments.first().map_or(0, |s| s.base_offset()),
+        next
+    );
+    println!(
+        "\n{:>20} {:>8} {:>12} {:>10} {:>12}  TAIL",
+        "BASE_OFFSET", "VERSION", "SIZE", "RECORDS", "END"
+    );
+    for segment in segments {
+        let scan = segment.scan();
+        println!(
+            "{:>20} {:>8} {:>12} {:>10} {:>12}  {}",
+            segment.base_offset(),
+            segment.version(),
+            segment.size(),
+            scan.records,
+            scan.end,
+            if scan.torn { "torn" } else { "clean" }
+        );
+    }
+}
+
+fn log_dump(segments: &[SegmentFile], range: &RangeArgs) {
+    println!(
+        "{:>12}  {:<10} {:>12} {:>20} {:>8} {:>10}",
+        "OFFSET", "TYPE", "SEQUENCE", "TIMESTAMP_NS", "LEN", "CHECKSUM"
+    );
+    for (offset, record) in range.records(segments) {
+        match Decoder::new(record).next() {
+            Ok((header, payload)) => print_frame(offset, &header, payload, range.payload),
+            // Record log tidak harus frame Hermes
+            Err(_) => {
+                println!("{:>12}  {:<10} {:>54}", offset, "raw", record.len());
+                if range.payload {
+                    print_payload(record);
+                }
+            }
+        }
+    }
+}
+
+/// Returns `true` jika semua segment bersih dan offset-nya bersambung
+fn log_verify(segments: &[SegmentFile]) -> bool {
+    let mut ok = true;
+    let mut expected = None;
+    let (mut records, mut bytes) = (0u64, 0usize);
+    let (mut torn_active, mut torn_closed) = (false, false);
+
+    for (i, segment) in segments.iter().enumerate() {
+        let scan = segment.scan();
+        let base = segment.base_offset();
+        if let Some(expected) = expected.filter(|&e| e != base) {
+            println!(
+                "❌ GAP: segment {} starts at offset {}, expected {}",
+                base, base, expected
+            );
+            ok = false;
+        }
+        if scan.torn {
+            println!(
+                "❌ CORRUPT: invalid or truncated record in segment {} at byte {} (offset {})",
+                base,
+                scan.end,
+                base + scan.records
+            );
+            ok = false;
+            if i + 1 == segments.len() {
+                torn_active = true;
+            } else {
+                torn_closed = true;
+            }
+        }
+        expected = Some(base + scan.records);
+        records += scan.records;
+        bytes += scan.end;
+    }
+
+    println!("records:    {} ({} bytes)", records, bytes);
+    if ok {
+        println!(
+            "✅ OK: all {} segments are valid up to offset {}",
+            segments.len(),
+            expected.unwrap_or(0)
+        );
+    }
+    if torn_active {
+        println!(
+            "   Run `hermes-storage repair` with the server stopped to truncate the torn tail."
+        );
+    }
+    if torn_closed {
+        println!(
+            "   Corrupt closed segments are not repaired; `export` keeps the readable records."
+        );
+    }
+    ok
+}
+
+fn log_export(segments: &[SegmentFile], out: &str, range: &RangeArgs) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(out)?);
+    let (mut records, mut bytes) = (0usize, 0usize);
+    let mut span = None;
+
+    // Record log yang ditulis server adalah frame utuh
+    for (offset, record) in range.records(segments) {
+        writer.write_all(record)?;
+        span = Some((span.map_or(offset, |(first, _)| first), offset + 1));
+        records += 1;
+        bytes += record.len();
+    }
+    writer.flush()?;
+
+    match span {
+        Some((first, end)) => println!(
+            "Exported {} records ({} bytes) from offsets [{}, {}) to {}",
+            records, bytes, first, end, out
+        ),
+        None => println!("No records in range; wrote empty {}", out),
+    }
+    Ok(())
+}
+
+/// Returns `false` jika ada segment tertutup yang rusak (tidak di-repair)
+fn log_repair(dir: &Path) -> io::Result<bool> {
+    // Membuka log membuang torn tail segment aktif dan membangun ulang index
+    // yang tertinggal; segment tertutup tidak diubah
+    let log = SegmentedLog::open(dir, MIN_SEGMENT_SIZE)?;
+    let recovery = log.recovery();
+    match recovery.truncated_at {
+        Some(pos) => println!("Truncated the active segment at byte {}", pos),
+        None => println!("No torn tail found"),
+    }
+    for (base, pos) in &recovery.corrupt {
+        println!(
+            "❌ Closed segment {} is corrupt at byte {}; left unchanged",
+            base, pos
+        );
+    }
+    for base in &recovery.rebuilt_indexes {
+        println!("Rebuilt the index of segment {}", base);
+    }
+    println!("next offset is now {}", log.next_offset());
+    Ok(recovery.corrupt.is_empty())
+}
+
+fn run_log(command: &str, dir: &Path, rest: &[String]) -> Result<bool, String> {
+    let segments =
+        SegmentFile::open_all(dir).map_err(|e| format!("cannot open {}: {}", dir.display(), e))?;
+    if segments.is_empty() {
+        return Err(format!("{} contains no log segments", dir.display()));
+    }
+
+    match command {
+        "header" => log_header(&segments),
+        "dump" => log_dump(&segments, &RangeArgs::parse(&rest[1..])?),
+        "verify" => return Ok(log_verify(&segments)),
+        "export" => {
+            let out = rest.get(1).ok_or("missing <OUT>")?;
+            let range = RangeArgs::parse(&rest[2..])?;
+            log_export(&segments, out, &range).map_err(|e| format!("export failed: {}", e))?;
+        }
+        "repair" => {
+            drop(segments);
+            return log_repair(dir).map_err(|e| format!("repair failed: {}", e));
+        }
+        "migrate" => {
+            return Err("migrate only applies to MmapStorage files; \
+                 every supported segment version is read as-is"
+                .to_string())
+        }
+        other => return Err(format!("unknown command: {}", other)),
+    }
+    Ok(true)
+}
+
+fn print_usage() {
+    println!("Hermes Storage Tool - inspect and repair MmapStorage files and log directories\n");
+    println!("Usage: hermes-storage <COMMAND> <FILE|DIR> [OPTIONS]\n");
+    println!("Commands:");
+    println!("  header <FILE>             Print the storage header");
+    println!("  dump <FILE>               Print decoded frame headers");
+    println!("  verify <FILE>             Check magic + checksum of every unread frame");
+    println!("  export <FILE> <OUT>       Write raw frames to OUT");
+    println!("  repair <FILE>             Truncate a corrupted tail, flag unflagged frame files (modifies FILE)");
+    println!(
+        "  migrate <FILE>            Upgrade the header to the current format (modifies FILE)"
+    );
+    println!("\nOptions (dump, export):");
+    println!("      --from <POS>          Start position (default: read_pos)");
+    println!("      --to <POS>            Stop before frames starting at POS");
+    println!("  -n, --limit <N>           At most N frames");
+    println!("  -p, --payload             Show the first 32 payload bytes (dump)");
+    println!("\nDIR is a SegmentedLog directory (hermes_server --storage):");
+    println!("  header/dump/verify/export work per segment; --from/--to are log offsets");
+    println!(
+        "  repair truncates the torn tail of the active segment; run it with the server stopped"
+    );
+    println!("  corrupt closed segments are reported, never truncated");
+    println!("  migrate is not needed: old segment versions are read as-is");
+}
+
+fn run(args: &[String]) -> Result<bool, String> {
+    let (command, rest) = match args {
+        [command, rest @ ..] => (command.as_str(), rest),
+        [] => {
+            print_usage();
+            return Ok(true);
+        }
+    };
+    if matches!(command, "--help" | "-h" | "help") {
+        print_usage();
+        return Ok(true);
+    }
+
+    let path = rest.first().ok_or("missing <FILE>")?;
+    if Path::new(path).is_dir() {
+        return run_log(command, Path::new(path), rest);
+    }
+    let open =
+        || MmapStorage::open_read_only(path).map_err(|e| format!("cannot open {}: {}", path, e));
+
+    match command {
+        "header" => print_header(&open()?),
+        "dump" => dump(&open()?, &RangeArgs::parse(&rest[1..])?),
+        "verify" => return Ok(verify(&open()?)),
+        "export" => {
+            let out = rest.get(1).ok_or("missing <OUT>")?;
+            let range = RangeArgs::parse(&rest[2..])?;
+            export(&open()?, out, &range).map_err(|e| format!("export failed: {}", e))?;
+        }
+        "repair" => return repair(path).map_err(|e| format!("repair failed: {}", e)),
+        "migrate" => migrate(path).map_err(|e| format!("migrate failed: {}", e))?,
+        other => return Err(format!("unknown command: {}", other)),
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
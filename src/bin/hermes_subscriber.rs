@@ -17,14 +17,19 @@
 //!
 //! - `--host ADDR` - Server address (default: 127.0.0.1:9999)
 //! - `--duration SEC` - Test duration in seconds (default: 60)
+//! - `--topic PATTERN` - Subscribe ke topic atau pattern wildcard seperti
+//!   `analysis.>` (boleh diulang; default: semua frame)
 
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::net::TcpStream;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use hermes::protocol::{Decoder, MessageType, HEADER_SIZE};
+use hermes::protocol::{
+    encode_topic_names, parse_error, split_topic, DecodeError, Decoder, Encoder, ErrorCode,
+    MessageType, HEADER_SIZE,
+};
 
 /// High-resolution timestamp in nanoseconds
 #[inline(always)]
@@ -257,6 +262,7 @@ struct SubscriberConfig {
     host: String,
     duration_secs: u64,
     verbose: bool,
+    topics: Vec<String>,
 }
 
 impl Default for SubscriberConfig {
@@ -265,6 +271,7 @@ impl Default for SubscriberConfig {
             host: "127.0.0.1:9999".to_string(),
             duration_secs: 60,
             verbose: false,
+            topics: Vec::new(),
         }
     }
 }
@@ -277,6 +284,9 @@ fn run_subscriber(config: SubscriberConfig) -> io::Result<()> {
     println!("Configuration:");
     println!("  Server:     {}", config.host);
     println!("  Duration:   {}s", config.duration_secs);
+    if !config.topics.is_empty() {
+        println!("  Topics:     {}", config.topics.join(", "));
+    }
     println!();
 
     // Connect to server
@@ -285,6 +295,15 @@ fn run_subscriber(config: SubscriberConfig) -> io::Result<()> {
 
     // CRITICAL: TCP_NODELAY
     stream.set_nodelay(true)?;
+
+    // Tanpa --topic server mengirim semua frame (tidak perlu Subscribe)
+    if !config.topics.is_empty() {
+        let names = encode_topic_names(config.topics.iter().map(String::as_str));
+        let mut encoder = Encoder::new(HEADER_SIZE + names.len());
+        if let Some(frame) = encoder.encode(MessageType::Subscribe, 0, &names) {
+            stream.write_all(frame)?;
+        }
+    }
     // Use non-blocking mode instead of timeout
     stream.set_nonblocking(true)?;
 
@@ -343,14 +362,33 @@ fn run_subscriber(config: SubscriberConfig) -> io::Result<()> {
                     let mut decoder = Decoder::new(&recv_buffer[consumed..buffer_pos]);
 
                     match decoder.next() {
-                        Some((header, payload)) => {
+                        Ok((header, payload)) => {
                             let msg_size = HEADER_SIZE + payload.len();
                             consumed += msg_size;
 
+                            // Penolakan dari server (mis. topic tidak valid)
+                            if header.msg_type == MessageType::Nack as u8
+                                || header.msg_type == MessageType::Error as u8
+                            {
+                                if let Some((code, reason)) = parse_error(payload) {
+                                    let kind = MessageType::from_u8(header.msg_type).unwrap();
+                                    eprintln!(
+                                        "⚠️ Server {:?}: {:?} ({})",
+                                        kind,
+                                        ErrorCode::from_u16(code),
+                                        reason
+                                    );
+                                }
+                                continue;
+                            }
+
                             // Only process Publish messages
                             if header.msg_type != MessageType::Publish as u8 {
                                 continue;
                             }
+                            let Some((_topic, payload)) = split_topic(&header, payload) else {
+                                continue;
+                            };
 
                             messages_received.fetch_add(1, Ordering::Relaxed);
 
@@ -381,7 +419,9 @@ fn run_subscriber(config: SubscriberConfig) -> io::Result<()> {
                                 }
                             }
                         }
-                        None => break,
+                        Err(DecodeError::Incomplete) => break,
+                        // Data rusak: lewati sampai header valid berikutnya
+                        Err(_) => consumed += decoder.resync(),
                     }
                 }
 
@@ -464,17 +504,17 @@ fn parse_args() -> SubscriberConfig {
     let mut i = 1;
     while i < args.len() {
         match args[i].as_str() {
-            "--host" | "-h" => {
-                if i + 1 < args.len() {
-                    config.host = args[i + 1].clone();
-                    i += 1;
-                }
+            "--host" | "-h" if i + 1 < args.len() => {
+                config.host = args[i + 1].clone();
+                i += 1;
             }
-            "--duration" | "-d" => {
-                if i + 1 < args.len() {
-                    config.duration_secs = args[i + 1].parse().unwrap_or(60);
-                    i += 1;
-                }
+            "--duration" | "-d" if i + 1 < args.len() => {
+                config.duration_secs = args[i + 1].parse().unwrap_or(60);
+                i += 1;
+            }
+            "--topic" | "-t" if i + 1 < args.len() => {
+                config.topics.push(args[i + 1].clone());
+                i += 1;
             }
             "--verbose" | "-v" => {
                 config.verbose = true;
@@ -485,6 +525,7 @@ fn parse_args() -> SubscriberConfig {
                 println!("Options:");
                 println!("  -h, --host <ADDR>      Server address (default: 127.0.0.1:9999)");
                 println!("  -d, --duration <SEC>   Test duration (default: 60)");
+                println!("  -t, --topic <PATTERN>  Topic/wildcard, e.g. analysis.> (repeatable)");
                 println!("  -v, --verbose          Verbose output");
                 println!("      --help             Show this help");
                 std::process::exit(0);
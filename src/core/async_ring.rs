@@ -0,0 +1,441 @@
+//! Async Adapters untuk RingBuffer (executor-agnostic)
+//!
+//! `AsyncProducer` / `AsyncConsumer` membungkus `RingBuffer` SPSC dengan waker:
+//! consumer yang menemukan buffer kosong (atau producer yang menemukan buffer
+//! penuh) mendaftarkan waker-nya dan dibangunkan oleh sisi lawan. Tidak
+//! bergantung pada tokio atau executor tertentu - cukup `std::task`.
+//!
+//! Dengan feature `futures`, `AsyncConsumer` mengimplementasikan
+//! `futures_core::Stream` dan `AsyncProducer` mengimplementasikan `futures_sink::Sink`.
+
+use std::future::poll_fn;
+use std::sync::atomic::{fence, AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use super::ring_buffer::RingBuffer;
+
+/// Error saat sisi lawan sudah di-drop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+impl std::fmt::Display for Closed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ring buffer closed")
+    }
+}
+
+impl std::error::Error for Closed {}
+
+/// Slot untuk satu waker yang menunggu
+///
+/// `armed` membuat `wake()` di hot path cukup fence + satu atomic load
+/// tanpa menyentuh mutex selama tidak ada yang menunggu.
+struct WakerSlot {
+    armed: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl WakerSlot {
+    const fn new() -> Self {
+        Self {
+            armed: AtomicBool::new(false),
+            waker: Mutex::new(None),
+        }
+    }
+
+    #[cold]
+    fn register(&self, waker: &Waker) {
+        let mut slot = self.waker.lock().unwrap_or_else(|e| e.into_inner());
+        match slot.as_ref() {
+            Some(current) if current.will_wake(waker) => {}
+            _ => *slot = Some(waker.clone()),
+        }
+        self.armed.store(true, Ordering::SeqCst);
+        // Berpasangan dengan fence di `wake`: setelah ini caller WAJIB cek ulang ring
+        fence(Ordering::SeqCst);
+    }
+
+    #[inline(always)]
+    fn wake(&self) {
+        // Berpasangan dengan fence di `register`: push/pop di atas
+        // terlihat oleh waiter, atau kita melihat `armed`
+        fence(Ordering::SeqCst);
+        if self.armed.load(Ordering::Relaxed) && self.armed.swap(false, Ordering::SeqCst) {
+            self.wake_slow();
+        }
+    }
+
+    #[cold]
+    fn wake_slow(&self) {
+        let waker = self.waker.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// State bersama producer dan consumer
+struct Shared<T, const N: usize> {
+    ring: RingBuffer<T, N>,
+    // Consumer menunggu data
+    rx_waker: WakerSlot,
+    // Producer menunggu ruang kosong
+    tx_waker: WakerSlot,
+    closed: AtomicBool,
+}
+
+impl<T, const N: usize> Shared<T, N> {
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.rx_waker.wake();
+        self.tx_waker.wake();
+    }
+
+    #[inline(always)]
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+}
+
+/// Membuat pasangan producer/consumer async di atas `ring`
+///
+/// Ring dipindahkan ke dalam adapter; setiap sisi dimiliki tepat satu task
+/// sehingga kontrak SPSC terjaga oleh type system.
+pub fn async_ring<T, const N: usize>(
+    ring: RingBuffer<T, N>,
+) -> (AsyncProducer<T, N>, AsyncConsumer<T, N>) {
+    let shared = Arc::new(Shared {
+        ring,
+        rx_waker: WakerSlot::new(),
+        tx_waker: WakerSlot::new(),
+        closed: AtomicBool::new(false),
+    });
+
+    (
+        AsyncProducer {
+            shared: shared.clone(),
+        },
+        AsyncConsumer { shared },
+    )
+}
+
+/// Producer side async (Sink-style)
+pub struct AsyncProducer<T, const N: usize = 0> {
+    shared: Arc<Shared<T, N>>,
+}
+
+impl<T, const N: usize> AsyncProducer<T, N> {
+    /// Tunggu sampai ada ruang kosong
+    ///
+    /// Returns `Ready(Err(Closed))` jika consumer sudah di-drop.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let shared = &*self.shared;
+
+        if shared.is_closed() {
+            return Poll::Ready(Err(Closed));
+        }
+        if !shared.ring.is_full() {
+            return Poll::Ready(Ok(()));
+        }
+
+        shared.tx_waker.register(cx.waker());
+
+        // Cek ulang setelah register: consumer mungkin pop di antaranya
+        if shared.is_closed() {
+            Poll::Ready(Err(Closed))
+        } else if !shared.ring.is_full() {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Push tanpa menunggu; membangunkan consumer jika sedang menunggu
+    ///
+    /// Returns `Err(value)` jika buffer penuh atau consumer sudah di-drop.
+    #[inline(always)]
+    pub fn try_send(&mut self, value: T) -> Result<(), T> {
+        if self.shared.is_closed() {
+            return Err(value);
+        }
+        // SAFETY: AsyncProducer adalah satu-satunya producer ring ini
+        unsafe { self.shared.ring.try_push(value)? };
+        self.shared.rx_waker.wake();
+        Ok(())
+    }
+
+    /// Push dan tunggu jika buffer penuh
+    ///
+    /// Returns `Err(value)` jika consumer sudah di-drop.
+    pub async fn send(&mut self, value: T) -> Result<(), T> {
+        let mut pending = Some(value);
+        poll_fn(|cx| match self.poll_ready(cx) {
+            Poll::Ready(Ok(())) => {
+                let value = pending.take().expect("send polled after completion");
+                Poll::Ready(self.try_send(value))
+            }
+            Poll::Ready(Err(Closed)) => {
+                Poll::Ready(Err(pending.take().expect("send polled after completion")))
+            }
+            Poll::Pending => Poll::Pending,
+        })
+        .await
+    }
+
+    /// Returns `true` jika consumer sudah di-drop
+    pub fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+}
+
+impl<T, const N: usize> Drop for AsyncProducer<T, N> {
+    fn drop(&mut self) {
+        // Consumer menghabiskan sisa data lalu menerima `None`
+        self.shared.close();
+    }
+}
+
+/// Consumer side async (Stream-style)
+pub struct AsyncConsumer<T, const N: usize = 0> {
+    shared: Arc<Shared<T, N>>,
+}
+
+impl<T, const N: usize> AsyncConsumer<T, N> {
+    /// Poll data berikutnya
+    ///
+    /// Returns `Ready(None)` jika producer sudah di-drop dan buffer kosong.
+    pub fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Some(value) = self.try_recv() {
+            return Poll::Ready(Some(value));
+        }
+
+        self.shared.rx_waker.register(cx.waker());
+
+        // Cek ulang setelah register: producer mungkin push di antaranya
+        if let Some(value) = self.try_recv() {
+            return Poll::Ready(Some(value));
+        }
+        if self.shared.is_closed() {
+            // Producer mungkin push tepat sebelum close
+            return Poll::Ready(self.try_recv());
+        }
+        Poll::Pending
+    }
+
+    /// Pop tanpa menunggu; membangunkan producer jika sedang menunggu
+    #[inline(always)]
+    pub fn try_recv(&mut self) -> Option<T> {
+        // SAFETY: AsyncConsumer adalah satu-satunya consumer ring ini
+        let value = unsafe { self.shared.ring.pop()? };
+        self.shared.tx_waker.wake();
+        Some(value)
+    }
+
+    /// Tunggu data berikutnya
+    ///
+    /// Returns `None` jika producer sudah di-drop dan buffer kosong.
+    pub async fn recv(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Returns `true` jika producer sudah di-drop
+    pub fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+}
+
+impl<T, const N: usize> Drop for AsyncConsumer<T, N> {
+    fn drop(&mut self) {
+        // Producer yang menunggu ruang menerima `Closed`
+        self.shared.close();
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T, const N: usize> futures_core::Stream for AsyncConsumer<T, N> {
+    type Item = T;
+
+    fn poll_next(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<T, const N: usize> futures_sink::Sink<T> for AsyncProducer<T, N> {
+    type Error = Closed;
+
+    fn poll_ready(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        AsyncProducer::poll_ready(self.get_mut(), cx)
+    }
+
+    fn start_send(self: std::pin::Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        // Setelah poll_ready Ok hanya consumer yang bisa mengubah state (menambah ruang)
+        self.get_mut().try_send(item).map_err(|_| Closed)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        // Item langsung terlihat oleh consumer setelah start_send
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.shared.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::Wake;
+    use std::thread::{self, Thread};
+
+    /// Executor minimal: park thread sampai waker dipanggil
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_async_send_recv() {
+        let (mut tx, mut rx) = async_ring(RingBuffer::<u64, 4>::new());
+
+        block_on(async {
+            for i in 0..4 {
+                assert_eq!(tx.send(i).await, Ok(()));
+            }
+            assert_eq!(tx.try_send(99), Err(99)); // Full
+
+            for i in 0..4 {
+                assert_eq!(rx.recv().await, Some(i));
+            }
+            assert_eq!(rx.try_recv(), None);
+        });
+    }
+
+    #[test]
+    fn test_async_cross_thread_wakeups() {
+        const MESSAGES: u64 = 10_000;
+        // Buffer kecil memaksa kedua sisi sering menunggu
+        let (mut tx, mut rx) = async_ring(RingBuffer::<u64>::with_capacity(8));
+
+        let producer = thread::spawn(move || {
+            block_on(async {
+                for i in 0..MESSAGES {
+                    assert_eq!(tx.send(i).await, Ok(()));
+                }
+            });
+        });
+
+        block_on(async {
+            for i in 0..MESSAGES {
+                assert_eq!(rx.recv().await, Some(i));
+            }
+            // Producer di-drop setelah selesai: stream berakhir
+            assert_eq!(rx.recv().await, None);
+        });
+
+        producer.join().unwrap();
+    }
+
+    #[test]
+    fn test_async_close() {
+        let (mut tx, mut rx) = async_ring(RingBuffer::<String, 2>::new());
+
+        block_on(async {
+            tx.send("a".to_string()).await.unwrap();
+            drop(tx);
+
+            // Sisa data tetap terkirim sebelum None
+            assert!(rx.is_closed());
+            assert_eq!(rx.recv().await.as_deref(), Some("a"));
+            assert_eq!(rx.recv().await, None);
+        });
+
+        let (mut tx, rx) = async_ring(RingBuffer::<u64, 2>::new());
+        drop(rx);
+        assert_eq!(block_on(tx.send(1)), Err(1));
+    }
+
+    #[test]
+    fn test_async_producer_waits_for_space() {
+        let (mut tx, mut rx) = async_ring(RingBuffer::<u64, 2>::new());
+
+        block_on(async {
+            tx.send(1).await.unwrap();
+            tx.send(2).await.unwrap();
+        });
+
+        let consumer = thread::spawn(move || {
+            thread::sleep(std::time::Duration::from_millis(20));
+            assert_eq!(rx.try_recv(), Some(1));
+            rx
+        });
+
+        // Pending sampai consumer pop, lalu dibangunkan
+        block_on(tx.send(3)).unwrap();
+        let mut rx = consumer.join().unwrap();
+        assert_eq!(rx.try_recv(), Some(2));
+        assert_eq!(rx.try_recv(), Some(3));
+    }
+
+    #[cfg(feature = "futures")]
+    #[test]
+    fn test_stream_sink_traits() {
+        use futures_core::Stream;
+        use futures_sink::Sink;
+        use std::pin::Pin;
+
+        let (mut tx, mut rx) = async_ring(RingBuffer::<u64, 4>::new());
+
+        block_on(poll_fn(|cx| {
+            for i in 0..3 {
+                assert!(matches!(
+                    Pin::new(&mut tx).poll_ready(cx),
+                    Poll::Ready(Ok(()))
+                ));
+                Pin::new(&mut tx).start_send(i).unwrap();
+            }
+            Pin::new(&mut tx).poll_close(cx)
+        }))
+        .unwrap();
+
+        let mut received = Vec::new();
+        block_on(poll_fn(|cx| loop {
+            match Pin::new(&mut rx).poll_next(cx) {
+                Poll::Ready(Some(v)) => received.push(v),
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Pending => return Poll::Pending,
+            }
+        }));
+        assert_eq!(received, vec![0, 1, 2]);
+    }
+}
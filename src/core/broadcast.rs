@@ -0,0 +1,457 @@
+//! Lock-Free Broadcast Ring Buffer (SPMC, Disruptor-style)
+//!
+//! Satu producer, banyak consumer, dan SETIAP consumer menerima SETIAP pesan.
+//! Berbeda dengan `RingBuffer::pop`, membaca tidak menghapus item:
+//! tiap consumer punya cursor (sequence) sendiri.
+//!
+//! Dua kebijakan saat consumer tertinggal:
+//! - `OverflowPolicy::Block`: producer di-gate oleh consumer paling lambat
+//! - `OverflowPolicy::Overwrite`: producer tidak pernah menunggu, consumer
+//!   yang tertinggal menerima `RecvError::Lapped` (terdeteksi, bukan data korup)
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+
+use super::ring_buffer::CacheLinePadded;
+
+/// Penanda cursor consumer yang tidak terpakai
+const FREE: usize = usize::MAX;
+
+/// Kebijakan producer saat consumer paling lambat sudah tertinggal N item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// `push` gagal (return `false`) sampai consumer paling lambat maju
+    Block,
+    /// `push` selalu berhasil, consumer yang tertinggal di-lap
+    Overwrite,
+}
+
+/// Error dari `BroadcastReceiver::try_recv`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// Belum ada pesan baru
+    Empty,
+    /// Producer sudah menimpa pesan yang belum dibaca.
+    /// Berisi jumlah pesan yang terlewat; cursor sudah dipindah ke pesan tertua yang masih valid.
+    Lapped(u64),
+}
+
+/// Slot dalam broadcast ring
+#[repr(C, align(64))] // Cache line alignment untuk menghindari false sharing
+struct Slot<T> {
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Lock-Free SPMC Broadcast Ring Buffer
+#[repr(C)]
+pub struct BroadcastRing<T, const N: usize> {
+    // Producer side: sequence berikutnya yang akan dipublish
+    head: CacheLinePadded<AtomicUsize>,
+    // Producer side: sequence yang sedang ditulis (seqlock untuk mode Overwrite)
+    claim: CacheLinePadded<AtomicUsize>,
+    // Cache cursor paling lambat - hanya disentuh producer
+    gate: CacheLinePadded<AtomicUsize>,
+    // Cursor per consumer, masing-masing di cache line sendiri
+    cursors: Box<[CacheLinePadded<AtomicUsize>]>,
+    // Pre-allocated buffer di heap - tidak ada alokasi setelah init
+    buffer: Box<[Slot<T>]>,
+    // Mask untuk operasi modulo yang cepat (N harus power of 2)
+    mask: usize,
+    policy: OverflowPolicy,
+}
+
+// SAFETY: BroadcastRing aman untuk Send/Sync karena:
+// - Hanya satu producer (menulis head/claim/slot)
+// - Consumer hanya membaca slot dan menulis cursor miliknya sendiri
+// - Mode Block: producer tidak menimpa slot yang belum dibaca semua consumer
+// - Mode Overwrite: pembacaan divalidasi ulang via `claim` (seqlock)
+unsafe impl<T: Send, const N: usize> Send for BroadcastRing<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for BroadcastRing<T, N> {}
+
+impl<T: Copy, const N: usize> BroadcastRing<T, N> {
+    /// Membuat broadcast ring baru. N HARUS power of 2.
+    ///
+    /// `max_consumers` menentukan jumlah cursor yang di-pre-allocate.
+    ///
+    /// # Panics
+    /// Panic jika N bukan power of 2 atau N == 0
+    pub fn new(max_consumers: usize, policy: OverflowPolicy) -> Self {
+        assert!(N > 0 && N.is_power_of_two(), "N must be power of 2");
+
+        let mut buffer = Vec::with_capacity(N);
+        for _ in 0..N {
+            buffer.push(Slot {
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            });
+        }
+
+        let mut cursors = Vec::with_capacity(max_consumers);
+        for _ in 0..max_consumers {
+            cursors.push(CacheLinePadded::new(AtomicUsize::new(FREE)));
+        }
+
+        Self {
+            head: CacheLinePadded::new(AtomicUsize::new(0)),
+            claim: CacheLinePadded::new(AtomicUsize::new(0)),
+            gate: CacheLinePadded::new(AtomicUsize::new(0)),
+            cursors: cursors.into_boxed_slice(),
+            buffer: buffer.into_boxed_slice(),
+            mask: N - 1,
+            policy,
+        }
+    }
+
+    /// Publish data ke semua consumer (Producer side)
+    ///
+    /// Returns `false` hanya pada `OverflowPolicy::Block` saat consumer
+    /// paling lambat masih tertinggal N item.
+    #[inline(always)]
+    pub fn push(&self, value: T) -> bool {
+        let head = self.head.value.load(Ordering::Relaxed);
+
+        if self.policy == OverflowPolicy::Block {
+            let gate = self.gate.value.load(Ordering::Relaxed);
+            if head.wrapping_sub(gate) >= N {
+                // Slow path: hitung ulang consumer paling lambat
+                let gate = self.slowest_cursor(head);
+                self.gate.value.store(gate, Ordering::Relaxed);
+                if head.wrapping_sub(gate) >= N {
+                    return false;
+                }
+            }
+        }
+
+        // Umumkan slot yang akan ditimpa sebelum menulis (seqlock writer)
+        self.claim
+            .value
+            .store(head.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        let slot = &self.buffer[head & self.mask];
+
+        // SAFETY: Hanya producer yang menulis slot. Pada mode Block slot ini
+        // sudah dibaca semua consumer; pada mode Overwrite pembaca yang
+        // bertabrakan akan mendeteksinya via `claim`.
+        unsafe {
+            (*slot.data.get()).write(value);
+        }
+
+        // Release: pastikan write di atas visible sebelum head di-update
+        self.head
+            .value
+            .store(head.wrapping_add(1), Ordering::Release);
+
+        true
+    }
+
+    /// Cursor consumer paling lambat (atau `head` jika tidak ada consumer)
+    #[cold]
+    fn slowest_cursor(&self, head: usize) -> usize {
+        // Berpasangan dengan fence di `subscribe`: consumer yang baru join
+        // pasti terlihat di sini, atau dia sudah melihat head terbaru.
+        fence(Ordering::SeqCst);
+
+        let mut slowest = head;
+        for cursor in self.cursors.iter() {
+            let pos = cursor.value.load(Ordering::Acquire);
+            if pos != FREE && head.wrapping_sub(pos) > head.wrapping_sub(slowest) {
+                slowest = pos;
+            }
+        }
+        slowest
+    }
+
+    /// Mendaftarkan consumer baru, mulai dari pesan berikutnya yang dipublish
+    ///
+    /// Returns `None` jika semua cursor (`max_consumers`) sudah terpakai.
+    pub fn subscribe(&self) -> Option<BroadcastReceiver<'_, T, N>> {
+        let head = self.head.value.load(Ordering::Acquire);
+
+        for (id, cursor) in self.cursors.iter().enumerate() {
+            if cursor
+                .value
+                .compare_exchange(FREE, head, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                fence(Ordering::SeqCst);
+                // Head mungkin sudah maju sebelum cursor terlihat oleh producer
+                let next = self.head.value.load(Ordering::Acquire);
+                cursor.value.store(next, Ordering::Release);
+
+                return Some(BroadcastReceiver {
+                    ring: self,
+                    id,
+                    next,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// Jumlah consumer aktif
+    pub fn consumer_count(&self) -> usize {
+        self.cursors
+            .iter()
+            .filter(|c| c.value.load(Ordering::Relaxed) != FREE)
+            .count()
+    }
+
+    /// Kebijakan overflow ring ini
+    #[inline(always)]
+    pub fn policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Kapasitas buffer
+    #[inline(always)]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+}
+
+/// Handle consumer dengan cursor independen
+///
+/// Cursor dilepas otomatis saat receiver di-drop.
+pub struct BroadcastReceiver<'a, T, const N: usize> {
+    ring: &'a BroadcastRing<T, N>,
+    id: usize,
+    next: usize,
+}
+
+impl<T: Copy, const N: usize> BroadcastReceiver<'_, T, N> {
+    /// Baca pesan berikutnya untuk consumer ini (zero-allocation, lock-free)
+    #[inline(always)]
+    pub fn try_recv(&mut self) -> Result<T, RecvError> {
+        let ring = self.ring;
+        let head = ring.head.value.load(Ordering::Acquire);
+
+        if self.next == head {
+            return Err(RecvError::Empty);
+        }
+
+        if ring.policy == OverflowPolicy::Overwrite && head.wrapping_sub(self.next) > N {
+            return Err(self.skip_to(head.wrapping_sub(N)));
+        }
+
+        let slot = &ring.buffer[self.next & ring.mask];
+
+        // SAFETY: slot sudah dipublish (next < head). Pada mode Overwrite
+        // producer bisa menimpa slot ini bersamaan, jadi baca secara volatile
+        // dan validasi ulang di bawah sebelum nilainya dipakai.
+        let value = unsafe { std::ptr::read_volatile((*slot.data.get()).as_ptr()) };
+
+        if ring.policy == OverflowPolicy::Overwrite {
+            fence(Ordering::Acquire);
+            let claim = ring.claim.value.load(Ordering::Relaxed);
+            if claim.wrapping_sub(self.next) > N {
+                // Slot ditimpa saat dibaca - buang nilai yang mungkin torn
+                return Err(self.skip_to(claim.wrapping_sub(N)));
+            }
+        }
+
+        self.next = self.next.wrapping_add(1);
+        // Release: read selesai sebelum producer boleh menimpa slot
+        ring.cursors[self.id]
+            .value
+            .store(self.next, Ordering::Release);
+
+        Ok(value)
+    }
+
+    /// Pindahkan cursor ke `pos` setelah di-lap oleh producer
+    #[cold]
+    fn skip_to(&mut self, pos: usize) -> RecvError {
+        let missed = pos.wrapping_sub(self.next) as u64;
+        self.next = pos;
+        self.ring.cursors[self.id]
+            .value
+            .store(pos, Ordering::Release);
+        RecvError::Lapped(missed)
+    }
+
+    /// Jumlah pesan yang sudah dipublish tapi belum dibaca consumer ini
+    #[inline(always)]
+    pub fn lag(&self) -> usize {
+        self.ring
+            .head
+            .value
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.next)
+    }
+}
+
+impl<T, const N: usize> Drop for BroadcastReceiver<'_, T, N> {
+    fn drop(&mut self) {
+        self.ring.cursors[self.id]
+            .value
+            .store(FREE, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_every_consumer_sees_every_message() {
+        let ring: BroadcastRing<u64, 16> = BroadcastRing::new(4, OverflowPolicy::Block);
+        let mut a = ring.subscribe().unwrap();
+        let mut b = ring.subscribe().unwrap();
+
+        for i in 0..10 {
+            assert!(ring.push(i));
+        }
+
+        for i in 0..10 {
+            assert_eq!(a.try_recv(), Ok(i));
+        }
+        assert_eq!(a.try_recv(), Err(RecvError::Empty));
+
+        // Consumer b membaca independen dari a
+        assert_eq!(b.lag(), 10);
+        for i in 0..10 {
+            assert_eq!(b.try_recv(), Ok(i));
+        }
+        assert_eq!(b.try_recv(), Err(RecvError::Empty));
+    }
+
+    #[test]
+    fn test_block_policy_gated_by_slowest() {
+        let ring: BroadcastRing<u64, 4> = BroadcastRing::new(2, OverflowPolicy::Block);
+        let mut fast = ring.subscribe().unwrap();
+        let mut slow = ring.subscribe().unwrap();
+
+        for i in 0..4 {
+            assert!(ring.push(i));
+        }
+        for i in 0..4 {
+            assert_eq!(fast.try_recv(), Ok(i));
+        }
+
+        // Fast consumer sudah habis, tapi slow consumer belum membaca apapun
+        assert!(!ring.push(4));
+
+        assert_eq!(slow.try_recv(), Ok(0));
+        assert!(ring.push(4)); // Now should succeed
+        assert!(!ring.push(5));
+
+        // Consumer yang di-drop tidak lagi menahan producer
+        drop(slow);
+        assert!(ring.push(5));
+        assert_eq!(ring.consumer_count(), 1);
+    }
+
+    #[test]
+    fn test_overwrite_policy_reports_lapped() {
+        let ring: BroadcastRing<u64, 4> = BroadcastRing::new(1, OverflowPolicy::Overwrite);
+        let mut rx = ring.subscribe().unwrap();
+
+        // Producer tidak pernah diblok
+        for i in 0..10 {
+            assert!(ring.push(i));
+        }
+
+        // 10 dipublish, hanya 4 terakhir yang masih ada: 6 terlewat
+        assert_eq!(rx.try_recv(), Err(RecvError::Lapped(6)));
+        for i in 6..10 {
+            assert_eq!(rx.try_recv(), Ok(i));
+        }
+        assert_eq!(rx.try_recv(), Err(RecvError::Empty));
+    }
+
+    #[test]
+    fn test_max_consumers_and_late_subscriber() {
+        let ring: BroadcastRing<u64, 8> = BroadcastRing::new(1, OverflowPolicy::Block);
+        assert!(ring.push(1));
+
+        let mut rx = ring.subscribe().unwrap();
+        assert!(ring.subscribe().is_none());
+
+        // Subscriber baru mulai dari pesan berikutnya
+        assert_eq!(rx.try_recv(), Err(RecvError::Empty));
+        assert!(ring.push(2));
+        assert_eq!(rx.try_recv(), Ok(2));
+
+        drop(rx);
+        assert!(ring.subscribe().is_some());
+    }
+
+    #[test]
+    fn test_concurrent_broadcast_block() {
+        const CONSUMERS: usize = 3;
+        const MESSAGES: u64 = 50_000;
+        let ring: BroadcastRing<u64, 256> = BroadcastRing::new(CONSUMERS, OverflowPolicy::Block);
+
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..CONSUMERS)
+                .map(|_| {
+                    let mut rx = ring.subscribe().unwrap();
+                    s.spawn(move || {
+                        let mut expected = 0u64;
+                        while expected < MESSAGES {
+                            match rx.try_recv() {
+                                Ok(v) => {
+                                    assert_eq!(v, expected);
+                                    expected += 1;
+                                }
+                                Err(RecvError::Empty) => thread::yield_now(),
+                                Err(e) => panic!("unexpected {:?}", e),
+                            }
+                        }
+                    })
+                })
+                .collect();
+
+            for i in 0..MESSAGES {
+                while !ring.push(i) {
+                    thread::yield_now();
+                }
+            }
+
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn test_concurrent_overwrite_never_torn() {
+        const MESSAGES: u64 = 50_000;
+        let ring: BroadcastRing<[u64; 4], 8> = BroadcastRing::new(2, OverflowPolicy::Overwrite);
+
+        thread::scope(|s| {
+            let mut rx = ring.subscribe().unwrap();
+            let reader = s.spawn(move || {
+                let mut last = None;
+                let mut received = 0u64;
+                loop {
+                    match rx.try_recv() {
+                        Ok(v) => {
+                            // Nilai yang dikembalikan tidak pernah campuran dua write
+                            assert!(v.iter().all(|&x| x == v[0]), "torn read {:?}", v);
+                            if let Some(prev) = last {
+                                assert!(v[0] > prev);
+                            }
+                            last = Some(v[0]);
+                            received += 1;
+                            if v[0] == MESSAGES - 1 {
+                                return received;
+                            }
+                        }
+                        Err(RecvError::Lapped(_)) => {}
+                        Err(RecvError::Empty) => thread::yield_now(),
+                    }
+                }
+            });
+
+            for i in 0..MESSAGES {
+                ring.push([i; 4]);
+            }
+
+            assert!(reader.join().unwrap() > 0);
+        });
+    }
+}
@@ -0,0 +1,505 @@
+//! Key-based compaction untuk `SegmentedLog` (last value per key)
+//!
+//! `Compactor` membaca segment yang sudah ditutup (read-only, aman dijalankan
+//! di thread lain selagi log terus di-append), menyimpan record terbaru per key,
+//! lalu menulis snapshot `compacted.snapshot` di direktori log secara atomic
+//! (tmp file + fsync + rename).
+//!
+//! Subscriber baru bootstrap dengan membaca `Snapshot` lalu melanjutkan dari
+//! `log.iter_from(snapshot.next_offset())`. Record tanpa key (extractor
+//! mengembalikan `None`) tidak masuk snapshot.
+//!
+//! Retention tidak boleh melepas segment yang belum diproses (lihat
+//! `SegmentedLog::detach_expired_before`); segment yang tetap hilang sebelum
+//! dikompaksi dilaporkan lewat `Compactor::missed`.
+//!
+//! Layout snapshot:
+//! ```text
+//! 0    magic: u64 ("HERMCMP1"), version: u32, reserved: u32
+//! 16   next_offset: u64 (semua record < offset ini sudah diproses), count: u64
+//! 32   record: [offset u64][len u32][crc32c u32][payload], urut offset
+//! ```
+//! Snapshot versi 1 (checksum legacy) masih dibaca; snapshot selalu ditulis
+//! ulang dengan versi terbaru.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use super::segmented_log::{segment_bases, sync_dir, SegmentFile};
+use crate::protocol::{crc32c, legacy_checksum, split_topic, MessageHeader, HEADER_SIZE};
+
+const SNAPSHOT_MAGIC: u64 = 0x4845524D434D5031; // "HERMCMP1" in hex
+const SNAPSHOT_VERSION: u32 = 2;
+/// Versi terakhir yang memakai checksum legacy untuk record
+const SNAPSHOT_VERSION_LEGACY: u32 = 1;
+const SNAPSHOT_HEADER_SIZE: usize = 32;
+const SNAPSHOT_RECORD_HEADER_SIZE: usize = 16;
+const SNAPSHOT_FILE: &str = "compacted.snapshot";
+
+/// `(offset, record)` hasil compaction
+type Entry = (u64, Box<[u8]>);
+
+/// Mengambil key dari satu record log
+pub trait KeyExtractor: Send {
+    /// Returns `None` jika record tidak punya key
+    fn key<'a>(&self, record: &'a [u8]) -> Option<&'a [u8]>;
+}
+
+/// Key = byte range tetap di dalam record mentah
+///
+/// Untuk frame Hermes range dihitung dari awal header, sehingga bergeser
+/// 4 bytes untuk frame `FLAG_TOPIC`; pakai `PayloadRange` untuk frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ByteRange(pub Range<usize>);
+
+impl KeyExtractor for ByteRange {
+    #[inline(always)]
+    fn key<'a>(&self, record: &'a [u8]) -> Option<&'a [u8]> {
+        record.get(self.0.clone())
+    }
+}
+
+/// Key = byte range di payload frame Hermes, setelah prefix topic id
+///
+/// Offset sama untuk frame dengan dan tanpa `FLAG_TOPIC`: contract address
+/// `TokenAnalysis` ada di `0..32`. Topic tidak termasuk key; record yang
+/// bukan frame Hermes tidak punya key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadRange(pub Range<usize>);
+
+impl KeyExtractor for PayloadRange {
+    #[inline(always)]
+    fn key<'a>(&self, record: &'a [u8]) -> Option<&'a [u8]> {
+        // SAFETY: MessageHeader packed (align 1) dan from_bytes cek panjang buffer
+        let header = unsafe { MessageHeader::from_bytes(record)? };
+        let payload = record.get(HEADER_SIZE..HEADER_SIZE + header.payload_len as usize)?;
+        let (_, payload) = split_topic(header, payload)?;
+        payload.get(self.0.clone())
+    }
+}
+
+/// Hasil compaction yang tersimpan di disk
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    next_offset: u64,
+    records: Vec<Entry>,
+}
+
+impl Snapshot {
+    /// Path snapshot untuk log di `dir`
+    pub fn path<P: AsRef<Path>>(dir: P) -> PathBuf {
+        dir.as_ref().join(SNAPSHOT_FILE)
+    }
+
+    /// Membaca snapshot log di `dir`; snapshot kosong jika belum ada
+    pub fn read<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let path = Self::path(dir);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("corrupted snapshot {}", path.display()),
+            )
+        };
+
+        if bytes.len() < SNAPSHOT_HEADER_SIZE
+            || read_u64(&bytes, 0) != SNAPSHOT_MAGIC
+            || !(1..=SNAPSHOT_VERSION).contains(&read_u32(&bytes, 8))
+        {
+            return Err(invalid());
+        }
+        let checksum: fn(&[u8]) -> u32 = if read_u32(&bytes, 8) <= SNAPSHOT_VERSION_LEGACY {
+            legacy_checksum
+        } else {
+            crc32c
+        };
+        let next_offset = read_u64(&bytes, 16);
+        let count = read_u64(&bytes, 24) as usize;
+
+        let mut records = Vec::with_capacity(count.min(bytes.len() / SNAPSHOT_RECORD_HEADER_SIZE));
+        let mut pos = SNAPSHOT_HEADER_SIZE;
+        for _ in 0..count {
+            let header = bytes
+                .get(pos..pos + SNAPSHOT_RECORD_HEADER_SIZE)
+                .ok_or_else(invalid)?;
+            let offset = read_u64(header, 0);
+            let len = read_u32(header, 8) as usize;
+            let start = pos + SNAPSHOT_RECORD_HEADER_SIZE;
+            let payload = bytes.get(start..start + len).ok_or_else(invalid)?;
+            if checksum(payload) != read_u32(header, 12) {
+                return Err(invalid());
+            }
+            records.push((offset, payload.into()));
+            pos = start + len;
+        }
+
+        Ok(Self {
+            next_offset,
+            records,
+        })
+    }
+
+    /// Offset log pertama yang belum tercakup snapshot
+    pub fn next_offset(&self) -> u64 {
+        self.next_offset
+    }
+
+    /// Jumlah key di snapshot
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns `true` jika snapshot kosong
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Iterasi `(offset, record)` terbaru per key, urut offset
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        self.records
+            .iter()
+            .map(|(offset, record)| (*offset, &record[..]))
+    }
+}
+
+/// Job compaction incremental di atas direktori `SegmentedLog`
+pub struct Compactor<K: KeyExtractor> {
+    dir: PathBuf,
+    extractor: K,
+    // key -> (offset, record) terbaru
+    latest: HashMap<Box<[u8]>, Entry>,
+    next_offset: u64,
+    missed: u64,
+}
+
+impl<K: KeyExtractor> Compactor<K> {
+    /// Membuka compactor untuk log di `dir`, melanjutkan snapshot yang ada
+    pub fn open<P: AsRef<Path>>(dir: P, extractor: K) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let snapshot = Snapshot::read(&dir)?;
+
+        let mut latest = HashMap::with_capacity(snapshot.len());
+        for (offset, record) in snapshot.records {
+            if let Some(key) = extractor.key(&record) {
+                latest.insert(key.into(), (offset, record));
+            }
+        }
+
+        Ok(Self {
+            dir,
+            extractor,
+            latest,
+            next_offset: snapshot.next_offset,
+            missed: 0,
+        })
+    }
+
+    /// Satu putaran: proses segment tertutup yang baru lalu tulis snapshot
+    ///
+    /// Segment aktif (base offset terbesar) tidak disentuh. Returns jumlah
+    /// record yang diproses; snapshot hanya ditulis ulang jika ada.
+    pub fn run_once(&mut self) -> io::Result<usize> {
+        let bases = segment_bases(&self.dir)?;
+        let mut processed = 0;
+
+        for pair in bases.windows(2) {
+            let (base, end) = (pair[0], pair[1]);
+            if end <= self.next_offset {
+                continue;
+            }
+            // Segment di antaranya sudah dihapus sebelum diproses. Compactor
+            // baru (next_offset 0) mulai dari segment tertua tanpa gap.
+            if self.next_offset > 0 && base > self.next_offset {
+                self.missed += base - self.next_offset;
+                self.next_offset = base;
+            }
+
+            let segment = match SegmentFile::open(&self.dir, base) {
+                Ok(segment) => segment,
+                // Dihapus retention di tengah jalan
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            debug_assert_eq!(segment.base_offset(), base);
+
+            for (offset, record) in segment.records() {
+                if offset < self.next_offset {
+                    continue;
+                }
+                if let Some(key) = self.extractor.key(record) {
+                    match self.latest.get_mut(key) {
+                        Some(entry) => *entry = (offset, record.into()),
+                        None => {
+                            self.latest.insert(key.into(), (offset, record.into()));
+                        }
+                    }
+                }
+                processed += 1;
+            }
+            self.next_offset = end;
+        }
+
+        if processed > 0 {
+            self.write_snapshot()?;
+        }
+        Ok(processed)
+    }
+
+    /// Tulis snapshot secara atomic (tmp + fsync + rename)
+    fn write_snapshot(&self) -> io::Result<()> {
+        let mut records: Vec<&Entry> = self.latest.values().collect();
+        records.sort_unstable_by_key(|(offset, _)| *offset);
+
+        let mut buf = Vec::with_capacity(SNAPSHOT_HEADER_SIZE);
+        buf.extend_from_slice(&SNAPSHOT_MAGIC.to_le_bytes());
+        buf.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        buf.extend_from_slice(&0u32.to_le_bytes());
+        buf.extend_from_slice(&self.next_offset.to_le_bytes());
+        buf.extend_from_slice(&(records.len() as u64).to_le_bytes());
+        for (offset, record) in records {
+            buf.extend_from_slice(&offset.to_le_bytes());
+            buf.extend_from_slice(&(record.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&crc32c(record).to_le_bytes());
+            buf.extend_from_slice(record);
+        }
+
+        let path = Snapshot::path(&self.dir);
+        let tmp = path.with_extension("snapshot.tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&buf)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        // Rename baru durable setelah direktorinya di-sync
+        sync_dir(&self.dir)
+    }
+
+    /// Jumlah key unik yang sudah dikompaksi
+    pub fn key_count(&self) -> usize {
+        self.latest.len()
+    }
+
+    /// Offset log pertama yang belum diproses
+    pub fn next_offset(&self) -> u64 {
+        self.next_offset
+    }
+
+    /// Jumlah offset yang terlewat karena segment-nya dihapus sebelum diproses
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+}
+
+#[inline(always)]
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+#[inline(always)]
+fn read_u64(bytes: &[u8], pos: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[pos..pos + 8]);
+    u64::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::SegmentedLog;
+
+    /// Record `[key 4 bytes][value u64]`
+    fn record(key: u32, value: u64) -> Vec<u8> {
+        let mut bytes = key.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&value.to_le_bytes());
+        bytes
+    }
+
+    fn fresh_dir(name: &str) -> PathBuf {
+        let dir = PathBuf::from(name);
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    #[test]
+    fn test_compaction_keeps_latest_per_key() {
+        let dir = fresh_dir("test_compaction");
+
+        {
+            // 24 bytes per record -> 8 record per segment 256 bytes
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..40u64 {
+                log.append(&record((i % 5) as u32, i)).unwrap();
+            }
+
+            let mut compactor = Compactor::open(&dir, ByteRange(0..4)).unwrap();
+            // Segment [32, 40) masih aktif
+            assert_eq!(compactor.run_once().unwrap(), 32);
+            assert_eq!(compactor.key_count(), 5);
+            assert_eq!(compactor.next_offset(), 32);
+            assert_eq!(compactor.run_once().unwrap(), 0);
+
+            let snapshot = Snapshot::read(&dir).unwrap();
+            assert_eq!(snapshot.next_offset(), 32);
+            let latest: Vec<(u64, Vec<u8>)> =
+                snapshot.iter().map(|(o, r)| (o, r.to_vec())).collect();
+            let expected: Vec<(u64, Vec<u8>)> =
+                (27..32).map(|i| (i, record((i % 5) as u32, i))).collect();
+            assert_eq!(latest, expected);
+
+            // Bootstrap: snapshot + sisa log = state terbaru setiap key
+            let mut state = HashMap::new();
+            for (_, r) in snapshot.iter().chain(log.iter_from(snapshot.next_offset())) {
+                state.insert(r[..4].to_vec(), r.to_vec());
+            }
+            assert_eq!(state.len(), 5);
+            assert_eq!(state[&1u32.to_le_bytes().to_vec()], record(1, 36));
+
+            // Key baru + update setelah restart compactor
+            for i in 40..50u64 {
+                log.append(&record(if i == 45 { 9 } else { 2 }, i)).unwrap();
+            }
+        }
+
+        {
+            let mut compactor = Compactor::open(&dir, ByteRange(0..4)).unwrap();
+            assert_eq!(compactor.key_count(), 5);
+            assert_eq!(compactor.run_once().unwrap(), 16);
+            assert_eq!(compactor.key_count(), 6);
+
+            let snapshot = Snapshot::read(&dir).unwrap();
+            assert_eq!(snapshot.next_offset(), 48);
+            let last = snapshot.iter().last().unwrap();
+            assert_eq!(last, (47, &record(2, 47)[..]));
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_compaction_reports_segments_removed_before_processing() {
+        let dir = fresh_dir("test_compaction_gap");
+
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..40u64 {
+                log.append(&record((i % 5) as u32, i)).unwrap();
+            }
+            let mut compactor = Compactor::open(&dir, ByteRange(0..4)).unwrap();
+            assert_eq!(compactor.run_once().unwrap(), 32);
+
+            // Segment [32, 56) dihapus sebelum compactor sempat membacanya
+            for i in 40..80u64 {
+                log.append(&record((i % 5) as u32, i)).unwrap();
+            }
+            log.delete_before(56).unwrap();
+            assert_eq!(compactor.run_once().unwrap(), 16);
+            assert_eq!(compactor.missed(), 24);
+            assert_eq!(compactor.next_offset(), 72);
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_compaction_skips_keyless_and_detects_corruption() {
+        let dir = fresh_dir("test_compaction_keyless");
+
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..16u64 {
+                // Record pendek tidak punya key untuk range 0..4
+                let data = if i % 2 == 0 { record(7, i) } else { vec![1, 2] };
+                log.append(&data).unwrap();
+            }
+
+            let mut compactor = Compactor::open(&dir, ByteRange(0..4)).unwrap();
+            compactor.run_once().unwrap();
+            let snapshot = Snapshot::read(&dir).unwrap();
+            assert_eq!(snapshot.len(), 1);
+        }
+
+        let path = Snapshot::path(&dir);
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        fs::write(&path, bytes).unwrap();
+        assert!(Snapshot::read(&dir).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_payload_range_skips_topic_prefix() {
+        use crate::protocol::{Encoder, MessageType};
+
+        let key = [7u8; 32];
+        let mut data = key.to_vec();
+        data.extend_from_slice(b"value");
+
+        let mut encoder = Encoder::new(256);
+        let plain = encoder
+            .encode(MessageType::Publish, 1, &data)
+            .unwrap()
+            .to_vec();
+        encoder.reset();
+        let topic = encoder
+            .encode_topic(MessageType::Publish, 3, 2, &data)
+            .unwrap()
+            .to_vec();
+
+        let extractor = PayloadRange(0..32);
+        assert_eq!(extractor.key(&plain), Some(&key[..]));
+        assert_eq!(extractor.key(&topic), Some(&key[..]));
+        assert_eq!(PayloadRange(0..64).key(&plain), None);
+        assert_eq!(extractor.key(&data), None);
+
+        // Range mentah bergeser sebesar prefix topic id
+        assert_eq!(ByteRange(32..64).key(&plain), Some(&key[..]));
+        assert_eq!(ByteRange(36..68).key(&topic), Some(&key[..]));
+    }
+
+    #[test]
+    fn test_snapshot_versions() {
+        let dir = fresh_dir("test_compaction_versions");
+        fs::create_dir_all(&dir).unwrap();
+        let path = Snapshot::path(&dir);
+
+        // Snapshot v1: record di-checksum legacy
+        let data = record(3, 42);
+        let mut v1 = SNAPSHOT_MAGIC.to_le_bytes().to_vec();
+        v1.extend_from_slice(&SNAPSHOT_VERSION_LEGACY.to_le_bytes());
+        v1.extend_from_slice(&0u32.to_le_bytes());
+        v1.extend_from_slice(&8u64.to_le_bytes());
+        v1.extend_from_slice(&1u64.to_le_bytes());
+        v1.extend_from_slice(&5u64.to_le_bytes());
+        v1.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        v1.extend_from_slice(&legacy_checksum(&data).to_le_bytes());
+        v1.extend_from_slice(&data);
+        fs::write(&path, &v1).unwrap();
+
+        let snapshot = Snapshot::read(&dir).unwrap();
+        assert_eq!(snapshot.iter().collect::<Vec<_>>(), [(5, &data[..])]);
+
+        // Ditulis ulang sebagai versi terbaru dengan CRC-32C
+        let compactor = Compactor::open(&dir, ByteRange(0..4)).unwrap();
+        compactor.write_snapshot().unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(read_u32(&bytes, 8), SNAPSHOT_VERSION);
+        assert_eq!(read_u32(&bytes, SNAPSHOT_HEADER_SIZE + 12), crc32c(&data));
+        assert_eq!(Snapshot::read(&dir).unwrap().len(), 1);
+
+        // Versi lebih baru ditolak
+        let mut bytes = bytes;
+        bytes[8..12].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+        assert!(Snapshot::read(&dir).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
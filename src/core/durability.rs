@@ -0,0 +1,259 @@
+//! Durability / fsync policy untuk persistent storage
+//!
+//! Data di mmap baru aman dari crash OS / power loss setelah di-`msync`
+//! (atau `fdatasync` file-nya). Policy menentukan kapan itu terjadi
+//! (trade-off latency vs safety):
+//! - `None`: tidak pernah flush eksplisit, writeback sepenuhnya oleh kernel
+//! - `Interval`: `fdatasync` di thread latar paling lambat tiap interval;
+//!   data yang hilang saat crash dibatasi ~interval + durasi satu sync
+//! - `EveryMessages`: sync flush (`MS_SYNC`) setiap N pesan
+//! - `BeforeAck`: sync flush saat caller akan mengirim ack (`sync()`)
+
+use std::fs::File;
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Kapan storage di-flush ke disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsyncPolicy {
+    /// Tidak ada flush eksplisit (latency terendah)
+    #[default]
+    None,
+    /// Sync di thread latar jika sync terakhir lebih lama dari interval ini
+    Interval(Duration),
+    /// Sync flush setiap N pesan
+    EveryMessages(u64),
+    /// Sync flush hanya saat diminta sebelum ack dikirim
+    BeforeAck,
+}
+
+impl FsyncPolicy {
+    /// Returns `true` jika data harus sudah di disk sebelum segment ditutup
+    #[inline(always)]
+    pub(crate) fn is_synchronous(&self) -> bool {
+        matches!(self, Self::EveryMessages(_) | Self::BeforeAck)
+    }
+}
+
+/// Jenis flush yang diminta policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Flush {
+    /// `fdatasync` oleh `BackgroundSync`, caller tidak menunggu
+    Background,
+    /// Sync di thread caller (blocking)
+    Sync,
+}
+
+/// Pelacak pesan yang belum di-flush untuk satu storage
+#[derive(Debug)]
+pub(crate) struct SyncState {
+    policy: FsyncPolicy,
+    unsynced: u64,
+    last_flush: Instant,
+}
+
+impl SyncState {
+    pub(crate) fn new(policy: FsyncPolicy) -> Self {
+        Self {
+            policy,
+            unsynced: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn policy(&self) -> FsyncPolicy {
+        self.policy
+    }
+
+    /// Catat satu pesan baru; returns flush yang harus dilakukan sekarang
+    #[inline(always)]
+    pub(crate) fn on_append(&mut self) -> Option<Flush> {
+        self.unsynced += 1;
+        match self.policy {
+            FsyncPolicy::EveryMessages(n) if self.unsynced >= n.max(1) => Some(Flush::Sync),
+            FsyncPolicy::Interval(_) => self.on_tick(),
+            _ => None,
+        }
+    }
+
+    /// Cek periodik (untuk `Interval` saat tidak ada append baru)
+    #[inline(always)]
+    pub(crate) fn on_tick(&mut self) -> Option<Flush> {
+        match self.policy {
+            FsyncPolicy::Interval(interval)
+                if self.unsynced > 0 && self.last_flush.elapsed() >= interval =>
+            {
+                Some(Flush::Background)
+            }
+            _ => None,
+        }
+    }
+
+    /// Jumlah pesan yang belum di-flush
+    #[inline(always)]
+    pub(crate) fn unsynced(&self) -> u64 {
+        self.unsynced
+    }
+
+    /// Tandai semua pesan sudah di-flush
+    #[inline(always)]
+    pub(crate) fn flushed(&mut self) {
+        self.unsynced = 0;
+        self.last_flush = Instant::now();
+    }
+}
+
+/// Permintaan `fdatasync` untuk `BackgroundSync`
+pub(crate) struct SyncRequest {
+    /// Identitas file bagi caller (mis. base offset segment)
+    pub(crate) id: u64,
+    pub(crate) file: Arc<File>,
+    /// Posisi tulis saat request dibuat: data sebelum ini aman setelah sync
+    pub(crate) end: usize,
+}
+
+/// Hasil satu `SyncRequest`
+pub(crate) struct SyncDone {
+    pub(crate) id: u64,
+    pub(crate) end: usize,
+    pub(crate) result: io::Result<()>,
+}
+
+/// Thread latar yang menjalankan `fdatasync` agar event loop tidak blocking
+///
+/// `fdatasync` pada file juga menulis halaman shared mmap yang dirty, jadi
+/// caller cukup mengirim handle file-nya. Thread berhenti saat struct di-drop.
+pub(crate) struct BackgroundSync {
+    requests: Sender<SyncRequest>,
+    done: Receiver<SyncDone>,
+    in_flight: usize,
+}
+
+impl BackgroundSync {
+    pub(crate) fn spawn() -> io::Result<Self> {
+        let (requests, rx) = mpsc::channel::<SyncRequest>();
+        let (tx, done) = mpsc::channel();
+        thread::Builder::new()
+            .name("hermes-fsync".into())
+            .spawn(move || {
+                for request in rx {
+                    let result = request.file.sync_data();
+                    let done = SyncDone {
+                        id: request.id,
+                        end: request.end,
+                        result,
+                    };
+                    if tx.send(done).is_err() {
+                        break;
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            requests,
+            done,
+            in_flight: 0,
+        })
+    }
+
+    /// Antrekan satu sync
+    pub(crate) fn submit(&mut self, request: SyncRequest) -> io::Result<()> {
+        self.requests.send(request).map_err(|_| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "background sync thread stopped")
+        })?;
+        self.in_flight += 1;
+        Ok(())
+    }
+
+    /// Returns `true` jika tidak ada sync yang sedang berjalan
+    #[inline(always)]
+    pub(crate) fn is_idle(&self) -> bool {
+        self.in_flight == 0
+    }
+
+    /// Ambil satu hasil sync yang sudah selesai (non-blocking)
+    #[inline(always)]
+    pub(crate) fn poll(&mut self) -> Option<SyncDone> {
+        if self.in_flight == 0 {
+            return None;
+        }
+        let done = self.done.try_recv().ok()?;
+        self.in_flight -= 1;
+        Some(done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_messages_policy() {
+        let mut state = SyncState::new(FsyncPolicy::EveryMessages(3));
+        assert_eq!(state.on_append(), None);
+        assert_eq!(state.on_append(), None);
+        assert_eq!(state.on_append(), Some(Flush::Sync));
+        state.flushed();
+        assert_eq!(state.unsynced(), 0);
+        assert_eq!(state.on_append(), None);
+        assert_eq!(state.on_tick(), None);
+    }
+
+    #[test]
+    fn test_interval_policy() {
+        let mut state = SyncState::new(FsyncPolicy::Interval(Duration::from_millis(20)));
+        assert_eq!(state.on_append(), None);
+
+        std::thread::sleep(Duration::from_millis(25));
+        // Tick tanpa append baru tetap mem-flush data yang tertunda
+        assert_eq!(state.on_tick(), Some(Flush::Background));
+        state.flushed();
+        assert_eq!(state.on_tick(), None);
+    }
+
+    #[test]
+    fn test_background_sync_reports_completion() {
+        let path = std::env::temp_dir().join("hermes_background_sync.tmp");
+        let file = Arc::new(File::create(&path).unwrap());
+        let mut sync = BackgroundSync::spawn().unwrap();
+        assert!(sync.is_idle());
+
+        sync.submit(SyncRequest {
+            id: 7,
+            file,
+            end: 64,
+        })
+        .unwrap();
+        assert!(!sync.is_idle());
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let done = loop {
+            if let Some(done) = sync.poll() {
+                break done;
+            }
+            assert!(Instant::now() < deadline, "sync never completed");
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!((done.id, done.end), (7, 64));
+        assert!(done.result.is_ok());
+        assert!(sync.is_idle());
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_none_and_before_ack_never_flush_on_append() {
+        for policy in [FsyncPolicy::None, FsyncPolicy::BeforeAck] {
+            let mut state = SyncState::new(policy);
+            for _ in 0..1000 {
+                assert_eq!(state.on_append(), None);
+            }
+            assert_eq!(state.on_tick(), None);
+            assert_eq!(state.unsynced(), 1000);
+        }
+    }
+}
@@ -5,8 +5,39 @@
 //! - Lock-Free: Hanya atomic operations, tidak ada Mutex/RwLock
 //! - No-Allocation: Semua buffer pre-allocated saat init
 
+mod async_ring;
+mod broadcast;
+mod byte_ring;
+mod compaction;
+mod durability;
 mod mmap_storage;
+mod mpmc_ring;
+mod retention;
 mod ring_buffer;
+mod segmented_log;
+mod shm_ring;
+mod storage_options;
+mod subscriptions;
+mod sync;
+mod topics;
+mod wait;
 
-pub use mmap_storage::MmapStorage;
-pub use ring_buffer::RingBuffer;
+pub use async_ring::{async_ring, AsyncConsumer, AsyncProducer, Closed};
+pub use broadcast::{BroadcastReceiver, BroadcastRing, OverflowPolicy, RecvError};
+pub use byte_ring::{ByteConsumer, ByteProducer, ByteRing, ReadGuard, WriteClaim};
+pub use compaction::{ByteRange, Compactor, KeyExtractor, PayloadRange, Snapshot};
+pub use durability::FsyncPolicy;
+pub use mmap_storage::{
+    Messages, MmapStorage, RecoveryReport, StorageInfo, StoredMessage, FLAG_FRAMES,
+};
+pub use mpmc_ring::{MpmcRingBuffer, MpscRingBuffer};
+pub use retention::RetentionPolicy;
+pub use ring_buffer::{RingBuffer, RingConsumer, RingProducer};
+pub use segmented_log::{
+    LogIter, LogRecovery, RetiredSegment, SegmentFile, SegmentScan, SegmentedLog, MIN_SEGMENT_SIZE,
+};
+pub use shm_ring::{ShmConsumer, ShmProducer, ShmReadGuard, ShmWriteClaim, MAX_CONSUMERS};
+pub use storage_options::{HugePages, StorageOptions};
+pub use subscriptions::{SubscriptionTrie, TopicRouter};
+pub use topics::TopicRegistry;
+pub use wait::{BusySpin, SpinThenPark, SpinThenYield, Timeout, WaitStrategy};
@@ -0,0 +1,420 @@
+//! Lock-Free Multi-Producer Ring Buffers (MPSC & MPMC)
+//!
+//! Implementasi bounded queue ala Dmitry Vyukov: setiap slot membawa
+//! sequence number sendiri, sehingga producer (dan consumer) bisa saling
+//! berebut index dengan satu CAS tanpa Mutex.
+//! Layout head/tail sama dengan `RingBuffer` (cache-line padded),
+//! dan tidak ada alokasi setelah inisialisasi.
+
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::ring_buffer::CacheLinePadded;
+use super::wait::WaitStrategy;
+
+/// Slot dengan sequence number untuk sinkronisasi per-slot
+///
+/// - `seq == pos`      : slot kosong, siap ditulis oleh producer untuk posisi `pos`
+/// - `seq == pos + 1`  : slot berisi data untuk posisi `pos`, siap dibaca
+#[repr(C, align(64))] // Cache line alignment untuk menghindari false sharing
+struct Slot<T> {
+    seq: AtomicUsize,
+    data: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// Inti bersama MPSC/MPMC: producer side selalu multi-producer
+#[repr(C)]
+struct SeqRing<T, const N: usize> {
+    // Producer side - cache line aligned
+    head: CacheLinePadded<AtomicUsize>,
+    // Consumer side - cache line aligned
+    tail: CacheLinePadded<AtomicUsize>,
+    // Pre-allocated buffer di heap - tidak ada alokasi setelah init
+    buffer: Box<[Slot<T>]>,
+    // Mask untuk operasi modulo yang cepat (N harus power of 2)
+    mask: usize,
+}
+
+impl<T: Copy, const N: usize> SeqRing<T, N> {
+    fn new() -> Self {
+        assert!(N > 0 && N.is_power_of_two(), "N must be power of 2");
+
+        let mut buffer = Vec::with_capacity(N);
+        for i in 0..N {
+            buffer.push(Slot {
+                seq: AtomicUsize::new(i),
+                data: UnsafeCell::new(MaybeUninit::uninit()),
+            });
+        }
+
+        Self {
+            head: CacheLinePadded::new(AtomicUsize::new(0)),
+            tail: CacheLinePadded::new(AtomicUsize::new(0)),
+            buffer: buffer.into_boxed_slice(),
+            mask: N - 1,
+        }
+    }
+
+    /// Multi-producer push: klaim posisi `head` via CAS
+    #[inline(always)]
+    fn push(&self, value: T) -> bool {
+        let mut head = self.head.value.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.buffer[head & self.mask];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(head) as isize;
+
+            if diff == 0 {
+                // Slot kosong untuk posisi ini - coba klaim
+                match self.head.value.compare_exchange_weak(
+                    head,
+                    head.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: CAS sukses memberi kita kepemilikan eksklusif atas slot ini
+                        unsafe {
+                            (*slot.data.get()).write(value);
+                        }
+                        // Release: data visible sebelum slot ditandai terisi
+                        slot.seq.store(head.wrapping_add(1), Ordering::Release);
+                        return true;
+                    }
+                    Err(current) => head = current,
+                }
+            } else if diff < 0 {
+                // Slot masih berisi data dari putaran sebelumnya - buffer penuh
+                return false;
+            } else {
+                // Producer lain sudah mengklaim posisi ini, ambil head terbaru
+                head = self.head.value.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Single-consumer pop: tidak perlu CAS pada `tail`
+    #[inline(always)]
+    fn pop_single(&self) -> Option<T> {
+        let tail = self.tail.value.load(Ordering::Relaxed);
+        let slot = &self.buffer[tail & self.mask];
+        let seq = slot.seq.load(Ordering::Acquire);
+
+        if seq != tail.wrapping_add(1) {
+            return None;
+        }
+
+        // SAFETY: seq == tail + 1 berarti producer sudah selesai menulis slot ini
+        let value = unsafe { (*slot.data.get()).assume_init_read() };
+
+        // Release: read selesai sebelum slot dikembalikan ke producer
+        slot.seq.store(tail.wrapping_add(N), Ordering::Release);
+        self.tail
+            .value
+            .store(tail.wrapping_add(1), Ordering::Release);
+
+        Some(value)
+    }
+
+    /// Multi-consumer pop: klaim posisi `tail` via CAS
+    #[inline(always)]
+    fn pop_multi(&self) -> Option<T> {
+        let mut tail = self.tail.value.load(Ordering::Relaxed);
+
+        loop {
+            let slot = &self.buffer[tail & self.mask];
+            let seq = slot.seq.load(Ordering::Acquire);
+            let diff = seq.wrapping_sub(tail.wrapping_add(1)) as isize;
+
+            if diff == 0 {
+                match self.tail.value.compare_exchange_weak(
+                    tail,
+                    tail.wrapping_add(1),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        // SAFETY: CAS sukses memberi kita kepemilikan eksklusif atas slot ini
+                        let value = unsafe { (*slot.data.get()).assume_init_read() };
+                        slot.seq.store(tail.wrapping_add(N), Ordering::Release);
+                        return Some(value);
+                    }
+                    Err(current) => tail = current,
+                }
+            } else if diff < 0 {
+                // Slot belum ditulis - buffer kosong
+                return None;
+            } else {
+                // Consumer lain sudah mengambil posisi ini
+                tail = self.tail.value.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn len(&self) -> usize {
+        let tail = self.tail.value.load(Ordering::Acquire);
+        let head = self.head.value.load(Ordering::Acquire);
+        // Snapshot bisa sedikit stale di bawah contention, clamp ke [0, N]
+        (head.wrapping_sub(tail) as isize).clamp(0, N as isize) as usize
+    }
+}
+
+/// Lock-Free MPSC Ring Buffer
+///
+/// Banyak producer thread boleh memanggil `push` bersamaan,
+/// tetapi hanya SATU consumer yang boleh memanggil `pop`.
+pub struct MpscRingBuffer<T, const N: usize> {
+    ring: SeqRing<T, N>,
+}
+
+/// Lock-Free MPMC Ring Buffer
+///
+/// Banyak producer dan banyak consumer boleh memanggil `push`/`pop` bersamaan.
+pub struct MpmcRingBuffer<T, const N: usize> {
+    ring: SeqRing<T, N>,
+}
+
+// SAFETY: Akses ke setiap slot diserialisasi oleh sequence number per-slot:
+// hanya pemenang CAS yang menulis/membaca slot tersebut.
+unsafe impl<T: Send, const N: usize> Send for MpscRingBuffer<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for MpscRingBuffer<T, N> {}
+unsafe impl<T: Send, const N: usize> Send for MpmcRingBuffer<T, N> {}
+unsafe impl<T: Send, const N: usize> Sync for MpmcRingBuffer<T, N> {}
+
+macro_rules! impl_multi_ring {
+    ($name:ident, $pop:ident) => {
+        impl<T: Copy, const N: usize> Default for $name<T, N> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T: Copy, const N: usize> $name<T, N> {
+            /// Membuat ring buffer baru. N HARUS power of 2.
+            ///
+            /// Alokasi hanya terjadi sekali saat inisialisasi.
+            ///
+            /// # Panics
+            /// Panic jika N bukan power of 2 atau N == 0
+            pub fn new() -> Self {
+                Self {
+                    ring: SeqRing::new(),
+                }
+            }
+
+            /// Push data ke buffer (boleh dari banyak producer)
+            ///
+            /// Returns `true` jika berhasil, `false` jika buffer penuh.
+            #[inline(always)]
+            pub fn push(&self, value: T) -> bool {
+                self.ring.push(value)
+            }
+
+            /// Pop data dari buffer
+            ///
+            /// Returns `Some(T)` jika ada data, `None` jika buffer kosong.
+            #[inline(always)]
+            pub fn pop(&self) -> Option<T> {
+                self.ring.$pop()
+            }
+
+            /// Push dengan menunggu saat buffer penuh
+            ///
+            /// Returns `false` jika `wait` menyerah (timeout).
+            #[inline]
+            pub fn push_blocking<W: WaitStrategy>(&self, value: T, wait: &W) -> bool {
+                let pushed = wait.wait_until(|| self.push(value).then_some(())).is_some();
+                if pushed {
+                    wait.signal();
+                }
+                pushed
+            }
+
+            /// Pop dengan menunggu saat buffer kosong
+            ///
+            /// Returns `None` jika `wait` menyerah (timeout).
+            #[inline]
+            pub fn pop_blocking<W: WaitStrategy>(&self, wait: &W) -> Option<T> {
+                let value = wait.wait_until(|| self.pop());
+                if value.is_some() {
+                    wait.signal();
+                }
+                value
+            }
+
+            /// Cek apakah buffer kosong (snapshot)
+            #[inline(always)]
+            pub fn is_empty(&self) -> bool {
+                self.ring.len() == 0
+            }
+
+            /// Cek apakah buffer penuh (snapshot)
+            #[inline(always)]
+            pub fn is_full(&self) -> bool {
+                self.ring.len() >= N
+            }
+
+            /// Jumlah elemen dalam buffer (snapshot)
+            #[inline(always)]
+            pub fn len(&self) -> usize {
+                self.ring.len()
+            }
+
+            /// Kapasitas buffer
+            #[inline(always)]
+            pub const fn capacity(&self) -> usize {
+                N
+            }
+        }
+    };
+}
+
+impl_multi_ring!(MpscRingBuffer, pop_single);
+impl_multi_ring!(MpmcRingBuffer, pop_multi);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    const PRODUCERS: usize = 4;
+    const PER_PRODUCER: u64 = 20_000;
+
+    /// Encode (producer id, sequence) dalam satu u64
+    fn tag(producer: usize, i: u64) -> u64 {
+        ((producer as u64) << 32) | i
+    }
+
+    #[test]
+    fn test_basic_push_pop() {
+        let rb: MpmcRingBuffer<u64, 16> = MpmcRingBuffer::new();
+
+        assert!(rb.is_empty());
+        assert!(rb.push(42));
+        assert_eq!(rb.len(), 1);
+        assert_eq!(rb.pop(), Some(42));
+        assert!(rb.is_empty());
+        assert_eq!(rb.pop(), None);
+    }
+
+    #[test]
+    fn test_full_and_wraparound() {
+        let rb: MpscRingBuffer<u64, 4> = MpscRingBuffer::new();
+
+        for round in 0..10 {
+            for i in 0..4 {
+                assert!(rb.push(round * 4 + i));
+            }
+            assert!(rb.is_full());
+            assert!(!rb.push(999)); // Should fail - buffer full
+
+            for i in 0..4 {
+                assert_eq!(rb.pop(), Some(round * 4 + i));
+            }
+            assert_eq!(rb.pop(), None);
+        }
+    }
+
+    #[test]
+    fn test_mpsc_many_producers() {
+        let rb: Arc<MpscRingBuffer<u64, 1024>> = Arc::new(MpscRingBuffer::new());
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let rb = Arc::clone(&rb);
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        while !rb.push(tag(p, i)) {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        // Single consumer: urutan per-producer harus tetap FIFO
+        let mut next = [0u64; PRODUCERS];
+        let mut received = 0u64;
+        while received < PRODUCERS as u64 * PER_PRODUCER {
+            match rb.pop() {
+                Some(v) => {
+                    let p = (v >> 32) as usize;
+                    assert_eq!(v & 0xFFFF_FFFF, next[p], "producer {} out of order", p);
+                    next[p] += 1;
+                    received += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+
+        for handle in producers {
+            handle.join().unwrap();
+        }
+        assert!(rb.is_empty());
+        assert!(next.iter().all(|&n| n == PER_PRODUCER));
+    }
+
+    #[test]
+    fn test_mpmc_many_producers_many_consumers() {
+        const CONSUMERS: usize = 4;
+        let rb: Arc<MpmcRingBuffer<u64, 256>> = Arc::new(MpmcRingBuffer::new());
+        let total = PRODUCERS as u64 * PER_PRODUCER;
+        let consumed = Arc::new(AtomicUsize::new(0));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|p| {
+                let rb = Arc::clone(&rb);
+                thread::spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        while !rb.push(tag(p, i)) {
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let rb = Arc::clone(&rb);
+                let consumed = Arc::clone(&consumed);
+                thread::spawn(move || {
+                    let mut seen = Vec::new();
+                    while consumed.load(Ordering::Relaxed) < total as usize {
+                        match rb.pop() {
+                            Some(v) => {
+                                seen.push(v);
+                                consumed.fetch_add(1, Ordering::Relaxed);
+                            }
+                            None => thread::yield_now(),
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        for handle in producers {
+            handle.join().unwrap();
+        }
+
+        // Setiap nilai harus diterima tepat satu kali
+        let mut all: Vec<u64> = consumers
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect();
+        all.sort_unstable();
+
+        let mut expected: Vec<u64> = (0..PRODUCERS)
+            .flat_map(|p| (0..PER_PRODUCER).map(move |i| tag(p, i)))
+            .collect();
+        expected.sort_unstable();
+
+        assert_eq!(all, expected);
+        assert!(rb.is_empty());
+    }
+}
@@ -0,0 +1,126 @@
+//! Retention policy untuk `SegmentedLog`
+//!
+//! Data lama dibuang per segment utuh (segment aktif tidak pernah dihapus):
+//! - umur: frame terbaru di segment lebih tua dari `max_age`
+//!   (berdasarkan `MessageHeader::timestamp_ns`)
+//! - ukuran: total bytes segment di atas `max_bytes`
+//! - jumlah: total record di atas `max_messages`
+//!
+//! Batas ukuran/jumlah dijaga sebagai minimum yang disimpan: segment tertua
+//! hanya dihapus jika sisanya masih memenuhi batas, sehingga log bisa melebihi
+//! batas paling banyak satu segment.
+
+use std::time::Duration;
+
+/// Batas retensi log; `None` = tidak dibatasi
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_bytes: Option<u64>,
+    pub max_messages: Option<u64>,
+}
+
+/// Ringkasan satu segment untuk keputusan retensi
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SegmentSummary {
+    pub(crate) bytes: u64,
+    pub(crate) messages: u64,
+    /// Timestamp frame Hermes terakhir (None jika tidak ada frame)
+    pub(crate) last_timestamp_ns: Option<u64>,
+}
+
+impl RetentionPolicy {
+    /// Returns `true` jika tidak ada batas sama sekali
+    #[inline(always)]
+    pub fn is_unbounded(&self) -> bool {
+        self.max_age.is_none() && self.max_bytes.is_none() && self.max_messages.is_none()
+    }
+
+    /// Jumlah segment tertua yang sudah lewat retensi
+    ///
+    /// `segments` urut dari yang tertua; elemen terakhir (segment aktif)
+    /// tidak pernah dihitung.
+    pub(crate) fn expired(&self, segments: &[SegmentSummary], now_ns: u64) -> usize {
+        let mut bytes: u64 = segments.iter().map(|s| s.bytes).sum();
+        let mut messages: u64 = segments.iter().map(|s| s.messages).sum();
+        let cutoff_ns = self
+            .max_age
+            .map(|age| now_ns.saturating_sub(age.as_nanos().min(u64::MAX as u128) as u64));
+
+        let mut expired = 0;
+        for segment in &segments[..segments.len().saturating_sub(1)] {
+            let too_old = matches!(
+                (cutoff_ns, segment.last_timestamp_ns),
+                (Some(cutoff), Some(ts)) if ts < cutoff
+            );
+            let too_big = self
+                .max_bytes
+                .is_some_and(|max| bytes - segment.bytes >= max);
+            let too_many = self
+                .max_messages
+                .is_some_and(|max| messages - segment.messages >= max);
+
+            if !(too_old || too_big || too_many) {
+                break;
+            }
+            bytes -= segment.bytes;
+            messages -= segment.messages;
+            expired += 1;
+        }
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1_000_000_000;
+
+    /// 4 segment @ 100 bytes / 10 record, frame terakhir di detik 10, 20, 30, 40
+    fn segments() -> Vec<SegmentSummary> {
+        (1..=4)
+            .map(|i| SegmentSummary {
+                bytes: 100,
+                messages: 10,
+                last_timestamp_ns: Some(i * 10 * SECOND),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_retention_by_age() {
+        let policy = RetentionPolicy {
+            max_age: Some(Duration::from_secs(15)),
+            ..Default::default()
+        };
+        // Cutoff detik 30: segment 10s dan 20s kadaluarsa
+        assert_eq!(policy.expired(&segments(), 45 * SECOND), 2);
+        // Segment aktif tidak pernah dihapus walau sudah tua
+        assert_eq!(policy.expired(&segments(), 1000 * SECOND), 3);
+
+        // Segment tanpa frame Hermes tidak kadaluarsa karena umur
+        let mut no_frames = segments();
+        no_frames[0].last_timestamp_ns = None;
+        assert_eq!(policy.expired(&no_frames, 45 * SECOND), 0);
+    }
+
+    #[test]
+    fn test_retention_by_bytes_and_count() {
+        let bytes = RetentionPolicy {
+            max_bytes: Some(250),
+            ..Default::default()
+        };
+        // 400 -> 300 (>= 250) -> berhenti: menghapus lagi menyisakan 200
+        assert_eq!(bytes.expired(&segments(), 0), 1);
+
+        let count = RetentionPolicy {
+            max_messages: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(count.expired(&segments(), 0), 3);
+
+        assert!(RetentionPolicy::default().is_unbounded());
+        assert_eq!(RetentionPolicy::default().expired(&segments(), u64::MAX), 0);
+    }
+}
@@ -2,10 +2,25 @@
 //!
 //! Implementasi menggunakan Lamport Queue dengan memory ordering yang tepat.
 //! Tidak ada Mutex, tidak ada alokasi setelah inisialisasi.
+//!
+//! Kapasitas bisa fixed saat compile time (`RingBuffer<T, 1024>`) atau
+//! ditentukan saat runtime (`RingBuffer::<T>::with_capacity(n)`); keduanya
+//! memakai kode push/pop yang sama dan tetap memakai mask power of 2.
+//!
+//! Push dan pop lewat handle dari `split(&mut self)`: `RingProducer` dan
+//! `RingConsumer` masing-masing tunggal, jadi dua thread tidak bisa pop
+//! slot yang sama.
+//!
+//! Elemen boleh bertipe apa saja (`Box<[u8]>`, handle, dll). Elemen yang
+//! belum di-consume saat buffer di-drop ikut di-drop. Test drop/leak di modul
+//! ini dijalankan di bawah Miri (`cargo +nightly miri test --lib ring_buffer`,
+//! lihat README); test yang sengaja me-leak memory di-skip.
 
-use std::cell::UnsafeCell;
 use std::mem::MaybeUninit;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::Ordering;
+
+use super::sync::{AtomicUsize, UnsafeCell};
+use super::wait::WaitStrategy;
 
 /// Slot dalam ring buffer - menyimpan data dengan ukuran tetap
 #[repr(C, align(64))] // Cache line alignment untuk menghindari false sharing
@@ -14,7 +29,7 @@ struct Slot<T> {
 }
 
 impl<T> Slot<T> {
-    const fn new() -> Self {
+    fn new() -> Self {
         Self {
             data: UnsafeCell::new(MaybeUninit::uninit()),
         }
@@ -25,57 +40,70 @@ impl<T> Slot<T> {
 ///
 /// Menggunakan separate cache lines untuk head dan tail
 /// untuk menghindari false sharing antara producer dan consumer.
+///
+/// `N == 0` (default) berarti kapasitas runtime, lihat [`RingBuffer::with_capacity`].
 #[repr(C)]
-pub struct RingBuffer<T, const N: usize> {
+pub struct RingBuffer<T, const N: usize = 0> {
     // Producer side - cache line aligned
     head: CacheLinePadded<AtomicUsize>,
     // Consumer side - cache line aligned
     tail: CacheLinePadded<AtomicUsize>,
     // Pre-allocated buffer di heap - tidak ada alokasi setelah init
     buffer: Box<[Slot<T>]>,
-    // Mask untuk operasi modulo yang cepat (N harus power of 2)
+    // Mask untuk operasi modulo yang cepat (kapasitas harus power of 2)
     mask: usize,
 }
 
 /// Padding untuk cache line isolation (64 bytes pada x86-64)
 #[repr(C, align(64))]
-struct CacheLinePadded<T> {
-    value: T,
+pub(crate) struct CacheLinePadded<T> {
+    pub(crate) value: T,
 }
 
 impl<T> CacheLinePadded<T> {
-    const fn new(value: T) -> Self {
+    pub(crate) const fn new(value: T) -> Self {
         Self { value }
     }
 }
 
-// SAFETY: RingBuffer aman untuk Send/Sync karena:
-// - Hanya satu producer (menulis head)
-// - Hanya satu consumer (menulis tail)
-// - Atomic operations menjamin visibility
+// SAFETY: Lewat `&RingBuffer` hanya operasi read-only yang safe; push/pop
+// butuh handle dari `split(&mut self)` sehingga producer dan consumer
+// masing-masing tunggal. Atomic operations menjamin visibility.
 unsafe impl<T: Send, const N: usize> Send for RingBuffer<T, N> {}
 unsafe impl<T: Send, const N: usize> Sync for RingBuffer<T, N> {}
 
-impl<T: Copy, const N: usize> Default for RingBuffer<T, N> {
+/// Hanya untuk kapasitas compile-time: `RingBuffer<T>::default()` (N == 0)
+/// ditolak saat compile, pakai `with_capacity`.
+impl<T, const N: usize> Default for RingBuffer<T, N> {
     fn default() -> Self {
+        let () = Self::CONST_CAPACITY;
         Self::new()
     }
 }
 
-impl<T: Copy, const N: usize> RingBuffer<T, N> {
+impl<T, const N: usize> RingBuffer<T, N> {
+    /// Dievaluasi saat monomorphization: error compile jika N tidak valid
+    const CONST_CAPACITY: () = assert!(
+        N > 0 && N.is_power_of_two(),
+        "RingBuffer<T, N> needs N to be a power of 2; use with_capacity for RingBuffer<T>"
+    );
+
     /// Membuat ring buffer baru. N HARUS power of 2.
     ///
     /// Alokasi hanya terjadi sekali saat inisialisasi.
     /// Setelah itu, tidak ada alokasi di hot path.
     ///
     /// # Panics
-    /// Panic jika N bukan power of 2 atau N == 0
+    /// Panic jika N bukan power of 2 atau N == 0 (pakai `with_capacity` untuk runtime size)
     pub fn new() -> Self {
         assert!(N > 0 && N.is_power_of_two(), "N must be power of 2");
+        Self::allocate(N)
+    }
 
+    fn allocate(capacity: usize) -> Self {
         // Alokasi buffer di heap untuk menghindari stack overflow
-        let mut buffer = Vec::with_capacity(N);
-        for _ in 0..N {
+        let mut buffer = Vec::with_capacity(capacity);
+        for _ in 0..capacity {
             buffer.push(Slot::new());
         }
 
@@ -83,45 +111,67 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
             head: CacheLinePadded::new(AtomicUsize::new(0)),
             tail: CacheLinePadded::new(AtomicUsize::new(0)),
             buffer: buffer.into_boxed_slice(),
-            mask: N - 1,
+            mask: capacity - 1,
+        }
+    }
+
+    /// Kapasitas efektif: konstanta N, atau dari mask untuk runtime size
+    ///
+    /// Untuk N > 0 branch ini hilang saat compile, jadi tidak ada
+    /// biaya tambahan di hot path versi const-generic.
+    #[inline(always)]
+    const fn cap(&self) -> usize {
+        if N == 0 {
+            self.mask + 1
+        } else {
+            N
         }
     }
 
+    /// Pecah buffer menjadi handle producer dan consumer
+    ///
+    /// `&mut self` menjamin masing-masing sisi hanya punya satu handle.
+    /// Kedua handle bisa dipindah ke thread berbeda (mis. `thread::scope`).
+    pub fn split(&mut self) -> (RingProducer<'_, T, N>, RingConsumer<'_, T, N>) {
+        let ring: &RingBuffer<T, N> = self;
+        (RingProducer { ring }, RingConsumer { ring })
+    }
+
     /// Push data ke buffer (Producer side)
     ///
-    /// Returns `true` jika berhasil, `false` jika buffer penuh.
-    /// Zero-allocation, lock-free.
+    /// # Safety
+    /// Hanya satu thread yang boleh menjalankan operasi producer pada satu waktu.
     #[inline(always)]
-    pub fn push(&self, value: T) -> bool {
+    pub(crate) unsafe fn try_push(&self, value: T) -> Result<(), T> {
         let head = self.head.value.load(Ordering::Relaxed);
         let tail = self.tail.value.load(Ordering::Acquire);
 
         // Cek apakah buffer penuh
-        if head.wrapping_sub(tail) >= N {
-            return false;
+        if head.wrapping_sub(tail) >= self.cap() {
+            return Err(value);
         }
 
         let slot = &self.buffer[head & self.mask];
 
         // SAFETY: Kita sudah memastikan slot ini tidak sedang dibaca
-        unsafe {
-            (*slot.data.get()).write(value);
-        }
+        slot.data.with_mut(|ptr| unsafe {
+            (*ptr).write(value);
+        });
 
         // Release fence: pastikan write di atas visible sebelum head di-update
         self.head
             .value
             .store(head.wrapping_add(1), Ordering::Release);
 
-        true
+        Ok(())
     }
 
     /// Pop data dari buffer (Consumer side)
     ///
-    /// Returns `Some(T)` jika ada data, `None` jika buffer kosong.
-    /// Zero-allocation, lock-free.
+    /// # Safety
+    /// Hanya satu thread yang boleh menjalankan operasi consumer pada satu waktu.
     #[inline(always)]
-    pub fn pop(&self) -> Option<T> {
+    pub(crate) unsafe fn pop(&self) -> Option<T> {
         let tail = self.tail.value.load(Ordering::Relaxed);
         let head = self.head.value.load(Ordering::Acquire);
 
@@ -133,7 +183,7 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
         let slot = &self.buffer[tail & self.mask];
 
         // SAFETY: Kita sudah memastikan slot ini sudah ditulis dan tidak sedang ditulis
-        let value = unsafe { (*slot.data.get()).assume_init_read() };
+        let value = slot.data.with(|ptr| unsafe { (*ptr).assume_init_read() });
 
         // Release fence: pastikan read di atas selesai sebelum tail di-update
         self.tail
@@ -143,6 +193,107 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
         Some(value)
     }
 
+    /// Pop banyak data sekaligus ke `out` (Consumer side)
+    ///
+    /// # Safety
+    /// Hanya satu thread yang boleh menjalankan operasi consumer pada satu waktu.
+    #[inline(always)]
+    pub(crate) unsafe fn pop_into(&self, out: &mut [T]) -> usize {
+        let tail = self.tail.value.load(Ordering::Relaxed);
+        let head = self.head.value.load(Ordering::Acquire);
+
+        let count = head.wrapping_sub(tail).min(out.len());
+
+        for (i, dst) in out[..count].iter_mut().enumerate() {
+            let slot = &self.buffer[tail.wrapping_add(i) & self.mask];
+            // SAFETY: Semua slot dalam [tail, tail + count) sudah ditulis
+            *dst = slot.data.with(|ptr| unsafe { (*ptr).assume_init_read() });
+        }
+
+        if count > 0 {
+            self.tail
+                .value
+                .store(tail.wrapping_add(count), Ordering::Release);
+        }
+
+        count
+    }
+
+    /// Bangun nilai langsung di dalam slot (Producer side)
+    ///
+    /// # Safety
+    /// Hanya satu thread yang boleh menjalankan operasi producer pada satu waktu.
+    #[inline(always)]
+    pub(crate) unsafe fn write_with<F>(&self, f: F) -> bool
+    where
+        F: FnOnce(&mut MaybeUninit<T>) -> &mut T,
+    {
+        let head = self.head.value.load(Ordering::Relaxed);
+        let tail = self.tail.value.load(Ordering::Acquire);
+
+        if head.wrapping_sub(tail) >= self.cap() {
+            return false;
+        }
+
+        let slot = &self.buffer[head & self.mask];
+
+        let initialized = slot.data.with_mut(|ptr| {
+            // SAFETY: Slot ini kosong dan tidak sedang dibaca consumer
+            let uninit = unsafe { &mut *ptr };
+            let slot_ptr = uninit.as_ptr();
+            let init = f(uninit);
+            // Satu-satunya cara safe mendapatkan &mut T ke slot adalah `write`,
+            // jadi pointer yang sama membuktikan slot sudah terisi.
+            std::ptr::eq(init, slot_ptr)
+        });
+        assert!(
+            initialized,
+            "write_with closure must initialize the given slot"
+        );
+
+        // Release fence: pastikan write di atas visible sebelum head di-update
+        self.head
+            .value
+            .store(head.wrapping_add(1), Ordering::Release);
+
+        true
+    }
+
+    /// Inspect nilai langsung di dalam slot lalu consume (Consumer side)
+    ///
+    /// # Safety
+    /// Hanya satu thread yang boleh menjalankan operasi consumer pada satu waktu.
+    #[inline(always)]
+    pub(crate) unsafe fn read_with<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        let tail = self.tail.value.load(Ordering::Relaxed);
+        let head = self.head.value.load(Ordering::Acquire);
+
+        if tail == head {
+            return None;
+        }
+
+        let slot = &self.buffer[tail & self.mask];
+
+        // SAFETY: Slot ini sudah ditulis dan tidak sedang ditulis;
+        // nilai di-drop di tempat sebelum slot dikembalikan ke producer
+        let result = slot.data.with_mut(|ptr| unsafe {
+            let value = &mut *ptr;
+            let result = f(value.assume_init_ref());
+            value.assume_init_drop();
+            result
+        });
+
+        // Release fence: pastikan read di atas selesai sebelum tail di-update
+        self.tail
+            .value
+            .store(tail.wrapping_add(1), Ordering::Release);
+
+        Some(result)
+    }
+
     /// Cek apakah buffer kosong
     #[inline(always)]
     #[allow(dead_code)]
@@ -158,7 +309,7 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
     pub fn is_full(&self) -> bool {
         let head = self.head.value.load(Ordering::Acquire);
         let tail = self.tail.value.load(Ordering::Acquire);
-        head.wrapping_sub(tail) >= N
+        head.wrapping_sub(tail) >= self.cap()
     }
 
     /// Jumlah elemen dalam buffer
@@ -174,7 +325,253 @@ impl<T: Copy, const N: usize> RingBuffer<T, N> {
     #[inline(always)]
     #[allow(dead_code)]
     pub const fn capacity(&self) -> usize {
-        N
+        self.cap()
+    }
+}
+
+impl<T> RingBuffer<T> {
+    /// Membuat ring buffer dengan kapasitas runtime (misalnya dari config)
+    ///
+    /// Kapasitas dibulatkan ke atas ke power of 2 agar indexing tetap
+    /// memakai mask; `capacity()` mengembalikan nilai setelah pembulatan.
+    ///
+    /// # Panics
+    /// Panic jika `capacity == 0` atau pembulatannya melebihi `usize::MAX`
+    pub fn with_capacity(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be non-zero");
+        let capacity = capacity
+            .checked_next_power_of_two()
+            .expect("capacity is too large to round up to a power of 2");
+        Self::allocate(capacity)
+    }
+}
+
+impl<T: Clone, const N: usize> RingBuffer<T, N> {
+    /// Push banyak data sekaligus (Producer side)
+    ///
+    /// # Safety
+    /// Hanya satu thread yang boleh menjalankan operasi producer pada satu waktu.
+    #[inline(always)]
+    pub(crate) unsafe fn push_slice(&self, values: &[T]) -> usize {
+        let head = self.head.value.load(Ordering::Relaxed);
+        let tail = self.tail.value.load(Ordering::Acquire);
+
+        let free = self.cap() - head.wrapping_sub(tail);
+        let count = free.min(values.len());
+
+        for (i, value) in values[..count].iter().enumerate() {
+            let slot = &self.buffer[head.wrapping_add(i) & self.mask];
+            // SAFETY: Semua slot dalam [head, head + count) kosong
+            slot.data.with_mut(|ptr| unsafe {
+                (*ptr).write(value.clone());
+            });
+        }
+
+        if count > 0 {
+            self.head
+                .value
+                .store(head.wrapping_add(count), Ordering::Release);
+        }
+
+        count
+    }
+}
+
+/// Sisi producer dari [`RingBuffer::split`]
+pub struct RingProducer<'a, T, const N: usize = 0> {
+    ring: &'a RingBuffer<T, N>,
+}
+
+impl<'a, T, const N: usize> RingProducer<'a, T, N> {
+    /// Push data ke buffer
+    ///
+    /// Returns `Err(value)` jika buffer penuh, sehingga ownership kembali ke caller.
+    /// Zero-allocation, lock-free.
+    #[inline(always)]
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        // SAFETY: split() hanya membuat satu producer per buffer
+        unsafe { self.ring.try_push(value) }
+    }
+
+    /// Bangun nilai langsung di dalam slot (zero-copy)
+    ///
+    /// Closure menerima slot kosong dan harus mengembalikan referensi
+    /// hasil `slot.write(..)`, misalnya `tx.write_with(|slot| slot.write(msg))`.
+    /// Returns `true` jika berhasil, `false` jika buffer penuh.
+    ///
+    /// # Panics
+    /// Panic jika closure mengembalikan referensi selain slot yang diberikan
+    #[inline(always)]
+    pub fn write_with<F>(&mut self, f: F) -> bool
+    where
+        F: FnOnce(&mut MaybeUninit<T>) -> &mut T,
+    {
+        // SAFETY: split() hanya membuat satu producer per buffer
+        unsafe { self.ring.write_with(f) }
+    }
+
+    /// Push dengan menunggu saat buffer penuh
+    ///
+    /// Memanggil `wait.signal()` setelah berhasil agar consumer yang
+    /// sedang park bangun. Returns `false` jika `wait` menyerah (timeout).
+    #[inline]
+    pub fn push_blocking<W: WaitStrategy>(&mut self, value: T, wait: &W) -> bool {
+        let mut pending = Some(value);
+        let pushed = wait
+            .wait_until(|| {
+                let value = pending.take()?;
+                match self.try_push(value) {
+                    Ok(()) => Some(()),
+                    Err(value) => {
+                        pending = Some(value);
+                        None
+                    }
+                }
+            })
+            .is_some();
+        if pushed {
+            wait.signal();
+        }
+        pushed
+    }
+
+    /// Cek apakah buffer penuh
+    #[inline(always)]
+    pub fn is_full(&self) -> bool {
+        self.ring.is_full()
+    }
+
+    /// Jumlah elemen dalam buffer
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Cek apakah buffer kosong
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    /// Kapasitas buffer
+    #[inline(always)]
+    pub const fn capacity(&self) -> usize {
+        self.ring.cap()
+    }
+}
+
+impl<'a, T: Copy, const N: usize> RingProducer<'a, T, N> {
+    /// Push data ke buffer
+    ///
+    /// Returns `true` jika berhasil, `false` jika buffer penuh.
+    /// Zero-allocation, lock-free.
+    #[inline(always)]
+    pub fn push(&mut self, value: T) -> bool {
+        self.try_push(value).is_ok()
+    }
+}
+
+impl<'a, T: Clone, const N: usize> RingProducer<'a, T, N> {
+    /// Push banyak data sekaligus
+    ///
+    /// Menulis sebanyak mungkin elemen dari `values` yang muat,
+    /// lalu publish semuanya dengan SATU Release store.
+    /// Returns jumlah elemen yang berhasil di-push.
+    #[inline(always)]
+    pub fn push_slice(&mut self, values: &[T]) -> usize {
+        // SAFETY: split() hanya membuat satu producer per buffer
+        unsafe { self.ring.push_slice(values) }
+    }
+}
+
+/// Sisi consumer dari [`RingBuffer::split`]
+pub struct RingConsumer<'a, T, const N: usize = 0> {
+    ring: &'a RingBuffer<T, N>,
+}
+
+impl<'a, T, const N: usize> RingConsumer<'a, T, N> {
+    /// Pop data dari buffer
+    ///
+    /// Returns `Some(T)` jika ada data, `None` jika buffer kosong.
+    /// Zero-allocation, lock-free.
+    #[inline(always)]
+    pub fn pop(&mut self) -> Option<T> {
+        // SAFETY: split() hanya membuat satu consumer per buffer
+        unsafe { self.ring.pop() }
+    }
+
+    /// Pop banyak data sekaligus ke `out`
+    ///
+    /// Membaca sampai `out.len()` elemen, lalu melepas semua slot
+    /// dengan SATU Release store.
+    /// Returns jumlah elemen yang ditulis ke `out`.
+    #[inline(always)]
+    pub fn pop_into(&mut self, out: &mut [T]) -> usize {
+        // SAFETY: split() hanya membuat satu consumer per buffer
+        unsafe { self.ring.pop_into(out) }
+    }
+
+    /// Inspect nilai langsung di dalam slot lalu consume (zero-copy)
+    ///
+    /// Returns `Some(R)` hasil closure, atau `None` jika buffer kosong.
+    #[inline(always)]
+    pub fn read_with<F, R>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        // SAFETY: split() hanya membuat satu consumer per buffer
+        unsafe { self.ring.read_with(f) }
+    }
+
+    /// Pop dengan menunggu saat buffer kosong
+    ///
+    /// Memanggil `wait.signal()` setelah berhasil agar producer yang
+    /// sedang park bangun. Returns `None` jika `wait` menyerah (timeout).
+    #[inline]
+    pub fn pop_blocking<W: WaitStrategy>(&mut self, wait: &W) -> Option<T> {
+        let value = wait.wait_until(|| self.pop());
+        if value.is_some() {
+            wait.signal();
+        }
+        value
+    }
+
+    /// Cek apakah buffer kosong
+    #[inline(always)]
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    /// Jumlah elemen dalam buffer
+    #[inline(always)]
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Kapasitas buffer
+    #[inline(always)]
+    pub const fn capacity(&self) -> usize {
+        self.ring.cap()
+    }
+}
+
+impl<T, const N: usize> Drop for RingBuffer<T, N> {
+    fn drop(&mut self) {
+        if !std::mem::needs_drop::<T>() {
+            return;
+        }
+
+        // &mut self: tidak ada producer/consumer lain
+        let head = self.head.value.load(Ordering::Relaxed);
+        let mut tail = self.tail.value.load(Ordering::Relaxed);
+
+        while tail != head {
+            let slot = &self.buffer[tail & self.mask];
+            // SAFETY: Slot dalam [tail, head) sudah ditulis dan belum di-consume
+            slot.data
+                .with_mut(|ptr| unsafe { (*ptr).assume_init_drop() });
+            tail = tail.wrapping_add(1);
+        }
     }
 }
 
@@ -184,46 +581,380 @@ mod tests {
 
     #[test]
     fn test_basic_push_pop() {
-        let rb: RingBuffer<u64, 16> = RingBuffer::new();
+        let mut rb: RingBuffer<u64, 16> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
 
-        assert!(rb.is_empty());
-        assert!(!rb.is_full());
+        assert!(rx.is_empty());
+        assert!(!tx.is_full());
 
-        assert!(rb.push(42));
-        assert!(!rb.is_empty());
+        assert!(tx.push(42));
+        assert!(!rx.is_empty());
 
-        assert_eq!(rb.pop(), Some(42));
-        assert!(rb.is_empty());
+        assert_eq!(rx.pop(), Some(42));
+        assert!(rx.is_empty());
     }
 
     #[test]
     fn test_full_buffer() {
-        let rb: RingBuffer<u64, 4> = RingBuffer::new();
+        let mut rb: RingBuffer<u64, 4> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
 
-        assert!(rb.push(1));
-        assert!(rb.push(2));
-        assert!(rb.push(3));
-        assert!(rb.push(4));
+        assert!(tx.push(1));
+        assert!(tx.push(2));
+        assert!(tx.push(3));
+        assert!(tx.push(4));
 
-        assert!(rb.is_full());
-        assert!(!rb.push(5)); // Should fail - buffer full
+        assert!(tx.is_full());
+        assert!(!tx.push(5)); // Should fail - buffer full
 
-        assert_eq!(rb.pop(), Some(1));
-        assert!(rb.push(5)); // Now should succeed
+        assert_eq!(rx.pop(), Some(1));
+        assert!(tx.push(5)); // Now should succeed
     }
 
     #[test]
     fn test_wraparound() {
-        let rb: RingBuffer<u64, 4> = RingBuffer::new();
+        let mut rb: RingBuffer<u64, 4> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
 
         // Fill and drain multiple times to test wraparound
         for round in 0..10 {
             for i in 0..4 {
-                assert!(rb.push(round * 4 + i));
+                assert!(tx.push(round * 4 + i));
+            }
+            for i in 0..4 {
+                assert_eq!(rx.pop(), Some(round * 4 + i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_push_slice_pop_into() {
+        let mut rb: RingBuffer<u64, 8> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
+
+        // Hanya 8 dari 10 yang muat
+        let values: Vec<u64> = (0..10).collect();
+        assert_eq!(tx.push_slice(&values), 8);
+        assert!(tx.is_full());
+        assert_eq!(tx.push_slice(&values[8..]), 0);
+
+        let mut out = [0u64; 5];
+        assert_eq!(rx.pop_into(&mut out), 5);
+        assert_eq!(out, [0, 1, 2, 3, 4]);
+
+        // Batch melewati wraparound
+        assert_eq!(tx.push_slice(&values[8..]), 2);
+        let mut out = [0u64; 16];
+        assert_eq!(rx.pop_into(&mut out), 5);
+        assert_eq!(&out[..5], &[5, 6, 7, 8, 9]);
+        assert_eq!(rx.pop_into(&mut out), 0);
+    }
+
+    #[test]
+    fn test_write_with_read_with() {
+        #[derive(Clone, Copy, Default)]
+        struct Order {
+            id: u64,
+            qty: u32,
+        }
+
+        let mut rb: RingBuffer<Order, 2> = RingBuffer::new();
+
+        let (mut tx, mut rx) = rb.split();
+
+        assert!(tx.write_with(|slot| slot.write(Order { id: 1, qty: 10 })));
+        assert!(tx.write_with(|slot| {
+            let order = slot.write(Order::default());
+            order.id = 2;
+            order.qty = 20;
+            order
+        }));
+        assert!(!tx.write_with(|slot| slot.write(Order::default()))); // Full
+
+        assert_eq!(rx.read_with(|o| o.id + o.qty as u64), Some(11));
+        assert_eq!(rx.read_with(|o| o.id), Some(2));
+        assert_eq!(rx.read_with(|o| o.id), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "must initialize")]
+    // Butuh `&'static mut` di luar slot, jadi sengaja leak
+    #[cfg_attr(miri, ignore)]
+    fn test_write_with_rejects_foreign_reference() {
+        let mut rb: RingBuffer<u64, 2> = RingBuffer::new();
+        let (mut tx, _) = rb.split();
+        let other = Box::leak(Box::new(0u64));
+        tx.write_with(|_| other);
+    }
+
+    /// Counter drop untuk mendeteksi leak dan double drop
+    struct DropCounter<'a>(&'a std::cell::Cell<usize>);
+
+    impl Drop for DropCounter<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_owned_values() {
+        let mut rb: RingBuffer<Box<[u8]>, 4> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
+
+        assert!(tx.try_push(vec![1, 2, 3].into_boxed_slice()).is_ok());
+        assert!(tx.try_push(Box::new([4])).is_ok());
+        assert_eq!(rx.pop().as_deref(), Some(&[1, 2, 3][..]));
+        assert_eq!(rx.read_with(|b| b.len()), Some(1));
+        assert!(rx.is_empty());
+
+        // Buffer penuh mengembalikan ownership ke caller
+        for i in 0..4 {
+            assert!(tx.try_push(Box::new([i])).is_ok());
+        }
+        let rejected = tx.try_push(Box::new([9])).unwrap_err();
+        assert_eq!(&*rejected, &[9]);
+    }
+
+    #[test]
+    fn test_drop_unconsumed() {
+        let drops = std::cell::Cell::new(0);
+
+        {
+            let mut rb: RingBuffer<DropCounter<'_>, 4> = RingBuffer::new();
+            let (mut tx, mut rx) = rb.split();
+            for _ in 0..4 {
+                assert!(tx.try_push(DropCounter(&drops)).is_ok());
+            }
+
+            // Rejected value di-drop oleh caller, bukan oleh buffer
+            drop(tx.try_push(DropCounter(&drops)).unwrap_err());
+            assert_eq!(drops.get(), 1);
+
+            drop(rx.pop());
+            assert_eq!(drops.get(), 2);
+            assert_eq!(rx.read_with(|_| ()), Some(()));
+            assert_eq!(drops.get(), 3);
+        }
+
+        // 2 sisa di buffer di-drop tepat sekali
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn test_drop_after_wraparound() {
+        let drops = std::cell::Cell::new(0);
+
+        {
+            let mut rb: RingBuffer<DropCounter<'_>, 4> = RingBuffer::new();
+            let (mut tx, mut rx) = rb.split();
+            for _ in 0..7 {
+                assert!(tx.try_push(DropCounter(&drops)).is_ok());
+                drop(rx.pop());
+            }
+            // head/tail melewati batas array: slot 3, 0, 1 terisi
+            for _ in 0..3 {
+                assert!(tx.try_push(DropCounter(&drops)).is_ok());
+            }
+            assert_eq!(drops.get(), 7);
+
+            let mut out: Vec<DropCounter<'_>> = Vec::new();
+            out.push(DropCounter(&drops));
+            assert_eq!(rx.pop_into(&mut out), 1);
+            assert_eq!(drops.get(), 8); // Nilai lama di `out` ditimpa
+        }
+
+        assert_eq!(drops.get(), 11);
+    }
+
+    #[test]
+    fn test_push_blocking_owned() {
+        use crate::core::{SpinThenYield, Timeout};
+        use std::time::Duration;
+
+        let mut rb: RingBuffer<String, 2> = RingBuffer::new();
+
+        let (mut tx, mut rx) = rb.split();
+        let wait = Timeout::new(SpinThenYield::default(), Duration::from_millis(10));
+
+        assert!(tx.push_blocking("a".to_string(), &wait));
+        assert!(tx.push_blocking("b".to_string(), &wait));
+        assert!(!tx.push_blocking("c".to_string(), &wait)); // Full - timeout
+        assert_eq!(rx.pop_blocking(&wait).as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let mut rb: RingBuffer<u64> = RingBuffer::with_capacity(3);
+        let (mut tx, mut rx) = rb.split();
+        assert_eq!(rx.capacity(), 4); // Dibulatkan ke power of 2
+
+        for round in 0..10 {
+            for i in 0..4 {
+                assert!(tx.push(round * 4 + i));
             }
+            assert!(tx.is_full());
+            assert!(!tx.push(99));
+            assert_eq!(rx.len(), 4);
             for i in 0..4 {
-                assert_eq!(rb.pop(), Some(round * 4 + i));
+                assert_eq!(rx.pop(), Some(round * 4 + i));
             }
         }
+
+        let values: Vec<u64> = (0..6).collect();
+        assert_eq!(tx.push_slice(&values), 4);
+        let mut out = [0u64; 8];
+        assert_eq!(rx.pop_into(&mut out), 4);
+        assert_eq!(&out[..4], &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_with_capacity_owned_drop() {
+        let drops = std::cell::Cell::new(0);
+        {
+            let mut rb = RingBuffer::with_capacity(16);
+            let (mut tx, rx) = rb.split();
+            for _ in 0..5 {
+                assert!(tx.try_push(DropCounter(&drops)).is_ok());
+            }
+            assert_eq!(rx.capacity(), 16);
+        }
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn test_with_capacity_zero() {
+        let _rb: RingBuffer<u64> = RingBuffer::with_capacity(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "too large")]
+    fn test_with_capacity_overflow() {
+        let _rb: RingBuffer<u64> = RingBuffer::with_capacity(usize::MAX / 2 + 2);
+    }
+}
+
+/// Model checking ordering producer/consumer dengan loom
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use super::*;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    // Loom butuh thread 'static, jadi test memakai operasi mentah lewat Arc:
+    // setiap model punya tepat satu thread producer dan satu consumer.
+
+    /// Producer push `values` dengan retry saat buffer penuh
+    fn spawn_producer<T: Send + 'static, const N: usize>(
+        rb: &Arc<RingBuffer<T, N>>,
+        values: Vec<T>,
+    ) -> thread::JoinHandle<()> {
+        let rb = rb.clone();
+        thread::spawn(move || {
+            for mut value in values {
+                // SAFETY: Satu-satunya producer
+                while let Err(rejected) = unsafe { rb.try_push(value) } {
+                    value = rejected;
+                    thread::yield_now();
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn loom_spsc_wraparound() {
+        loom::model(|| {
+            let rb = Arc::new(RingBuffer::<usize, 2>::new());
+            // 3 elemen lewat 2 slot: index melewati batas array
+            let producer = spawn_producer(&rb, vec![0, 1, 2]);
+
+            for expected in 0..3 {
+                loop {
+                    // SAFETY: Satu-satunya consumer
+                    if let Some(value) = unsafe { rb.pop() } {
+                        assert_eq!(value, expected);
+                        break;
+                    }
+                    thread::yield_now();
+                }
+            }
+
+            producer.join().unwrap();
+            assert!(rb.is_empty());
+        });
+    }
+
+    #[test]
+    fn loom_full_empty_boundary() {
+        loom::model(|| {
+            let rb = Arc::new(RingBuffer::<usize, 1>::new());
+            let producer = spawn_producer(&rb, vec![10, 20]);
+
+            let mut received = Vec::new();
+            while received.len() < 2 {
+                // Invariant dari sisi consumer: 0 <= len <= capacity
+                assert!(rb.len() <= rb.capacity());
+                // SAFETY: Satu-satunya consumer
+                match unsafe { rb.pop() } {
+                    Some(value) => received.push(value),
+                    None => thread::yield_now(),
+                }
+            }
+
+            producer.join().unwrap();
+            assert_eq!(received, vec![10, 20]);
+            assert!(rb.is_empty() && !rb.is_full());
+        });
+    }
+
+    #[test]
+    fn loom_batch_runtime_capacity() {
+        loom::model(|| {
+            let rb = Arc::new(RingBuffer::<u64>::with_capacity(2));
+
+            let producer = {
+                let rb = rb.clone();
+                thread::spawn(move || {
+                    let values = [1u64, 2, 3];
+                    let mut sent = 0;
+                    while sent < values.len() {
+                        // SAFETY: Satu-satunya producer
+                        sent += unsafe { rb.push_slice(&values[sent..]) };
+                        thread::yield_now();
+                    }
+                })
+            };
+
+            let mut out = [0u64; 2];
+            let mut received = Vec::new();
+            while received.len() < 3 {
+                // SAFETY: Satu-satunya consumer
+                let n = unsafe { rb.pop_into(&mut out) };
+                received.extend_from_slice(&out[..n]);
+                if n == 0 {
+                    thread::yield_now();
+                }
+            }
+
+            producer.join().unwrap();
+            assert_eq!(received, vec![1, 2, 3]);
+        });
+    }
+
+    #[test]
+    fn loom_owned_values_dropped_once() {
+        loom::model(|| {
+            let rb = Arc::new(RingBuffer::<Box<usize>, 2>::new());
+            let producer = spawn_producer(&rb, vec![Box::new(1), Box::new(2)]);
+
+            // Consumer mengambil paling banyak satu; sisanya di-drop bersama buffer
+            // SAFETY: Satu-satunya consumer
+            if let Some(value) = unsafe { rb.pop() } {
+                assert_eq!(*value, 1);
+            }
+
+            producer.join().unwrap();
+            drop(rb);
+        });
     }
 }
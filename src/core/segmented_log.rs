@@ -0,0 +1,1958 @@
+//! Segmented Append-Only Log (rolling mmap segments)
+//!
+//! Pengganti `MmapStorage` circular untuk persistence jangka panjang:
+//! setiap record mendapat offset logis yang naik monotonic, dan saat segment
+//! aktif penuh log pindah ke segment baru. Segment lama bisa dihapus utuh.
+//!
+//! Satu direktori berisi file `{base_offset:020}.log`. Layout segment:
+//! ```text
+//! 0    magic: u64 ("HERMSEG1"), version: u32, reserved: u32, base_offset: u64
+//! 64   record: [len u32][crc32c u32][payload (len bytes)][pad ke 8]
+//!      ...
+//!      len == 0 menandai akhir data (file di-preallocate dengan nol)
+//! ```
+//! Record kosong tidak diizinkan karena `len == 0` adalah terminator.
+//! Segment versi 1 (checksum legacy) tetap bisa dibaca dan di-append;
+//! segment baru selalu ditulis dengan versi terbaru.
+//! Saat dibuka, segment di-scan: tail rusak (torn write) segment aktif dibuang,
+//! sedangkan record rusak di segment yang sudah ditutup hanya dilaporkan
+//! (`SegmentedLog::recovery`) dan file-nya tidak diubah.
+//!
+//! Setiap segment punya sparse index `{base_offset:020}.index`: satu entry
+//! per ~`INDEX_INTERVAL` bytes untuk record yang berupa frame Hermes.
+//! ```text
+//! entry (32 bytes): [sequence u64][timestamp_ns u64][offset u64][pos u64]
+//! ```
+//! Lookup by sequence/timestamp melewati segment yang key maksimumnya di bawah
+//! target, lalu binary search ke entry terdekat dan scan linear paling banyak
+//! satu interval. Binary search hanya dipakai jika key di segment tersebut
+//! tidak pernah turun (satu publisher); jika tidak (mis. beberapa publisher
+//! dengan sequence masing-masing) segment di-scan linear dari awal. Index yang
+//! hilang atau tertinggal dari segment dibangun ulang saat dibuka.
+//!
+//! Kapan data di-sync ditentukan `FsyncPolicy` (lihat `set_fsync_policy`),
+//! data lama dibuang per segment sesuai `RetentionPolicy`.
+
+use memmap2::{Mmap, MmapMut};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use super::durability::{BackgroundSync, Flush, FsyncPolicy, SyncRequest, SyncState};
+use super::retention::{RetentionPolicy, SegmentSummary};
+use super::storage_options::StorageOptions;
+use crate::protocol::{crc32c, legacy_checksum, MessageHeader};
+
+const SEGMENT_MAGIC: u64 = 0x4845524D53454731; // "HERMSEG1" in hex
+const SEGMENT_VERSION: u32 = 2;
+/// Versi terakhir yang memakai checksum legacy untuk record
+const SEGMENT_VERSION_LEGACY: u32 = 1;
+/// Ukuran header segment (data dimulai setelah ini)
+const SEGMENT_HEADER_SIZE: usize = 64;
+/// Header per record: len + checksum
+const RECORD_HEADER_SIZE: usize = 8;
+const RECORD_ALIGN: usize = 8;
+/// Ukuran segment minimum
+pub const MIN_SEGMENT_SIZE: usize = 256;
+/// Jarak minimum (bytes) antar entry sparse index
+const INDEX_INTERVAL: usize = 4096;
+/// File segment berikutnya yang sedang disiapkan (lihat `SegmentedLog::prepare_next`)
+const NEXT_SEGMENT_FILE: &str = "next-segment.tmp";
+const INDEX_ENTRY_SIZE: usize = 32;
+
+/// Ukuran record (header + payload) setelah di-align
+#[inline(always)]
+const fn record_size(len: usize) -> usize {
+    (RECORD_HEADER_SIZE + len + RECORD_ALIGN - 1) & !(RECORD_ALIGN - 1)
+}
+
+#[inline(always)]
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+#[inline(always)]
+fn read_u64(bytes: &[u8], pos: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[pos..pos + 8]);
+    u64::from_le_bytes(buf)
+}
+
+fn segment_path(dir: &Path, base_offset: u64) -> PathBuf {
+    dir.join(format!("{:020}.log", base_offset))
+}
+
+fn index_path(dir: &Path, base_offset: u64) -> PathBuf {
+    dir.join(format!("{:020}.index", base_offset))
+}
+
+/// Base offset semua segment di `dir`, urut naik
+pub(crate) fn segment_bases(dir: &Path) -> io::Result<Vec<u64>> {
+    let mut bases = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("log") {
+            continue;
+        }
+        if let Some(base) = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.parse::<u64>().ok())
+        {
+            bases.push(base);
+        }
+    }
+    bases.sort_unstable();
+    Ok(bases)
+}
+
+/// fsync direktori agar file baru / hasil rename tetap ada setelah crash
+pub(crate) fn sync_dir(dir: &Path) -> io::Result<()> {
+    // Direktori tidak bisa dibuka sebagai file di luar Unix
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = dir;
+    Ok(())
+}
+
+/// Checksum record untuk segment versi `version`
+type Checksum = fn(&[u8]) -> u32;
+
+#[inline(always)]
+fn record_checksum(version: u32) -> Checksum {
+    if version <= SEGMENT_VERSION_LEGACY {
+        legacy_checksum
+    } else {
+        crc32c
+    }
+}
+
+/// Validasi ukuran + header file segment; returns checksum record sesuai versinya
+fn check_segment(bytes: &[u8], path: &Path, base_offset: u64) -> io::Result<Checksum> {
+    if bytes.len() < MIN_SEGMENT_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("segment {} is truncated", path.display()),
+        ));
+    }
+    let version = read_u32(bytes, 8);
+    if read_u64(bytes, 0) != SEGMENT_MAGIC || version == 0 || read_u64(bytes, 16) != base_offset {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid segment header in {}", path.display()),
+        ));
+    }
+    if version > SEGMENT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "segment {} has unsupported version {} (max {})",
+                path.display(),
+                version,
+                SEGMENT_VERSION
+            ),
+        ));
+    }
+    Ok(record_checksum(version))
+}
+
+/// Ada sisa data (torn write / record rusak) setelah record valid terakhir di `end`
+#[inline(always)]
+fn has_tail(bytes: &[u8], end: usize) -> bool {
+    bytes
+        .get(end..end + RECORD_HEADER_SIZE)
+        .is_some_and(|header| header.iter().any(|&b| b != 0))
+}
+
+/// Key yang bisa di-lookup dari header frame Hermes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameKeys {
+    sequence: u64,
+    timestamp_ns: u64,
+}
+
+impl FrameKeys {
+    /// Ambil sequence/timestamp jika `payload` diawali header Hermes yang valid
+    #[inline(always)]
+    fn parse(payload: &[u8]) -> Option<Self> {
+        // SAFETY: MessageHeader packed (align 1) dan from_bytes cek panjang buffer
+        let header = unsafe { MessageHeader::from_bytes(payload)? };
+        Some(Self {
+            sequence: header.sequence,
+            timestamp_ns: header.timestamp_ns,
+        })
+    }
+}
+
+/// Key untuk `iter_from_sequence` / `iter_from_timestamp`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeekKey {
+    Sequence,
+    Timestamp,
+}
+
+impl SeekKey {
+    #[inline(always)]
+    fn of(self, keys: &FrameKeys) -> u64 {
+        match self {
+            Self::Sequence => keys.sequence,
+            Self::Timestamp => keys.timestamp_ns,
+        }
+    }
+}
+
+/// Ringkasan key semua frame di satu segment
+#[derive(Debug, Clone, Copy)]
+struct KeyStats {
+    last: Option<FrameKeys>,
+    // Maksimum per komponen
+    max: Option<FrameKeys>,
+    // Key tidak pernah turun sepanjang segment (binary search valid)
+    sequence_sorted: bool,
+    timestamp_sorted: bool,
+}
+
+impl Default for KeyStats {
+    fn default() -> Self {
+        Self {
+            last: None,
+            max: None,
+            sequence_sorted: true,
+            timestamp_sorted: true,
+        }
+    }
+}
+
+impl KeyStats {
+    #[inline(always)]
+    fn observe(&mut self, keys: FrameKeys) {
+        if let Some(last) = self.last {
+            self.sequence_sorted &= keys.sequence >= last.sequence;
+            self.timestamp_sorted &= keys.timestamp_ns >= last.timestamp_ns;
+        }
+        self.max = Some(match self.max {
+            Some(max) => FrameKeys {
+                sequence: max.sequence.max(keys.sequence),
+                timestamp_ns: max.timestamp_ns.max(keys.timestamp_ns),
+            },
+            None => keys,
+        });
+        self.last = Some(keys);
+    }
+
+    #[inline(always)]
+    fn is_sorted(&self, key: SeekKey) -> bool {
+        match key {
+            SeekKey::Sequence => self.sequence_sorted,
+            SeekKey::Timestamp => self.timestamp_sorted,
+        }
+    }
+}
+
+/// Entry sparse index: key frame + lokasi record-nya
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct IndexEntry {
+    keys: FrameKeys,
+    offset: u64,
+    pos: usize,
+}
+
+impl IndexEntry {
+    fn encode(&self) -> [u8; INDEX_ENTRY_SIZE] {
+        let mut buf = [0u8; INDEX_ENTRY_SIZE];
+        buf[0..8].copy_from_slice(&self.keys.sequence.to_le_bytes());
+        buf[8..16].copy_from_slice(&self.keys.timestamp_ns.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.offset.to_le_bytes());
+        buf[24..32].copy_from_slice(&(self.pos as u64).to_le_bytes());
+        buf
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        Self {
+            keys: FrameKeys {
+                sequence: read_u64(bytes, 0),
+                timestamp_ns: read_u64(bytes, 8),
+            },
+            offset: read_u64(bytes, 16),
+            pos: read_u64(bytes, 24) as usize,
+        }
+    }
+}
+
+/// Sparse index satu segment (file `.index` append-only)
+///
+/// `entries` di memori selalu lengkap; file hanya cache agar open cepat.
+struct SegmentIndex {
+    path: PathBuf,
+    file: File,
+    entries: Vec<IndexEntry>,
+    // Write gagal: file tertinggal dari `entries` (dibangun ulang saat open)
+    stale: Option<io::Error>,
+    // File ditulis ulang saat open karena tertinggal, rusak atau hilang
+    rebuilt: bool,
+}
+
+impl SegmentIndex {
+    /// Membuat index kosong untuk segment baru
+    fn create(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            entries: Vec::new(),
+            stale: None,
+            rebuilt: false,
+        })
+    }
+
+    /// Memuat index dan menyamakannya dengan isi segment
+    ///
+    /// Entry yang tidak cocok dengan record di segment (mis. setelah torn tail
+    /// dibuang) di-drop, lalu record setelah entry terakhir di-index ulang.
+    fn open(
+        path: PathBuf,
+        bytes: &[u8],
+        base_offset: u64,
+        write_pos: usize,
+        checksum: Checksum,
+    ) -> io::Result<Self> {
+        let raw = match fs::read(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let mut entries: Vec<IndexEntry> = Vec::with_capacity(raw.len() / INDEX_ENTRY_SIZE);
+        for chunk in raw.chunks_exact(INDEX_ENTRY_SIZE) {
+            let entry = IndexEntry::decode(chunk);
+            let in_order = entries
+                .last()
+                .map_or(entry.pos >= SEGMENT_HEADER_SIZE, |last| {
+                    entry.pos > last.pos
+                });
+            let matches = in_order
+                && entry.pos < write_pos
+                && entry.offset >= base_offset
+                && Segment::validate_at(bytes, entry.pos, checksum)
+                    .and_then(|(payload, _)| FrameKeys::parse(payload))
+                    == Some(entry.keys);
+            if !matches {
+                break;
+            }
+            entries.push(entry);
+        }
+        let valid = entries.len();
+
+        // Index record yang belum tercatat (index tertinggal atau hilang)
+        let (mut offset, mut pos) = match entries.last() {
+            Some(last) => (last.offset, last.pos),
+            None => (base_offset, SEGMENT_HEADER_SIZE),
+        };
+        while pos < write_pos {
+            let Some((payload, next)) = Segment::validate_at(bytes, pos, checksum) else {
+                break;
+            };
+            Self::observe(&mut entries, offset, pos, payload);
+            offset += 1;
+            pos = next;
+        }
+
+        let rebuilt = valid * INDEX_ENTRY_SIZE != raw.len() || valid != entries.len();
+        let file = if !rebuilt {
+            OpenOptions::new().append(true).create(true).open(&path)?
+        } else {
+            // Tulis ulang utuh
+            let mut file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&path)?;
+            let mut buf = Vec::with_capacity(entries.len() * INDEX_ENTRY_SIZE);
+            for entry in &entries {
+                buf.extend_from_slice(&entry.encode());
+            }
+            file.write_all(&buf)?;
+            file
+        };
+
+        Ok(Self {
+            path,
+            file,
+            entries,
+            stale: None,
+            rebuilt,
+        })
+    }
+
+    /// Tambah entry untuk record di `pos` jika jarak dari entry terakhir cukup
+    #[inline(always)]
+    fn observe(
+        entries: &mut Vec<IndexEntry>,
+        offset: u64,
+        pos: usize,
+        payload: &[u8],
+    ) -> Option<IndexEntry> {
+        let due = entries
+            .last()
+            .map_or(true, |last| pos >= last.pos + INDEX_INTERVAL);
+        if !due {
+            return None;
+        }
+        let entry = IndexEntry {
+            keys: FrameKeys::parse(payload)?,
+            offset,
+            pos,
+        };
+        entries.push(entry);
+        Some(entry)
+    }
+
+    /// Catat record yang baru di-append
+    ///
+    /// Gagal menulis file index tidak menggagalkan append (record sudah ada
+    /// di segment): index ditandai stale dan file-nya tidak ditulis lagi,
+    /// lihat `SegmentedLog::stale_indexes`.
+    #[inline(always)]
+    fn append(&mut self, offset: u64, pos: usize, payload: &[u8]) {
+        let Some(entry) = Self::observe(&mut self.entries, offset, pos, payload) else {
+            return;
+        };
+        if self.stale.is_some() {
+            return;
+        }
+        if let Err(e) = self.file.write_all(&entry.encode()) {
+            self.stale = Some(e);
+        }
+    }
+
+    /// Entry terakhir dengan `key < target` (titik awal scan)
+    ///
+    /// Hanya valid jika key di segment terurut (`KeyStats::is_sorted`).
+    fn floor(&self, target: u64, key: SeekKey) -> Option<&IndexEntry> {
+        let index = self.entries.partition_point(|e| key.of(&e.keys) < target);
+        index.checked_sub(1).map(|i| &self.entries[i])
+    }
+}
+
+/// Satu file segment yang di-mmap
+struct Segment {
+    base_offset: u64,
+    path: PathBuf,
+    // Handle untuk fdatasync di thread latar
+    file: Arc<File>,
+    // Checksum record sesuai versi segment
+    checksum: Checksum,
+    mmap: MmapMut,
+    // Posisi byte setelah record terakhir
+    write_pos: usize,
+    // Jumlah record di segment ini
+    records: u64,
+    // Data sebelum posisi ini sudah di-sync ke disk
+    synced_pos: usize,
+    // Key frame Hermes (untuk lookup dan retensi by age)
+    keys: KeyStats,
+    index: SegmentIndex,
+}
+
+impl Segment {
+    /// Membuat segment baru (preallocated, berisi nol)
+    fn create(
+        dir: &Path,
+        base_offset: u64,
+        size: usize,
+        options: &StorageOptions,
+    ) -> io::Result<Self> {
+        let (file, mmap) = Self::allocate(&segment_path(dir, base_offset), size, options)?;
+        Self::from_allocated(dir, base_offset, file, mmap)
+    }
+
+    /// Buat file segment kosong di `path` lalu map (tanpa base offset dan warm)
+    fn allocate(path: &Path, size: usize, options: &StorageOptions) -> io::Result<(File, MmapMut)> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)?;
+        // Jangan tinggalkan file kosong yang akan ditolak saat log dibuka ulang
+        let mapped = options.mapping_len(&file, size).and_then(|size| {
+            file.set_len(size as u64)?;
+            options.map_mut(&file, size)
+        });
+        let mut mmap = mapped.map_err(|e| {
+            fs::remove_file(path).ok();
+            e
+        })?;
+
+        mmap[0..8].copy_from_slice(&SEGMENT_MAGIC.to_le_bytes());
+        mmap[8..12].copy_from_slice(&SEGMENT_VERSION.to_le_bytes());
+        Ok((file, mmap))
+    }
+
+    /// Segment dari file hasil `allocate` yang sudah ada di `segment_path`
+    fn from_allocated(
+        dir: &Path,
+        base_offset: u64,
+        file: File,
+        mut mmap: MmapMut,
+    ) -> io::Result<Self> {
+        mmap[16..24].copy_from_slice(&base_offset.to_le_bytes());
+
+        Ok(Self {
+            base_offset,
+            path: segment_path(dir, base_offset),
+            file: Arc::new(file),
+            checksum: record_checksum(SEGMENT_VERSION),
+            mmap,
+            write_pos: SEGMENT_HEADER_SIZE,
+            records: 0,
+            synced_pos: 0,
+            keys: KeyStats::default(),
+            index: SegmentIndex::create(index_path(dir, base_offset))?,
+        })
+    }
+
+    /// Membuka segment yang sudah ada dan memulihkan posisi tulis
+    ///
+    /// Returns juga posisi byte data rusak setelah record valid terakhir, jika
+    /// ada. Hanya segment `active` yang tail-nya dibuang; di segment tertutup
+    /// itu bukan torn write, jadi file dibiarkan.
+    fn open(
+        dir: &Path,
+        base_offset: u64,
+        options: &StorageOptions,
+        active: bool,
+    ) -> io::Result<(Self, Option<usize>)> {
+        let path = segment_path(dir, base_offset);
+        let file = OpenOptions::new().read(true).write(true).open(&path)?;
+        let size = file.metadata()?.len() as usize;
+
+        let mut mmap = options.map_mut(&file, size)?;
+        let checksum = check_segment(&mmap, &path, base_offset)?;
+
+        // Scan record valid; berhenti di terminator atau record rusak
+        let mut write_pos = SEGMENT_HEADER_SIZE;
+        let mut records = 0;
+        let mut keys = KeyStats::default();
+        while let Some((payload, next)) = Self::validate_at(&mmap, write_pos, checksum) {
+            debug_assert!(!payload.is_empty());
+            if let Some(frame) = FrameKeys::parse(payload) {
+                keys.observe(frame);
+            }
+            write_pos = next;
+            records += 1;
+        }
+
+        // Buang tail rusak agar scan berikutnya berhenti di tempat yang sama
+        let corrupt_at = has_tail(&mmap, write_pos).then_some(write_pos);
+        if active && corrupt_at.is_some() {
+            mmap[write_pos..write_pos + RECORD_HEADER_SIZE].fill(0);
+            mmap.flush_range(write_pos, RECORD_HEADER_SIZE)?;
+        }
+
+        let index = SegmentIndex::open(
+            index_path(dir, base_offset),
+            &mmap,
+            base_offset,
+            write_pos,
+            checksum,
+        )?;
+
+        let segment = Self {
+            base_offset,
+            path,
+            file: Arc::new(file),
+            checksum,
+            mmap,
+            write_pos,
+            records,
+            synced_pos: 0,
+            keys,
+            index,
+        };
+        Ok((segment, corrupt_at))
+    }
+
+    /// Decode dan validasi (bounds + checksum) record di `pos`
+    fn validate_at(bytes: &[u8], pos: usize, checksum: Checksum) -> Option<(&[u8], usize)> {
+        if pos + RECORD_HEADER_SIZE > bytes.len() {
+            return None;
+        }
+        let len = read_u32(bytes, pos) as usize;
+        let next = pos + record_size(len);
+        if len == 0 || next > bytes.len() {
+            return None;
+        }
+
+        let payload = &bytes[pos + RECORD_HEADER_SIZE..pos + RECORD_HEADER_SIZE + len];
+        (checksum(payload) == read_u32(bytes, pos + 4)).then_some((payload, next))
+    }
+
+    /// Record di `pos` (hanya untuk posisi yang sudah lolos recovery/append)
+    #[inline(always)]
+    fn record_at(&self, pos: usize) -> Option<(&[u8], usize)> {
+        if pos >= self.write_pos {
+            return None;
+        }
+        let len = read_u32(&self.mmap, pos) as usize;
+        let start = pos + RECORD_HEADER_SIZE;
+        Some((&self.mmap[start..start + len], pos + record_size(len)))
+    }
+
+    /// Append record; returns `false` jika segment penuh
+    #[inline(always)]
+    fn append(&mut self, data: &[u8]) -> io::Result<bool> {
+        let pos = self.write_pos;
+        let next = pos + record_size(data.len());
+        if next > self.mmap.len() {
+            return Ok(false);
+        }
+
+        let start = pos + RECORD_HEADER_SIZE;
+        self.mmap[start..start + data.len()].copy_from_slice(data);
+        self.mmap[pos + 4..pos + 8].copy_from_slice(&(self.checksum)(data).to_le_bytes());
+        // len ditulis terakhir: record baru "ada" setelah len non-zero
+        self.mmap[pos..pos + 4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+
+        let offset = self.next_offset();
+        self.write_pos = next;
+        self.records += 1;
+        if let Some(keys) = FrameKeys::parse(data) {
+            self.keys.observe(keys);
+        }
+        self.index.append(offset, pos, data);
+        Ok(true)
+    }
+
+    #[inline(always)]
+    fn summary(&self) -> SegmentSummary {
+        SegmentSummary {
+            bytes: self.mmap.len() as u64,
+            messages: self.records,
+            // Frame terbaru = timestamp terbesar, walau urutan publish acak
+            last_timestamp_ns: self.keys.max.map(|k| k.timestamp_ns),
+        }
+    }
+
+    #[inline(always)]
+    fn next_offset(&self) -> u64 {
+        self.base_offset + self.records
+    }
+
+    /// Sync data yang belum di-sync (blocking)
+    fn sync(&mut self) -> io::Result<()> {
+        let (start, end) = (self.synced_pos, self.write_pos);
+        if start == end {
+            return Ok(());
+        }
+        self.mmap.flush_range(start, end - start)?;
+        self.synced_pos = end;
+        Ok(())
+    }
+
+    /// Request `fdatasync` untuk semua data yang sudah ditulis
+    fn sync_request(&self) -> SyncRequest {
+        SyncRequest {
+            id: self.base_offset,
+            file: Arc::clone(&self.file),
+            end: self.write_pos,
+        }
+    }
+
+    /// Posisi byte record ke-`index` di segment ini
+    ///
+    /// Mulai dari entry sparse index terdekat, lalu scan linear.
+    fn position_of(&self, index: u64) -> Option<usize> {
+        if index >= self.records {
+            return None;
+        }
+        let offset = self.base_offset + index;
+        let entries = &self.index.entries;
+        let (mut current, mut pos) = match entries.partition_point(|e| e.offset <= offset) {
+            0 => (self.base_offset, SEGMENT_HEADER_SIZE),
+            i => (entries[i - 1].offset, entries[i - 1].pos),
+        };
+        while current < offset {
+            pos = self.record_at(pos)?.1;
+            current += 1;
+        }
+        Some(pos)
+    }
+}
+
+/// Hasil recovery saat `SegmentedLog::open`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogRecovery {
+    /// Posisi byte tail rusak di segment aktif yang dibuang (torn write)
+    pub truncated_at: Option<usize>,
+    /// Segment tertutup dengan record rusak: `(base_offset, posisi byte)`.
+    /// File tidak diubah; record setelah posisi ini tidak bisa dibaca.
+    pub corrupt: Vec<(u64, usize)>,
+    /// Segment yang file index-nya tertinggal/rusak/hilang dan ditulis ulang
+    pub rebuilt_indexes: Vec<u64>,
+}
+
+/// Append-only log dengan rolling mmap segments
+pub struct SegmentedLog {
+    dir: PathBuf,
+    segment_size: usize,
+    // Urut berdasarkan base_offset; elemen terakhir = segment aktif
+    segments: Vec<Segment>,
+    sync: SyncState,
+    // Thread fdatasync untuk FsyncPolicy::Interval (dibuat saat dibutuhkan)
+    background: Option<BackgroundSync>,
+    // Segment berikutnya yang sedang dibuat + di-warm di thread latar
+    next: Option<JoinHandle<io::Result<(File, MmapMut)>>>,
+    options: StorageOptions,
+    recovery: LogRecovery,
+}
+
+impl SegmentedLog {
+    /// Membuka (atau membuat) log di direktori `dir`
+    ///
+    /// Segment baru dibuat dengan ukuran `segment_size` bytes; segment lama
+    /// tetap memakai ukuran file-nya sendiri.
+    ///
+    /// # Panics
+    /// Panic jika `segment_size < MIN_SEGMENT_SIZE` atau bukan kelipatan 8
+    pub fn open<P: AsRef<Path>>(dir: P, segment_size: usize) -> io::Result<Self> {
+        Self::open_with(dir, segment_size, StorageOptions::new())
+    }
+
+    /// Seperti `open`, dengan tuning mmap untuk segment aktif
+    ///
+    /// Hanya segment aktif yang di-prefault/di-`mlock`; segment yang ditutup
+    /// saat roll di-`munlock`. Segment lama dibuka tanpa opsi. Jika opsi
+    /// membuat segment baru mahal (populate/prefault/mlock), segment berikutnya
+    /// disiapkan di thread latar begitu segment aktif setengah penuh, jadi
+    /// selama itu dua segment bisa ter-`mlock` sekaligus.
+    pub fn open_with<P: AsRef<Path>>(
+        dir: P,
+        segment_size: usize,
+        options: StorageOptions,
+    ) -> io::Result<Self> {
+        assert!(
+            segment_size >= MIN_SEGMENT_SIZE && segment_size % RECORD_ALIGN == 0,
+            "segment size must be >= 256 and a multiple of 8"
+        );
+
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        // Sisa persiapan roll sebelum crash
+        match fs::remove_file(dir.join(NEXT_SEGMENT_FILE)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+
+        let bases = segment_bases(&dir)?;
+        let mut segments = Vec::with_capacity(bases.len().max(1));
+        let mut recovery = LogRecovery::default();
+        let cold = StorageOptions::new();
+        for (i, &base) in bases.iter().enumerate() {
+            let active = i + 1 == bases.len();
+            let options = if active { &options } else { &cold };
+            let (segment, corrupt_at) = Segment::open(&dir, base, options, active)?;
+            match corrupt_at {
+                Some(pos) if active => recovery.truncated_at = Some(pos),
+                Some(pos) => recovery.corrupt.push((base, pos)),
+                None => {}
+            }
+            if segment.index.rebuilt {
+                recovery.rebuilt_indexes.push(base);
+            }
+            segments.push(segment);
+        }
+
+        if segments.is_empty() {
+            segments.push(Segment::create(&dir, 0, segment_size, &options)?);
+            sync_dir(&dir)?;
+        }
+
+        let mut log = Self {
+            dir,
+            segment_size,
+            segments,
+            sync: SyncState::new(FsyncPolicy::None),
+            background: None,
+            next: None,
+            options,
+            recovery,
+        };
+        options.warm(&mut log.active_mut().mmap)?;
+        Ok(log)
+    }
+
+    /// Hasil recovery dari `open` (kosong jika semua segment bersih)
+    pub fn recovery(&self) -> &LogRecovery {
+        &self.recovery
+    }
+
+    /// Segment yang file index-nya gagal ditulis: `(base_offset, error)`
+    ///
+    /// Append dan lookup tetap jalan (entry ada di memory); file index
+    /// dibangun ulang saat log dibuka lagi.
+    pub fn stale_indexes(&self) -> impl Iterator<Item = (u64, &io::Error)> + '_ {
+        self.segments
+            .iter()
+            .filter_map(|s| Some((s.base_offset, s.index.stale.as_ref()?)))
+    }
+
+    /// Ganti durability policy (default: `FsyncPolicy::None`)
+    pub fn set_fsync_policy(&mut self, policy: FsyncPolicy) {
+        self.sync = SyncState::new(policy);
+    }
+
+    /// Durability policy yang aktif
+    pub fn fsync_policy(&self) -> FsyncPolicy {
+        self.sync.policy()
+    }
+
+    /// Append record, returns offset logis record tersebut
+    ///
+    /// Pindah ke segment baru jika segment aktif penuh. Gagal dengan
+    /// `InvalidInput` untuk record kosong atau lebih besar dari satu segment.
+    /// Flush yang diminta `FsyncPolicy` dilakukan setelah record tertulis;
+    /// error flush dikembalikan walaupun record sudah ada di log.
+    pub fn append(&mut self, data: &[u8]) -> io::Result<u64> {
+        if data.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "empty records are not allowed",
+            ));
+        }
+        if record_size(data.len()) > self.segment_size - SEGMENT_HEADER_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "record larger than segment size",
+            ));
+        }
+
+        let active = self.active_mut();
+        let offset = active.next_offset();
+        if !active.append(data)? {
+            self.roll()?;
+            let appended = self.active_mut().append(data)?;
+            debug_assert!(appended);
+        }
+        if self.next.is_none() && self.options.warms() {
+            let active = self.active_mut();
+            if active.write_pos >= active.mmap.len() / 2 {
+                self.prepare_next();
+            }
+        }
+
+        if let Some(flush) = self.sync.on_append() {
+            self.apply(flush)?;
+        }
+        Ok(offset)
+    }
+
+    /// Cek periodik untuk `FsyncPolicy::Interval`
+    ///
+    /// Mencatat sync latar yang sudah selesai (memajukan posisi yang aman di
+    /// disk) dan memulai sync baru jika interval sudah lewat. Error sync latar
+    /// dikembalikan di sini.
+    #[inline(always)]
+    pub fn tick(&mut self) -> io::Result<()> {
+        self.reap_background()?;
+        if let Some(flush) = self.sync.on_tick() {
+            self.apply(flush)?;
+        }
+        Ok(())
+    }
+
+    /// Jumlah record yang belum di-flush sejak flush terakhir dimulai
+    pub fn unsynced(&self) -> u64 {
+        self.sync.unsynced()
+    }
+
+    /// Jalankan flush yang diminta `SyncState`
+    fn apply(&mut self, flush: Flush) -> io::Result<()> {
+        match flush {
+            Flush::Sync => self.active_mut().sync()?,
+            Flush::Background => {
+                self.reap_background()?;
+                // Satu sync latar sekaligus; yang berikutnya menyusul di tick
+                if !self.background()?.is_idle() {
+                    return Ok(());
+                }
+                let request = self.active_mut().sync_request();
+                self.background()?.submit(request)?;
+            }
+        }
+        self.sync.flushed();
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn background(&mut self) -> io::Result<&mut BackgroundSync> {
+        if self.background.is_none() {
+            self.background = Some(BackgroundSync::spawn()?);
+        }
+        Ok(self.background.as_mut().expect("background sync spawned"))
+    }
+
+    /// Catat sync latar yang sudah selesai
+    fn reap_background(&mut self) -> io::Result<()> {
+        let Some(background) = self.background.as_mut() else {
+            return Ok(());
+        };
+        while let Some(done) = background.poll() {
+            done.result?;
+            // Segment bisa sudah dilepas retention selama sync berjalan
+            if let Some(segment) = self.segments.iter_mut().find(|s| s.base_offset == done.id) {
+                segment.synced_pos = segment.synced_pos.max(done.end);
+            }
+        }
+        Ok(())
+    }
+
+    /// Tutup segment aktif dan mulai segment baru
+    ///
+    /// Direktori di-fsync setelah file segment baru dibuat / di-rename, jadi
+    /// record yang sudah di-sync tidak hilang bersama entry direktorinya.
+    #[cold]
+    fn roll(&mut self) -> io::Result<()> {
+        let options = self.options;
+        let policy = self.sync.policy();
+        // Segment penuh tidak berubah lagi: policy sync menunggu sampai di
+        // disk, Interval menyerahkannya ke thread latar
+        if policy.is_synchronous() {
+            self.active_mut().sync()?;
+        } else if let FsyncPolicy::Interval(_) = policy {
+            let request = self.active_mut().sync_request();
+            self.background()?.submit(request)?;
+        }
+        let active = self.active_mut();
+        if options.locks() {
+            active.mmap.unlock()?;
+        }
+        let base = active.next_offset();
+
+        let segment = match self.take_prepared(base)? {
+            Some(segment) => segment,
+            None => {
+                let mut segment = Segment::create(&self.dir, base, self.segment_size, &options)?;
+                options.warm(&mut segment.mmap)?;
+                segment
+            }
+        };
+        self.segments.push(segment);
+        sync_dir(&self.dir)
+    }
+
+    /// Mulai membuat + warm segment berikutnya di thread latar
+    ///
+    /// Base offset belum diketahui, jadi file dibuat sebagai `NEXT_SEGMENT_FILE`
+    /// dan di-rename saat roll.
+    #[cold]
+    fn prepare_next(&mut self) {
+        let path = self.dir.join(NEXT_SEGMENT_FILE);
+        let (size, options) = (self.segment_size, self.options);
+        let prepare = move || {
+            let (file, mut mmap) = Segment::allocate(&path, size, &options)?;
+            if let Err(e) = options.warm(&mut mmap) {
+                fs::remove_file(&path).ok();
+                return Err(e);
+            }
+            Ok((file, mmap))
+        };
+        // Gagal spawn: roll membuat segment secara sinkron seperti biasa
+        self.next = thread::Builder::new()
+            .name("hermes-segment".into())
+            .spawn(prepare)
+            .ok();
+    }
+
+    /// Pakai segment hasil `prepare_next` untuk `base_offset`
+    ///
+    /// Menunggu jika persiapan belum selesai. Returns `None` jika tidak ada
+    /// atau gagal; caller lalu membuat segment sinkron (dan melaporkan error).
+    fn take_prepared(&mut self, base_offset: u64) -> io::Result<Option<Segment>> {
+        let Some(next) = self.next.take() else {
+            return Ok(None);
+        };
+        let tmp = self.dir.join(NEXT_SEGMENT_FILE);
+        let Ok(Ok((file, mmap))) = next.join() else {
+            fs::remove_file(&tmp).ok();
+            return Ok(None);
+        };
+        if fs::rename(&tmp, segment_path(&self.dir, base_offset)).is_err() {
+            drop(mmap);
+            fs::remove_file(&tmp).ok();
+            return Ok(None);
+        }
+        Segment::from_allocated(&self.dir, base_offset, file, mmap).map(Some)
+    }
+
+    #[inline(always)]
+    fn active_mut(&mut self) -> &mut Segment {
+        self.segments.last_mut().expect("log always has a segment")
+    }
+
+    /// Index segment yang berisi `offset`
+    fn segment_index(&self, offset: u64) -> Option<usize> {
+        let index = self
+            .segments
+            .partition_point(|s| s.base_offset <= offset)
+            .checked_sub(1)?;
+        (offset < self.segments[index].next_offset()).then_some(index)
+    }
+
+    /// Baca record pada `offset` (zero-copy)
+    ///
+    /// Returns `None` jika offset sudah dihapus atau belum ditulis.
+    pub fn read(&self, offset: u64) -> Option<&[u8]> {
+        let segment = &self.segments[self.segment_index(offset)?];
+        let pos = segment.position_of(offset - segment.base_offset)?;
+        segment.record_at(pos).map(|(payload, _)| payload)
+    }
+
+    /// Iterasi record mulai dari `offset` sampai akhir log
+    ///
+    /// Offset yang sudah dihapus dimulai dari record tertua yang masih ada.
+    pub fn iter_from(&self, offset: u64) -> LogIter<'_> {
+        let offset = offset.max(self.first_offset());
+        let (segment, pos) = match self.segment_index(offset) {
+            Some(index) => {
+                let segment = &self.segments[index];
+                let pos = segment
+                    .position_of(offset - segment.base_offset)
+                    .unwrap_or(segment.write_pos);
+                (index, pos)
+            }
+            None => (self.segments.len(), 0),
+        };
+
+        LogIter {
+            log: self,
+            segment,
+            pos,
+            offset,
+        }
+    }
+
+    /// Iterasi mulai dari frame pertama dengan `sequence >= sequence`
+    ///
+    /// Record yang bukan frame Hermes di depan titik tersebut dilewati.
+    /// Sequence dihitung per publisher: dengan beberapa publisher hasilnya
+    /// frame pertama (urutan log) dari publisher mana pun, dan segment yang
+    /// sequence-nya tidak terurut di-scan linear.
+    pub fn iter_from_sequence(&self, sequence: u64) -> LogIter<'_> {
+        self.seek(sequence, SeekKey::Sequence)
+    }
+
+    /// Iterasi mulai dari frame pertama dengan `timestamp_ns >= timestamp_ns`
+    pub fn iter_from_timestamp(&self, timestamp_ns: u64) -> LogIter<'_> {
+        self.seek(timestamp_ns, SeekKey::Timestamp)
+    }
+
+    /// Cari segment + sparse index, lalu scan linear ke frame target
+    fn seek(&self, target: u64, key: SeekKey) -> LogIter<'_> {
+        // Segment sebelum ini tidak punya frame dengan key >= target
+        let Some(segment) = self
+            .segments
+            .iter()
+            .position(|s| s.keys.max.is_some_and(|max| key.of(&max) >= target))
+        else {
+            return self.iter_from(self.next_offset());
+        };
+
+        let start = &self.segments[segment];
+        let floor = if start.keys.is_sorted(key) {
+            start.index.floor(target, key)
+        } else {
+            None
+        };
+        let (offset, pos) = match floor {
+            Some(entry) => (entry.offset, entry.pos),
+            None => (start.base_offset, SEGMENT_HEADER_SIZE),
+        };
+
+        let mut iter = LogIter {
+            log: self,
+            segment,
+            pos,
+            offset,
+        };
+        loop {
+            let mut ahead = iter.clone();
+            match ahead.next() {
+                Some((_, payload))
+                    if FrameKeys::parse(payload).map_or(true, |k| key.of(&k) < target) =>
+                {
+                    iter = ahead;
+                }
+                _ => return iter,
+            }
+        }
+    }
+
+    /// Hapus segment yang seluruh record-nya di bawah `offset`
+    ///
+    /// Segment aktif tidak pernah dihapus. Returns jumlah segment yang dihapus.
+    pub fn delete_before(&mut self, offset: u64) -> io::Result<usize> {
+        let count = self.segments[..self.segments.len() - 1]
+            .iter()
+            .take_while(|s| s.next_offset() <= offset)
+            .count();
+        Self::remove_all(self.detach(count))
+    }
+
+    /// Lepas segment yang sudah lewat `policy` dari log tanpa menghapus file
+    ///
+    /// Murah (hanya memindahkan segment keluar dari log); unmap + hapus file
+    /// via `RetiredSegment::remove` bisa dilakukan di thread lain supaya
+    /// tidak menahan hot path.
+    pub fn detach_expired(&mut self, policy: &RetentionPolicy) -> Vec<RetiredSegment> {
+        self.detach_expired_before(policy, u64::MAX)
+    }
+
+    /// Seperti `detach_expired`, tapi hanya segment yang semua record-nya `< offset`
+    ///
+    /// Untuk job yang membaca segment tertutup di thread lain (mis.
+    /// `Compactor::next_offset`): segment yang belum diproses tetap di log.
+    pub fn detach_expired_before(
+        &mut self,
+        policy: &RetentionPolicy,
+        offset: u64,
+    ) -> Vec<RetiredSegment> {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        let now_ns = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        self.detach_expired_at(policy, now_ns, offset)
+    }
+
+    fn detach_expired_at(
+        &mut self,
+        policy: &RetentionPolicy,
+        now_ns: u64,
+        before: u64,
+    ) -> Vec<RetiredSegment> {
+        if policy.is_unbounded() {
+            return Vec::new();
+        }
+        let summaries: Vec<SegmentSummary> = self.segments.iter().map(Segment::summary).collect();
+        let done = self
+            .segments
+            .iter()
+            .take_while(|s| s.next_offset() <= before)
+            .count();
+        self.detach(policy.expired(&summaries, now_ns).min(done))
+    }
+
+    /// Terapkan `policy` langsung (blocking); returns jumlah segment yang dihapus
+    pub fn enforce_retention(&mut self, policy: &RetentionPolicy) -> io::Result<usize> {
+        let retired = self.detach_expired(policy);
+        Self::remove_all(retired)
+    }
+
+    /// Keluarkan `count` segment tertua (segment aktif tidak pernah ikut)
+    fn detach(&mut self, count: usize) -> Vec<RetiredSegment> {
+        let count = count.min(self.segments.len() - 1);
+        self.segments
+            .drain(..count)
+            .map(|segment| RetiredSegment { segment })
+            .collect()
+    }
+
+    fn remove_all(retired: Vec<RetiredSegment>) -> io::Result<usize> {
+        let count = retired.len();
+        for segment in retired {
+            segment.remove()?;
+        }
+        Ok(count)
+    }
+
+    /// Offset record tertua yang masih tersimpan
+    pub fn first_offset(&self) -> u64 {
+        self.segments[0].base_offset
+    }
+
+    /// Offset yang akan diberikan ke record berikutnya
+    pub fn next_offset(&self) -> u64 {
+        self.segments
+            .last()
+            .expect("log always has a segment")
+            .next_offset()
+    }
+
+    /// Jumlah file segment
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Total bytes file segment di disk
+    pub fn size_bytes(&self) -> u64 {
+        self.segments.iter().map(|s| s.mmap.len() as u64).sum()
+    }
+
+    /// Sync semua record yang belum di-sync ke disk (blocking)
+    ///
+    /// Dipakai langsung untuk `FsyncPolicy::BeforeAck`: setelah `flush`
+    /// berhasil, semua record yang sudah di-append aman untuk di-ack.
+    pub fn flush(&mut self) -> io::Result<()> {
+        // Segment yang sudah di-sync (synced_pos == write_pos) langsung dilewati
+        for segment in &mut self.segments {
+            segment.sync()?;
+        }
+        self.sync.flushed();
+        Ok(())
+    }
+}
+
+impl Drop for SegmentedLog {
+    fn drop(&mut self) {
+        // Segment berikutnya yang belum terpakai tidak boleh tertinggal
+        if let Some(next) = self.next.take() {
+            drop(next.join());
+            fs::remove_file(self.dir.join(NEXT_SEGMENT_FILE)).ok();
+        }
+    }
+}
+
+/// View read-only satu file segment (untuk job di thread/proses lain)
+///
+/// Hanya konsisten untuk segment yang sudah ditutup: segment aktif bisa
+/// berubah selama dibaca. Record dibaca sampai terminator atau record rusak
+/// pertama.
+pub struct SegmentFile {
+    base_offset: u64,
+    mmap: Mmap,
+    checksum: Checksum,
+}
+
+/// Hasil scan satu segment, lihat [`SegmentFile::scan`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SegmentScan {
+    /// Jumlah record valid
+    pub records: u64,
+    /// Posisi byte setelah record valid terakhir
+    pub end: usize,
+    /// Ada data setelah `end` (torn write); dibuang saat log dibuka
+    pub torn: bool,
+}
+
+impl SegmentFile {
+    pub fn open(dir: &Path, base_offset: u64) -> io::Result<Self> {
+        let path = segment_path(dir, base_offset);
+        let file = File::open(&path)?;
+        // SAFETY: Read-only mapping; segment tertutup tidak ditulis lagi
+        let mmap = unsafe { Mmap::map(&file)? };
+        let checksum = check_segment(&mmap, &path, base_offset)?;
+        Ok(Self {
+            base_offset,
+            mmap,
+            checksum,
+        })
+    }
+
+    /// Semua segment di `dir`, urut berdasarkan base offset
+    pub fn open_all(dir: &Path) -> io::Result<Vec<Self>> {
+        segment_bases(dir)?
+            .into_iter()
+            .map(|base| Self::open(dir, base))
+            .collect()
+    }
+
+    #[inline(always)]
+    pub fn base_offset(&self) -> u64 {
+        self.base_offset
+    }
+
+    /// Versi format segment (1 = checksum legacy)
+    pub fn version(&self) -> u32 {
+        read_u32(&self.mmap, 8)
+    }
+
+    /// Ukuran file segment dalam bytes
+    pub fn size(&self) -> usize {
+        self.mmap.len()
+    }
+
+    /// Scan record valid dan cek apakah ada tail rusak
+    pub fn scan(&self) -> SegmentScan {
+        let mut end = SEGMENT_HEADER_SIZE;
+        let mut records = 0;
+        while let Some((_, next)) = Segment::validate_at(&self.mmap, end, self.checksum) {
+            end = next;
+            records += 1;
+        }
+        SegmentScan {
+            records,
+            end,
+            torn: has_tail(&self.mmap, end),
+        }
+    }
+
+    /// Iterasi `(offset, record)` yang lolos validasi checksum
+    pub fn records(&self) -> impl Iterator<Item = (u64, &[u8])> {
+        let mut pos = SEGMENT_HEADER_SIZE;
+        let mut offset = self.base_offset;
+        std::iter::from_fn(move || {
+            let (payload, next) = Segment::validate_at(&self.mmap, pos, self.checksum)?;
+            let item = (offset, payload);
+            pos = next;
+            offset += 1;
+            Some(item)
+        })
+    }
+}
+
+/// Segment yang sudah dilepas dari log dan menunggu dihapus
+pub struct RetiredSegment {
+    segment: Segment,
+}
+
+impl RetiredSegment {
+    /// Offset record pertama di segment ini
+    pub fn base_offset(&self) -> u64 {
+        self.segment.base_offset
+    }
+
+    /// Jumlah record di segment ini
+    pub fn records(&self) -> u64 {
+        self.segment.records
+    }
+
+    /// Unmap lalu hapus file segment dan index-nya
+    pub fn remove(self) -> io::Result<()> {
+        let Segment {
+            path, mmap, index, ..
+        } = self.segment;
+        drop(mmap);
+        fs::remove_file(path)?;
+        fs::remove_file(&index.path).ok();
+        Ok(())
+    }
+}
+
+/// Iterator `(offset, payload)` di atas `SegmentedLog`
+#[derive(Clone)]
+pub struct LogIter<'a> {
+    log: &'a SegmentedLog,
+    segment: usize,
+    pos: usize,
+    offset: u64,
+}
+
+impl<'a> Iterator for LogIter<'a> {
+    type Item = (u64, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let segment = self.log.segments.get(self.segment)?;
+
+            if let Some((payload, next)) = segment.record_at(self.pos) {
+                let offset = self.offset;
+                self.pos = next;
+                self.offset += 1;
+                return Some((offset, payload));
+            }
+
+            // Segment habis: lanjut ke segment berikutnya
+            self.segment += 1;
+            self.pos = SEGMENT_HEADER_SIZE;
+            if let Some(next) = self.log.segments.get(self.segment) {
+                self.offset = next.base_offset;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::MessageType;
+    use std::time::{Duration, Instant};
+
+    fn fresh_dir(name: &str) -> PathBuf {
+        let dir = PathBuf::from(name);
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    fn message(i: u64) -> Vec<u8> {
+        format!("message-{:04}", i).into_bytes()
+    }
+
+    /// Frame Hermes dengan sequence `i` dan timestamp `1_000 + 10 * i`
+    fn frame(i: u64) -> Vec<u8> {
+        let payload = message(i);
+        let mut header = MessageHeader::new(MessageType::Publish, i, payload.len() as u32);
+        header.timestamp_ns = 1_000 + 10 * i;
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    #[test]
+    fn test_log_append_read() {
+        let dir = fresh_dir("test_log_basic");
+
+        {
+            let mut log = SegmentedLog::open(&dir, 4096).unwrap();
+            assert_eq!(log.next_offset(), 0);
+
+            for i in 0..10 {
+                assert_eq!(log.append(&message(i)).unwrap(), i);
+            }
+
+            assert_eq!(log.read(0).unwrap(), b"message-0000");
+            assert_eq!(log.read(9).unwrap(), b"message-0009");
+            assert!(log.read(10).is_none());
+
+            let all: Vec<u64> = log.iter_from(0).map(|(offset, _)| offset).collect();
+            assert_eq!(all, (0..10).collect::<Vec<_>>());
+            assert_eq!(log.iter_from(7).next().unwrap().1, b"message-0007");
+
+            assert!(log.append(b"").is_err());
+            assert!(log.append(&[0u8; 8192]).is_err());
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_rolls_segments() {
+        let dir = fresh_dir("test_log_roll");
+
+        {
+            // 24 bytes per record -> 8 record per segment 256 bytes
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..50 {
+                assert_eq!(log.append(&message(i)).unwrap(), i);
+            }
+
+            assert_eq!(log.segment_count(), 7);
+            for i in 0..50 {
+                assert_eq!(log.read(i).unwrap(), message(i).as_slice());
+            }
+
+            let replay: Vec<(u64, Vec<u8>)> =
+                log.iter_from(5).map(|(o, p)| (o, p.to_vec())).collect();
+            assert_eq!(replay.len(), 45);
+            assert!(replay.iter().all(|(o, p)| *p == message(*o)));
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_storage_options() {
+        let dir = fresh_dir("test_log_options");
+        let options = StorageOptions::new()
+            .populate(true)
+            .prefault(true)
+            .sequential(true)
+            .lock(true);
+
+        {
+            // Roll: segment lama di-munlock, segment baru di-prefault + mlock
+            let mut log = SegmentedLog::open_with(&dir, 256, options).unwrap();
+            for i in 0..50 {
+                assert_eq!(log.append(&message(i)).unwrap(), i);
+            }
+            assert_eq!(log.segment_count(), 7);
+
+            // 8 record per segment: segment berikutnya disiapkan mulai record ke-3
+            assert!(log.next.is_none());
+            log.append(&message(50)).unwrap();
+            assert!(log.next.is_some());
+
+            // Roll memakai segment yang sudah disiapkan
+            for i in 51..57 {
+                log.append(&message(i)).unwrap();
+            }
+            assert_eq!(log.segment_count(), 8);
+            assert!(log.next.is_none());
+            assert!(segment_path(&dir, 56).exists());
+            assert!(!dir.join(NEXT_SEGMENT_FILE).exists());
+            log.append(&message(57)).unwrap();
+            log.append(&message(58)).unwrap();
+            assert!(log.next.is_some());
+        }
+        assert!(!dir.join(NEXT_SEGMENT_FILE).exists());
+        assert_eq!(segment_bases(&dir).unwrap().len(), 8);
+
+        {
+            let log = SegmentedLog::open_with(&dir, 256, options).unwrap();
+            assert_eq!(log.next_offset(), 59);
+            for i in 0..59 {
+                assert_eq!(log.read(i).unwrap(), message(i).as_slice());
+            }
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_reopen_continues_offsets() {
+        let dir = fresh_dir("test_log_reopen");
+
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..20 {
+                log.append(&message(i)).unwrap();
+            }
+            log.flush().unwrap();
+        }
+
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            assert_eq!(log.next_offset(), 20);
+            assert_eq!(log.read(13).unwrap(), message(13).as_slice());
+            assert_eq!(log.append(&message(20)).unwrap(), 20);
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_delete_before() {
+        let dir = fresh_dir("test_log_delete");
+
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..30 {
+                log.append(&message(i)).unwrap();
+            }
+            assert_eq!(log.segment_count(), 4);
+
+            // Segment [0, 8) dan [8, 16) dihapus utuh; [16, 24) masih berisi 20
+            assert_eq!(log.delete_before(20).unwrap(), 2);
+            assert_eq!(log.first_offset(), 16);
+            assert!(log.read(15).is_none());
+            assert_eq!(log.read(16).unwrap(), message(16).as_slice());
+            assert_eq!(log.iter_from(0).next().unwrap().0, 16);
+
+            // Segment aktif tidak pernah dihapus
+            log.delete_before(u64::MAX).unwrap();
+            assert_eq!(log.segment_count(), 1);
+            assert_eq!(log.append(&message(30)).unwrap(), 30);
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_fsync_policy() {
+        let dir = fresh_dir("test_log_fsync");
+
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            assert_eq!(log.fsync_policy(), FsyncPolicy::None);
+
+            // None: tidak ada sync eksplisit, termasuk saat roll
+            for i in 0..10 {
+                log.append(&message(i)).unwrap();
+            }
+            assert_eq!(log.unsynced(), 10);
+            assert!(log.segments.iter().all(|s| s.synced_pos < s.write_pos));
+            log.flush().unwrap();
+            assert!(log.segments.iter().all(|s| s.synced_pos == s.write_pos));
+
+            // EveryMessages: sync tiap 4 record
+            log.set_fsync_policy(FsyncPolicy::EveryMessages(4));
+            for i in 10..13 {
+                log.append(&message(i)).unwrap();
+            }
+            assert_eq!(log.unsynced(), 3);
+            log.append(&message(13)).unwrap();
+            assert_eq!(log.unsynced(), 0);
+            let active = log.segments.last().unwrap();
+            assert_eq!(active.synced_pos, active.write_pos);
+
+            // BeforeAck: append tidak pernah flush, tapi roll menunggu sync
+            log.set_fsync_policy(FsyncPolicy::BeforeAck);
+            let segments = log.segment_count();
+            for i in 14..30 {
+                log.append(&message(i)).unwrap();
+            }
+            assert_eq!(log.unsynced(), 16);
+            assert!(log.segment_count() > segments);
+            let (active, closed) = log.segments.split_last().unwrap();
+            assert!(closed.iter().all(|s| s.synced_pos == s.write_pos));
+            assert!(active.synced_pos < active.write_pos);
+            log.flush().unwrap();
+            assert_eq!(log.unsynced(), 0);
+
+            // Interval: fdatasync di thread latar, posisi aman maju di tick
+            log.set_fsync_policy(FsyncPolicy::Interval(Duration::from_millis(1)));
+            for i in 30..50 {
+                log.append(&message(i)).unwrap();
+            }
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while log.segments.iter().any(|s| s.synced_pos < s.write_pos) {
+                assert!(Instant::now() < deadline, "interval sync never completed");
+                std::thread::sleep(Duration::from_millis(2));
+                log.tick().unwrap();
+            }
+            assert_eq!(log.unsynced(), 0);
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_retention() {
+        let dir = fresh_dir("test_log_retention");
+
+        {
+            // Frame 44 bytes -> record 56 bytes -> 3 record per segment 256 bytes
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..30 {
+                log.append(&frame(i)).unwrap();
+            }
+            assert_eq!(log.segment_count(), 10);
+
+            // Umur: frame terakhir segment k bertimestamp 1_020 + 30k, cutoff 1_190
+            let by_age = RetentionPolicy {
+                max_age: Some(Duration::from_nanos(100)),
+                ..Default::default()
+            };
+            let mut retired = log.detach_expired_at(&by_age, 1_290, 7);
+            // Hanya segment yang seluruhnya < 7 (mis. progress compactor)
+            assert_eq!(retired.len(), 2);
+            assert_eq!(log.first_offset(), 6);
+            retired.extend(log.detach_expired_at(&by_age, 1_290, u64::MAX));
+            assert_eq!(retired.len(), 6);
+            assert_eq!(retired[5].base_offset(), 15);
+            assert_eq!(log.first_offset(), 18);
+            assert_eq!(log.iter_from_sequence(0).next().unwrap().0, 18);
+
+            // File baru hilang setelah remove (boleh di thread lain)
+            assert!(segment_path(&dir, 0).exists());
+            std::thread::spawn(move || {
+                for segment in retired {
+                    segment.remove().unwrap();
+                }
+            })
+            .join()
+            .unwrap();
+            assert!(!segment_path(&dir, 0).exists());
+            assert!(!index_path(&dir, 0).exists());
+
+            // Jumlah: sisa 12 record, minimal 6 tetap disimpan
+            let by_count = RetentionPolicy {
+                max_messages: Some(6),
+                ..Default::default()
+            };
+            assert_eq!(log.enforce_retention(&by_count).unwrap(), 2);
+            assert_eq!(log.first_offset(), 24);
+
+            // Ukuran: minimal 256 bytes (satu segment) tetap disimpan
+            let by_bytes = RetentionPolicy {
+                max_bytes: Some(256),
+                ..Default::default()
+            };
+            assert_eq!(log.enforce_retention(&by_bytes).unwrap(), 1);
+            assert_eq!(log.segment_count(), 1);
+            assert_eq!(log.size_bytes(), 256);
+            assert_eq!(log.append(&frame(30)).unwrap(), 30);
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_discards_torn_tail() {
+        let dir = fresh_dir("test_log_torn");
+
+        {
+            let mut log = SegmentedLog::open(&dir, 4096).unwrap();
+            for i in 0..3 {
+                log.append(&message(i)).unwrap();
+            }
+            log.flush().unwrap();
+        }
+
+        // Rusak payload record terakhir (checksum tidak cocok lagi)
+        let path = segment_path(&dir, 0);
+        let mut bytes = fs::read(&path).unwrap();
+        let end = SEGMENT_HEADER_SIZE + 2 * record_size(12);
+        bytes[end + RECORD_HEADER_SIZE] ^= 0xFF;
+        fs::write(&path, bytes).unwrap();
+
+        let scan = |dir: &Path| SegmentFile::open_all(dir).unwrap()[0].scan();
+        assert_eq!(
+            scan(&dir),
+            SegmentScan {
+                records: 2,
+                end,
+                torn: true
+            }
+        );
+
+        {
+            let mut log = SegmentedLog::open(&dir, 4096).unwrap();
+            assert_eq!(log.recovery().truncated_at, Some(end));
+            assert_eq!(log.next_offset(), 2);
+            assert!(log.read(2).is_none());
+            assert_eq!(log.append(&message(2)).unwrap(), 2);
+            assert_eq!(log.read(2).unwrap(), message(2).as_slice());
+        }
+        assert!(!scan(&dir).torn);
+        assert_eq!(scan(&dir).records, 3);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_reports_corrupt_closed_segment() {
+        let dir = fresh_dir("test_log_corrupt_closed");
+
+        {
+            // 8 record per segment: segment 0 dan 8 tertutup, 16 aktif
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            for i in 0..20 {
+                log.append(&message(i)).unwrap();
+            }
+            log.flush().unwrap();
+        }
+
+        // Record ke-5 segment 8 rusak: bukan torn write, file tidak diubah
+        let path = segment_path(&dir, 8);
+        let mut bytes = fs::read(&path).unwrap();
+        let pos = SEGMENT_HEADER_SIZE + 5 * record_size(12);
+        bytes[pos + RECORD_HEADER_SIZE] ^= 0xFF;
+        fs::write(&path, &bytes).unwrap();
+
+        {
+            let log = SegmentedLog::open(&dir, 256).unwrap();
+            assert_eq!(log.recovery().corrupt, vec![(8, pos)]);
+            assert_eq!(log.recovery().truncated_at, None);
+            assert_eq!(log.read(12).unwrap(), message(12).as_slice());
+            assert!(log.read(13).is_none());
+            assert_eq!(log.read(16).unwrap(), message(16).as_slice());
+            assert_eq!(log.next_offset(), 20);
+        }
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_segment_versions() {
+        let dir = fresh_dir("test_log_versions");
+        let path = segment_path(&dir, 0);
+        let checksum_at = |pos: usize| read_u32(&fs::read(&path).unwrap(), pos + 4);
+
+        // Segment baru: record di-checksum dengan CRC-32C
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            log.append(&message(0)).unwrap();
+        }
+        assert_eq!(read_u32(&fs::read(&path).unwrap(), 8), SEGMENT_VERSION);
+        assert_eq!(checksum_at(SEGMENT_HEADER_SIZE), crc32c(&message(0)));
+
+        // Segment v1 tetap dibaca dan di-append dengan checksum legacy
+        fs::remove_dir_all(&dir).unwrap();
+        drop(SegmentedLog::open(&dir, 256).unwrap());
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[8..12].copy_from_slice(&SEGMENT_VERSION_LEGACY.to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+        {
+            let mut log = SegmentedLog::open(&dir, 256).unwrap();
+            log.append(&message(0)).unwrap();
+        }
+        assert_eq!(
+            checksum_at(SEGMENT_HEADER_SIZE),
+            legacy_checksum(&message(0))
+        );
+        {
+            let log = SegmentedLog::open(&dir, 256).unwrap();
+            assert_eq!(log.read(0).unwrap(), message(0).as_slice());
+        }
+
+        // Versi lebih baru ditolak
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[8..12].copy_from_slice(&(SEGMENT_VERSION + 1).to_le_bytes());
+        fs::write(&path, bytes).unwrap();
+        let err = SegmentedLog::open(&dir, 256).err().unwrap();
+        assert!(err.to_string().contains("unsupported version"));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_index_lookup() {
+        let dir = fresh_dir("test_log_index");
+
+        {
+            // 56 bytes per record -> ~16 entry index per segment 64KB
+            let mut log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+            for i in 0..5000 {
+                log.append(&frame(i)).unwrap();
+            }
+            assert_eq!(log.segment_count(), 5);
+            assert!(log.segments[..4].iter().all(|s| s.index.entries.len() > 10));
+
+            for sequence in [0, 1, 1169, 1170, 2500, 4999] {
+                let (offset, payload) = log.iter_from_sequence(sequence).next().unwrap();
+                assert_eq!(offset, sequence);
+                assert_eq!(payload, frame(sequence).as_slice());
+            }
+            assert!(log.iter_from_sequence(5000).next().is_none());
+
+            // Timestamp di antara dua frame -> frame berikutnya
+            assert_eq!(
+                log.iter_from_timestamp(1_000 + 10 * 3210 + 5)
+                    .next()
+                    .unwrap()
+                    .0,
+                3211
+            );
+            assert_eq!(log.iter_from_timestamp(0).next().unwrap().0, 0);
+            assert_eq!(log.iter_from_timestamp(1_000 + 10 * 4000).count(), 1000);
+
+            // Lookup offset memakai index yang sama
+            assert_eq!(log.read(4321).unwrap(), frame(4321).as_slice());
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_index_lookup_multiple_publishers() {
+        let dir = fresh_dir("test_log_index_publishers");
+        let publish = |sequence: u64, timestamp_ns: u64| {
+            let payload = message(sequence);
+            let mut header =
+                MessageHeader::new(MessageType::Publish, sequence, payload.len() as u32);
+            header.timestamp_ns = timestamp_ns;
+            let mut bytes = header.as_bytes().to_vec();
+            bytes.extend_from_slice(&payload);
+            bytes
+        };
+
+        {
+            // A: sequence 0.., B: sequence 10_000.. dengan clock 3ns tertinggal,
+            // jadi sequence maupun timestamp tidak terurut di log
+            let mut log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+            for i in 0..2500 {
+                log.append(&publish(i, 1_000 + 10 * i)).unwrap();
+                log.append(&publish(10_000 + i, 997 + 10 * i)).unwrap();
+            }
+            assert_eq!(log.segment_count(), 5);
+            for s in &log.segments {
+                assert!(!s.keys.is_sorted(SeekKey::Sequence));
+                assert!(!s.keys.is_sorted(SeekKey::Timestamp));
+            }
+
+            // Frame pertama dalam urutan log dengan key >= target
+            assert_eq!(
+                log.iter_from_sequence(11_234).next().unwrap().0,
+                2 * 1234 + 1
+            );
+            assert_eq!(log.iter_from_sequence(12_499).next().unwrap().0, 4999);
+            assert_eq!(log.iter_from_sequence(1234).next().unwrap().0, 1);
+            assert!(log.iter_from_sequence(12_500).next().is_none());
+
+            assert_eq!(log.iter_from_timestamp(18_997).next().unwrap().0, 3600);
+            assert_eq!(log.iter_from_timestamp(19_001).next().unwrap().0, 3602);
+            assert_eq!(log.iter_from_timestamp(25_990).next().unwrap().0, 4998);
+            assert!(log.iter_from_timestamp(25_991).next().is_none());
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_index_rebuilt_on_open() {
+        let dir = fresh_dir("test_log_index_rebuild");
+
+        let entries = {
+            let mut log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+            for i in 0..1000 {
+                log.append(&frame(i)).unwrap();
+            }
+            log.flush().unwrap();
+            log.segments[0].index.entries.clone()
+        };
+
+        // Index yang tersimpan dipakai ulang apa adanya
+        {
+            let log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+            assert_eq!(log.segments[0].index.entries, entries);
+            assert!(log.recovery().rebuilt_indexes.is_empty());
+        }
+
+        // Index hilang -> dibangun ulang dari segment
+        fs::remove_file(index_path(&dir, 0)).unwrap();
+        {
+            let log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+            assert_eq!(log.recovery().rebuilt_indexes, vec![0]);
+            assert_eq!(log.segments[0].index.entries, entries);
+            assert_eq!(log.iter_from_sequence(777).next().unwrap().0, 777);
+        }
+
+        // Index rusak di tengah -> entry sisanya dibangun ulang
+        let path = index_path(&dir, 0);
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[INDEX_ENTRY_SIZE * 3] ^= 0xFF;
+        bytes.truncate(bytes.len() - 5);
+        fs::write(&path, bytes).unwrap();
+        {
+            let mut log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+            assert_eq!(log.segments[0].index.entries, entries);
+            log.append(&frame(1000)).unwrap();
+        }
+        assert_eq!(
+            fs::read(&path).unwrap().len() % INDEX_ENTRY_SIZE,
+            0,
+            "index file rewritten in whole entries"
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_log_index_write_failure_is_not_fatal() {
+        let dir = fresh_dir("test_log_index_stale");
+
+        let entries = {
+            let mut log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+            log.append(&frame(0)).unwrap();
+            // Handle read-only: setiap write ke file index gagal
+            let index = &mut log.active_mut().index;
+            index.file = File::open(&index.path).unwrap();
+
+            for i in 1..1000 {
+                assert_eq!(log.append(&frame(i)).unwrap(), i);
+            }
+            let index = &log.segments[0].index;
+            assert_eq!(
+                log.stale_indexes()
+                    .map(|(base, _)| base)
+                    .collect::<Vec<_>>(),
+                [0]
+            );
+            assert!(index.entries.len() > 10);
+            assert_eq!(log.iter_from_sequence(777).next().unwrap().0, 777);
+            let entries = index.entries.clone();
+            log.flush().unwrap();
+            entries
+        };
+
+        // File index yang tertinggal dibangun ulang saat open
+        let log = SegmentedLog::open(&dir, 64 * 1024).unwrap();
+        assert_eq!(log.segments[0].index.entries, entries);
+        assert_eq!(log.stale_indexes().count(), 0);
+        assert_eq!(log.recovery().rebuilt_indexes, vec![0]);
+        assert_eq!(
+            fs::read(index_path(&dir, 0)).unwrap().len(),
+            entries.len() * INDEX_ENTRY_SIZE
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
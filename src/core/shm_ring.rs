@@ -0,0 +1,1027 @@
+//! Cross-Process Shared-Memory Ring (IPC tanpa socket)
+//!
+//! Byte ring (framing sama dengan `ByteRing`) yang hidup di file mmap,
+//! sehingga proses lain di mesin yang sama bisa attach sebagai producer
+//! atau consumer. Satu producer, sampai `MAX_CONSUMERS` consumer; setiap
+//! consumer menerima setiap frame (SPSC jika hanya satu consumer, SPMC
+//! broadcast jika lebih). Producer di-gate oleh consumer hidup paling lambat.
+//!
+//! On-disk layout (little-endian, semua offset dalam bytes):
+//! ```text
+//! 0      magic: u64 ("HERMESHM")       8   version: u32
+//! 12     max_consumers: u32            16  capacity: u64 (data region)
+//! 24..64 reserved
+//! 64     head: u64 (posisi byte producer, monotonic)
+//! 72     producer_pid: u32             80  producer_heartbeat_ns: u64
+//! 128 + 64*i  consumer slot i (i < MAX_CONSUMERS):
+//!        cursor: u64, pid: u32, epoch: u32, heartbeat_ns: u64
+//! 4096   data region (capacity bytes, record: [len u32][reserved u32][payload][pad ke 8])
+//! ```
+//! `pid == 0` berarti slot kosong; `epoch` naik setiap kali slot di-attach,
+//! sehingga consumer yang slot-nya sudah dilepas tahu dirinya bukan pemilik
+//! lagi. Liveness peer dideteksi lewat pid (`kill(pid, 0)` pada Unix) dan
+//! heartbeat timestamp.
+//!
+//! Pembuatan file diserialisasi dengan `flock` (Unix): header hanya
+//! diinisialisasi oleh pemanggil yang membuat file; file yang sudah ada tapi
+//! asing, versi lain, atau belum selesai diinisialisasi ditolak, tidak ditimpa.
+
+use memmap2::{MmapMut, MmapOptions};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::byte_ring::{FrameRegion, Record};
+use super::ring_buffer::CacheLinePadded;
+
+const MAGIC: u64 = 0x4845524D4553484D; // "HERMESHM" in hex
+const VERSION: u32 = 1;
+
+/// Jumlah maksimum consumer yang bisa attach bersamaan
+pub const MAX_CONSUMERS: usize = 8;
+/// Offset data region (page-aligned)
+const DATA_OFFSET: usize = 4096;
+/// Interval minimum antar update heartbeat di jalur cepat (claim/read)
+const HEARTBEAT_INTERVAL_NS: u64 = 1_000_000;
+
+#[repr(C)]
+struct ProducerState {
+    head: AtomicU64,
+    pid: AtomicU32,
+    _reserved: u32,
+    heartbeat_ns: AtomicU64,
+}
+
+#[repr(C)]
+struct ConsumerState {
+    cursor: AtomicU64,
+    pid: AtomicU32,
+    epoch: AtomicU32,
+    heartbeat_ns: AtomicU64,
+}
+
+/// Header shm ring - di-cast langsung dari awal mmap region
+#[repr(C, align(64))]
+struct ShmHeader {
+    magic: u64,
+    version: u32,
+    max_consumers: u32,
+    capacity: u64,
+    producer: CacheLinePadded<ProducerState>,
+    consumers: [CacheLinePadded<ConsumerState>; MAX_CONSUMERS],
+}
+
+const _: () = assert!(std::mem::size_of::<ShmHeader>() <= DATA_OFFSET);
+
+/// Timestamp saat ini dalam nanoseconds (epoch) untuk heartbeat
+#[inline(always)]
+fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Timestamp murah untuk throttle heartbeat (resolusi beberapa ms)
+#[inline(always)]
+fn coarse_now_ns() -> u64 {
+    #[cfg(target_os = "linux")]
+    {
+        let mut ts = libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        // SAFETY: ts valid; CLOCK_REALTIME_COARSE lewat vDSO tanpa syscall
+        if unsafe { libc::clock_gettime(libc::CLOCK_REALTIME_COARSE, &mut ts) } == 0 {
+            return ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64;
+        }
+    }
+    now_ns()
+}
+
+#[inline(always)]
+fn current_pid() -> u32 {
+    std::process::id()
+}
+
+/// Cek apakah proses dengan `pid` masih hidup
+fn pid_alive(pid: u32) -> bool {
+    if pid == 0 {
+        return false;
+    }
+
+    #[cfg(unix)]
+    {
+        // Signal 0: hanya cek keberadaan proses, tidak mengirim apa-apa
+        let rc = unsafe { libc::kill(pid as libc::pid_t, 0) };
+        rc == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+
+    #[cfg(not(unix))]
+    {
+        // Tanpa API portable: andalkan heartbeat saja
+        true
+    }
+}
+
+/// Kunci eksklusif file sampai `file` ditutup (serialisasi pembuatan ring)
+fn lock_exclusive(file: &File) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+        // Blocking: tunggu creator lain selesai menginisialisasi header
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = file;
+    Ok(())
+}
+
+/// Status liveness peer
+fn peer_alive(pid: u32, heartbeat_ns: u64, timeout: Option<Duration>) -> bool {
+    if !pid_alive(pid) {
+        return false;
+    }
+    match timeout {
+        Some(timeout) => now_ns().saturating_sub(heartbeat_ns) <= timeout.as_nanos() as u64,
+        None => true,
+    }
+}
+
+/// Mapping file shm bersama untuk producer dan consumer
+struct ShmMapping {
+    mmap: MmapMut,
+    region: FrameRegion,
+}
+
+impl ShmMapping {
+    /// Map file shm; dengan `create_capacity`, file baru dibuat dan diinisialisasi
+    ///
+    /// Header hanya ditulis jika panggilan ini yang membuat file (kosong);
+    /// selain itu header divalidasi dan error `InvalidData` jika tidak cocok.
+    /// Lock file dipegang sampai inisialisasi selesai.
+    fn map<P: AsRef<Path>>(path: P, create_capacity: Option<usize>) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(create_capacity.is_some())
+            .truncate(false)
+            .open(path)?;
+        if create_capacity.is_some() {
+            lock_exclusive(&file)?;
+        }
+
+        let existing_len = file.metadata()?.len() as usize;
+
+        let capacity = match create_capacity {
+            Some(capacity) => {
+                assert!(
+                    capacity >= 64 && capacity.is_power_of_two(),
+                    "Capacity must be power of 2 (min 64)"
+                );
+                if existing_len == 0 {
+                    file.set_len((DATA_OFFSET + capacity) as u64)?;
+                } else if existing_len != DATA_OFFSET + capacity {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "shm ring file exists with different capacity",
+                    ));
+                }
+                capacity
+            }
+            None => {
+                if existing_len <= DATA_OFFSET {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "shm ring file not initialized",
+                    ));
+                }
+                existing_len - DATA_OFFSET
+            }
+        };
+
+        // SAFETY: File sudah dibuka dengan read/write permission
+        let mut mmap = unsafe {
+            MmapOptions::new()
+                .len(DATA_OFFSET + capacity)
+                .map_mut(&file)?
+        };
+
+        // SAFETY: DATA_OFFSET page-aligned, capacity power of 2
+        let region = unsafe { FrameRegion::new(mmap.as_mut_ptr().add(DATA_OFFSET), capacity) };
+
+        let mut shm = Self { mmap, region };
+        if create_capacity.is_some() {
+            if existing_len == 0 {
+                shm.init();
+            } else {
+                shm.validate()?;
+            }
+        }
+        Ok(shm)
+    }
+
+    #[inline(always)]
+    fn header(&self) -> &ShmHeader {
+        // SAFETY: Header berada di awal mmap region (page-aligned)
+        unsafe { &*(self.mmap.as_ptr() as *const ShmHeader) }
+    }
+
+    /// Validasi header dari file yang sudah ada
+    fn validate(&self) -> io::Result<()> {
+        let header = self.header();
+        // SAFETY: magic ditulis terakhir oleh initializer (lihat `init`)
+        let magic = unsafe { std::ptr::read_volatile(&header.magic) };
+        fence(Ordering::Acquire);
+
+        if magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a Hermes shm ring (bad magic)",
+            ));
+        }
+        if header.version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported shm ring version {}", header.version),
+            ));
+        }
+        if header.capacity as usize != self.region.capacity()
+            || header.max_consumers as usize != MAX_CONSUMERS
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "shm ring header does not match file size",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Inisialisasi header file baru (dipanggil dengan lock file dipegang)
+    fn init(&mut self) {
+        let capacity = self.region.capacity() as u64;
+        // SAFETY: Header berada di awal mmap region; file baru dibuat panggilan
+        // ini dan creator lain menunggu lock, consumer menolak file tanpa magic
+        let header = unsafe { &mut *(self.mmap.as_mut_ptr() as *mut ShmHeader) };
+
+        header.version = VERSION;
+        header.max_consumers = MAX_CONSUMERS as u32;
+        header.capacity = capacity;
+        header.producer.value.head.store(0, Ordering::Relaxed);
+        for slot in header.consumers.iter() {
+            slot.value.pid.store(0, Ordering::Relaxed);
+            slot.value.cursor.store(0, Ordering::Relaxed);
+        }
+
+        // Magic ditulis terakhir: consumer yang melihat magic melihat header lengkap
+        fence(Ordering::Release);
+        // SAFETY: pointer valid ke field header
+        unsafe { std::ptr::write_volatile(&mut header.magic, MAGIC) };
+    }
+}
+
+/// Producer side shm ring (satu per file)
+pub struct ShmProducer {
+    shm: ShmMapping,
+    // Cache posisi consumer hidup paling lambat - dihitung ulang saat ruang habis
+    gate: usize,
+    consumer_timeout: Option<Duration>,
+    // Heartbeat terakhir yang ditulis (throttle jalur cepat)
+    last_heartbeat: u64,
+}
+
+// SAFETY: Pointer mentah di FrameRegion menunjuk ke mmap milik struct ini
+unsafe impl Send for ShmProducer {}
+
+impl ShmProducer {
+    /// Membuat (atau membuka ulang) shm ring dan attach sebagai producer
+    ///
+    /// File yang sudah ada dengan kapasitas sama dipakai ulang (head dilanjutkan),
+    /// sehingga consumer yang masih attach tidak kehilangan posisi. File asing
+    /// atau dengan header tidak valid gagal dengan `InvalidData`.
+    /// Gagal dengan `AlreadyExists` jika producer lain yang masih hidup sudah attach.
+    pub fn create<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        let shm = ShmMapping::map(path, Some(capacity))?;
+
+        let me = current_pid();
+        let producer = &shm.header().producer.value;
+        let previous = producer.pid.load(Ordering::Acquire);
+
+        if previous != 0 && previous != me && pid_alive(previous) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("shm ring already has a live producer (pid {})", previous),
+            ));
+        }
+        if producer
+            .pid
+            .compare_exchange(previous, me, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "another producer attached concurrently",
+            ));
+        }
+        let last_heartbeat = now_ns();
+        producer
+            .heartbeat_ns
+            .store(last_heartbeat, Ordering::Release);
+
+        let head = producer.head.load(Ordering::Relaxed) as usize;
+        Ok(Self {
+            shm,
+            gate: head,
+            consumer_timeout: None,
+            last_heartbeat,
+        })
+    }
+
+    /// Consumer yang heartbeat-nya lebih tua dari `timeout` dianggap mati
+    ///
+    /// Default `None`: hanya consumer yang prosesnya sudah tidak ada yang dilepas.
+    /// Consumer yang dilepas mendapat error dari `ShmConsumer::read`.
+    pub fn set_consumer_timeout(&mut self, timeout: Option<Duration>) {
+        self.consumer_timeout = timeout;
+    }
+
+    /// Klaim ruang untuk frame berukuran `len` bytes (zero-copy write)
+    ///
+    /// Returns `None` jika consumer paling lambat belum memberi ruang.
+    #[inline(always)]
+    pub fn claim(&mut self, len: usize) -> Option<ShmWriteClaim<'_>> {
+        let head = self
+            .shm
+            .header()
+            .producer
+            .value
+            .head
+            .load(Ordering::Relaxed) as usize;
+
+        // SAFETY: Hanya satu producer per file (dijamin oleh pid di header)
+        let record = match unsafe { self.shm.region.reserve(head, self.gate, len) } {
+            Some(record) => record,
+            None => {
+                // Slow path: hitung ulang consumer paling lambat
+                self.gate = self.slowest_consumer(head);
+                self.heartbeat();
+                unsafe { self.shm.region.reserve(head, self.gate, len)? }
+            }
+        };
+
+        Some(ShmWriteClaim {
+            producer: self,
+            record,
+        })
+    }
+
+    /// Copy frame ke ring (convenience di atas `claim`)
+    #[inline(always)]
+    pub fn push(&mut self, frame: &[u8]) -> bool {
+        match self.claim(frame.len()) {
+            Some(mut claim) => {
+                claim.copy_from_slice(frame);
+                claim.commit();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Posisi consumer hidup paling lambat; consumer mati dilepas dari slot-nya
+    #[cold]
+    fn slowest_consumer(&self, head: usize) -> usize {
+        // Berpasangan dengan fence di `ShmConsumer::attach`
+        fence(Ordering::SeqCst);
+
+        let mut slowest = head;
+        for slot in self.shm.header().consumers.iter() {
+            let state = &slot.value;
+            let pid = state.pid.load(Ordering::Acquire);
+            if pid == 0 {
+                continue;
+            }
+
+            let heartbeat = state.heartbeat_ns.load(Ordering::Relaxed);
+            if !peer_alive(pid, heartbeat, self.consumer_timeout) {
+                // Consumer crash: jangan biarkan dia menahan producer selamanya
+                let _ = state
+                    .pid
+                    .compare_exchange(pid, 0, Ordering::AcqRel, Ordering::Relaxed);
+                continue;
+            }
+
+            let cursor = state.cursor.load(Ordering::Acquire) as usize;
+            if head.wrapping_sub(cursor) > head.wrapping_sub(slowest) {
+                slowest = cursor;
+            }
+        }
+        slowest
+    }
+
+    /// Update heartbeat producer (panggil periodik jika jarang publish)
+    ///
+    /// `commit` juga memperbarui heartbeat, paling sering sekali per
+    /// `HEARTBEAT_INTERVAL_NS`.
+    #[inline]
+    pub fn heartbeat(&self) {
+        self.shm
+            .header()
+            .producer
+            .value
+            .heartbeat_ns
+            .store(now_ns(), Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn refresh_heartbeat(&mut self) {
+        let now = coarse_now_ns();
+        if now.saturating_sub(self.last_heartbeat) >= HEARTBEAT_INTERVAL_NS {
+            self.last_heartbeat = now;
+            self.shm
+                .header()
+                .producer
+                .value
+                .heartbeat_ns
+                .store(now, Ordering::Release);
+        }
+    }
+
+    /// Jumlah consumer yang sedang attach
+    pub fn consumer_count(&self) -> usize {
+        self.shm
+            .header()
+            .consumers
+            .iter()
+            .filter(|slot| slot.value.pid.load(Ordering::Acquire) != 0)
+            .count()
+    }
+
+    /// Kapasitas data region dalam bytes
+    pub fn capacity(&self) -> usize {
+        self.shm.region.capacity()
+    }
+}
+
+impl Drop for ShmProducer {
+    fn drop(&mut self) {
+        // Lepas slot producer agar proses lain bisa attach
+        let _ = self.shm.header().producer.value.pid.compare_exchange(
+            current_pid(),
+            0,
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        );
+    }
+}
+
+/// Ruang yang sudah diklaim producer, belum terlihat oleh consumer
+pub struct ShmWriteClaim<'p> {
+    producer: &'p mut ShmProducer,
+    record: Record,
+}
+
+impl ShmWriteClaim<'_> {
+    /// Publish frame ke semua consumer
+    #[inline(always)]
+    pub fn commit(self) {
+        let head = &self.producer.shm.header().producer.value.head;
+        let pos = head.load(Ordering::Relaxed);
+        // Release: payload visible (juga lintas proses) sebelum head di-update
+        head.store(
+            pos.wrapping_add(self.record.advance as u64),
+            Ordering::Release,
+        );
+        // Producer yang tidak pernah kena backpressure tetap terlihat hidup
+        self.producer.refresh_heartbeat();
+    }
+}
+
+impl Deref for ShmWriteClaim<'_> {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        let ptr = self.producer.shm.region.payload(&self.record);
+        // SAFETY: Region ini eksklusif milik claim sampai commit
+        unsafe { std::slice::from_raw_parts(ptr, self.record.len) }
+    }
+}
+
+impl DerefMut for ShmWriteClaim<'_> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let ptr = self.producer.shm.region.payload(&self.record);
+        // SAFETY: Region ini eksklusif milik claim sampai commit
+        unsafe { std::slice::from_raw_parts_mut(ptr, self.record.len) }
+    }
+}
+
+/// Consumer side shm ring
+pub struct ShmConsumer {
+    shm: ShmMapping,
+    slot: usize,
+    next: u64,
+    // (pid, epoch) yang tercatat di slot selama consumer ini memilikinya
+    pid: u32,
+    epoch: u32,
+    // Heartbeat terakhir yang ditulis (throttle jalur cepat)
+    last_heartbeat: u64,
+}
+
+// SAFETY: Pointer mentah di FrameRegion menunjuk ke mmap milik struct ini
+unsafe impl Send for ShmConsumer {}
+
+impl ShmConsumer {
+    /// Attach ke shm ring yang sudah dibuat producer
+    ///
+    /// Consumer mulai dari frame berikutnya yang dipublish.
+    /// Gagal jika file belum diinisialisasi atau semua slot consumer terpakai.
+    pub fn attach<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let shm = ShmMapping::map(path, None)?;
+        shm.validate()?;
+
+        let me = current_pid();
+        let header = shm.header();
+
+        for (slot, state) in header.consumers.iter().enumerate() {
+            let state = &state.value;
+            let pid = state.pid.load(Ordering::Acquire);
+
+            // Slot kosong, atau milik consumer yang sudah mati
+            let reclaimable = pid == 0 || (pid != me && !pid_alive(pid));
+            if !reclaimable {
+                continue;
+            }
+
+            // Cursor di-set sebelum slot terlihat aktif oleh producer
+            let head = header.producer.value.head.load(Ordering::Acquire);
+            let last_heartbeat = now_ns();
+            state.cursor.store(head, Ordering::Release);
+            state.heartbeat_ns.store(last_heartbeat, Ordering::Release);
+
+            if state
+                .pid
+                .compare_exchange(pid, me, Ordering::AcqRel, Ordering::Relaxed)
+                .is_err()
+            {
+                continue;
+            }
+
+            let epoch = state.epoch.fetch_add(1, Ordering::AcqRel).wrapping_add(1);
+
+            fence(Ordering::SeqCst);
+            // Head mungkin sudah maju sebelum slot terlihat oleh producer
+            let next = header.producer.value.head.load(Ordering::Acquire);
+            state.cursor.store(next, Ordering::Release);
+
+            return Ok(Self {
+                shm,
+                slot,
+                next,
+                pid: me,
+                epoch,
+                last_heartbeat,
+            });
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            "all shm ring consumer slots are in use",
+        ))
+    }
+
+    #[inline(always)]
+    fn state(&self) -> &ConsumerState {
+        &self.shm.header().consumers[self.slot].value
+    }
+
+    /// Baca frame berikutnya (zero-copy)
+    ///
+    /// Ruang frame dikembalikan ke producer saat guard di-drop.
+    /// Returns `Ok(None)` jika tidak ada frame baru.
+    ///
+    /// # Errors
+    /// - `ConnectionAborted` jika slot sudah dilepas producer (heartbeat
+    ///   melewati consumer timeout); attach ulang untuk lanjut dari head.
+    /// - `InvalidData` jika record di posisi cursor tidak konsisten (file rusak).
+    #[inline(always)]
+    pub fn read(&mut self) -> io::Result<Option<ShmReadGuard<'_>>> {
+        // Slot yang sudah dilepas tidak lagi menahan producer: data bisa tertimpa
+        if !self.is_attached() {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "shm ring consumer slot was released by the producer",
+            ));
+        }
+
+        let head = self
+            .shm
+            .header()
+            .producer
+            .value
+            .head
+            .load(Ordering::Acquire);
+
+        if self.next == head {
+            // Idle: waktu yang tepat untuk heartbeat
+            self.heartbeat();
+            return Ok(None);
+        }
+
+        // SAFETY: Record antara next dan head sudah di-publish; producer
+        // tidak menimpanya selama slot masih milik kita dan cursor belum maju
+        let Some(record) = (unsafe { self.shm.region.peek(self.next as usize, head as usize) })
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("corrupt shm ring record at position {}", self.next),
+            ));
+        };
+        // Consumer sibuk yang tidak pernah idle tetap terlihat hidup
+        self.refresh_heartbeat();
+
+        Ok(Some(ShmReadGuard {
+            consumer: self,
+            record,
+        }))
+    }
+
+    /// Returns `true` selama slot consumer ini belum dilepas producer
+    #[inline(always)]
+    pub fn is_attached(&self) -> bool {
+        let state = self.state();
+        state.pid.load(Ordering::Acquire) == self.pid
+            && state.epoch.load(Ordering::Acquire) == self.epoch
+    }
+
+    /// Update heartbeat consumer (otomatis dipanggil saat `read()`)
+    #[inline]
+    pub fn heartbeat(&self) {
+        self.state().heartbeat_ns.store(now_ns(), Ordering::Release);
+    }
+
+    #[inline(always)]
+    fn refresh_heartbeat(&mut self) {
+        let now = coarse_now_ns();
+        if now.saturating_sub(self.last_heartbeat) >= HEARTBEAT_INTERVAL_NS {
+            self.last_heartbeat = now;
+            self.state().heartbeat_ns.store(now, Ordering::Release);
+        }
+    }
+
+    /// Cek apakah producer masih hidup
+    ///
+    /// Producer dianggap mati jika tidak ada producer attach, prosesnya
+    /// sudah tidak ada, atau heartbeat-nya lebih tua dari `timeout`.
+    pub fn producer_alive(&self, timeout: Option<Duration>) -> bool {
+        let producer = &self.shm.header().producer.value;
+        peer_alive(
+            producer.pid.load(Ordering::Acquire),
+            producer.heartbeat_ns.load(Ordering::Acquire),
+            timeout,
+        )
+    }
+
+    /// Jumlah bytes yang sudah dipublish tapi belum dibaca consumer ini
+    pub fn lag(&self) -> usize {
+        let head = self
+            .shm
+            .header()
+            .producer
+            .value
+            .head
+            .load(Ordering::Acquire);
+        head.wrapping_sub(self.next) as usize
+    }
+}
+
+impl Drop for ShmConsumer {
+    fn drop(&mut self) {
+        // Lepas slot agar producer tidak lagi di-gate oleh consumer ini
+        if self.is_attached() {
+            let _ =
+                self.state()
+                    .pid
+                    .compare_exchange(self.pid, 0, Ordering::AcqRel, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Frame yang sedang dibaca consumer (zero-copy view ke mmap)
+pub struct ShmReadGuard<'c> {
+    consumer: &'c mut ShmConsumer,
+    record: Record,
+}
+
+impl Deref for ShmReadGuard<'_> {
+    type Target = [u8];
+
+    #[inline(always)]
+    fn deref(&self) -> &[u8] {
+        let ptr = self.consumer.shm.region.payload(&self.record);
+        // SAFETY: Producer tidak menyentuh region ini sampai cursor maju
+        unsafe { std::slice::from_raw_parts(ptr, self.record.len) }
+    }
+}
+
+impl Drop for ShmReadGuard<'_> {
+    #[inline(always)]
+    fn drop(&mut self) {
+        let consumer = &mut *self.consumer;
+        consumer.next = consumer.next.wrapping_add(self.record.advance as u64);
+        // Slot yang sudah dilepas mungkin dipakai consumer lain: jangan sentuh cursor-nya
+        if consumer.is_attached() {
+            // Release: read selesai sebelum producer boleh menimpa region
+            consumer
+                .state()
+                .cursor
+                .store(consumer.next, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::thread;
+
+    #[test]
+    fn test_shm_basic_roundtrip() {
+        let path = "test_shm_basic.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 4096).unwrap();
+            let mut consumer = ShmConsumer::attach(path).unwrap();
+            assert_eq!(producer.consumer_count(), 1);
+
+            assert!(consumer.read().unwrap().is_none());
+            assert!(producer.push(b"Hello, Hermes!"));
+
+            let mut claim = producer.claim(4).unwrap();
+            claim.copy_from_slice(b"IPC!");
+            claim.commit();
+
+            assert_eq!(&*consumer.read().unwrap().unwrap(), b"Hello, Hermes!");
+            assert_eq!(&*consumer.read().unwrap().unwrap(), b"IPC!");
+            assert!(consumer.read().unwrap().is_none());
+            assert!(consumer.producer_alive(Some(Duration::from_secs(5))));
+        }
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_broadcast_and_gating() {
+        let path = "test_shm_gating.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 64).unwrap();
+            let mut fast = ShmConsumer::attach(path).unwrap();
+            let mut slow = ShmConsumer::attach(path).unwrap();
+
+            // 4 record x 16 bytes = 64 bytes
+            for i in 0..4u8 {
+                assert!(producer.push(&[i; 8]));
+            }
+            for i in 0..4u8 {
+                assert_eq!(&*fast.read().unwrap().unwrap(), &[i; 8]);
+            }
+
+            // Slow consumer belum membaca: producer harus menunggu
+            assert!(!producer.push(&[9; 8]));
+            assert_eq!(&*slow.read().unwrap().unwrap(), &[0; 8]);
+            assert!(producer.push(&[4; 8])); // Now should succeed
+
+            // Consumer yang detach tidak lagi menahan producer
+            drop(slow);
+            assert_eq!(producer.consumer_count(), 1);
+            assert_eq!(&*fast.read().unwrap().unwrap(), &[4; 8]);
+            assert!(producer.push(&[5; 8]));
+        }
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_single_producer_and_reattach() {
+        let path = "test_shm_reattach.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 4096).unwrap();
+            let mut consumer = ShmConsumer::attach(path).unwrap();
+            assert!(producer.push(b"before restart"));
+            drop(producer);
+
+            // Producer restart: head dilanjutkan, consumer tidak kehilangan data
+            let mut producer = ShmProducer::create(path, 4096).unwrap();
+            assert!(producer.push(b"after restart"));
+            assert_eq!(&*consumer.read().unwrap().unwrap(), b"before restart");
+            assert_eq!(&*consumer.read().unwrap().unwrap(), b"after restart");
+
+            // Kapasitas berbeda ditolak
+            drop(producer);
+            assert!(ShmProducer::create(path, 8192).is_err());
+        }
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_dead_consumer_released() {
+        let path = "test_shm_dead.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 64).unwrap();
+            let consumer = ShmConsumer::attach(path).unwrap();
+
+            // Simulasikan consumer crash: slot masih terisi pid yang tidak ada
+            let slot = consumer.slot;
+            std::mem::forget(consumer);
+            producer.shm.header().consumers[slot]
+                .value
+                .pid
+                .store(u32::MAX - 1, Ordering::Release);
+
+            // Ring penuh tidak membuat producer macet selamanya
+            for i in 0..16u8 {
+                assert!(producer.push(&[i; 8]));
+            }
+            assert_eq!(producer.consumer_count(), 0);
+        }
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_busy_peers_refresh_heartbeat() {
+        let path = "test_shm_heartbeat.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 4096).unwrap();
+            let mut consumer = ShmConsumer::attach(path).unwrap();
+            producer.set_consumer_timeout(Some(Duration::from_secs(5)));
+
+            // Heartbeat basi, lalu hanya jalur cepat (tanpa backpressure / idle read)
+            producer
+                .shm
+                .header()
+                .producer
+                .value
+                .heartbeat_ns
+                .store(0, Ordering::Release);
+            consumer.state().heartbeat_ns.store(0, Ordering::Release);
+            producer.last_heartbeat = 0;
+            consumer.last_heartbeat = 0;
+
+            assert!(producer.push(b"busy"));
+            assert!(consumer.producer_alive(Some(Duration::from_secs(5))));
+            assert_eq!(&*consumer.read().unwrap().unwrap(), b"busy");
+            assert!(consumer.state().heartbeat_ns.load(Ordering::Acquire) > 0);
+
+            // Consumer sibuk tidak dilepas saat producer menghitung ulang gate
+            assert_eq!(producer.slowest_consumer(16), 16);
+            assert_eq!(producer.consumer_count(), 1);
+            assert!(consumer.is_attached());
+        }
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_evicted_consumer_read_fails() {
+        let path = "test_shm_evicted.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 64).unwrap();
+            let mut consumer = ShmConsumer::attach(path).unwrap();
+            producer.set_consumer_timeout(Some(Duration::from_millis(100)));
+
+            for i in 0..4u8 {
+                assert!(producer.push(&[i; 8]));
+            }
+            // Consumer macet: heartbeat basi, producer melepas slot saat ring penuh
+            consumer.state().heartbeat_ns.store(0, Ordering::Release);
+            assert!(producer.push(&[4; 8]));
+            assert_eq!(producer.consumer_count(), 0);
+
+            let err = consumer.read().err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::ConnectionAborted);
+            assert!(!consumer.is_attached());
+
+            // Slot dipakai consumer baru (proses sama): yang lama tetap bukan pemilik
+            let fresh = ShmConsumer::attach(path).unwrap();
+            assert_eq!(fresh.slot, consumer.slot);
+            assert!(consumer.read().is_err());
+            drop(consumer);
+            assert!(fresh.is_attached());
+            assert_eq!(producer.consumer_count(), 1);
+        }
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_corrupt_record_is_an_error() {
+        let path = "test_shm_corrupt.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 64).unwrap();
+            let mut consumer = ShmConsumer::attach(path).unwrap();
+            assert!(producer.push(&[1; 8]));
+
+            // Panjang record rusak (lebih besar dari region)
+            unsafe { producer.shm.region.write_len(0, 1000) };
+            let err = consumer.read().err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_attach_requires_initialized_file() {
+        let path = "test_shm_uninit.shm";
+        fs::write(path, vec![0u8; DATA_OFFSET + 4096]).unwrap();
+
+        assert!(ShmConsumer::attach(path).is_err());
+        assert!(ShmConsumer::attach("test_shm_missing.shm").is_err());
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_create_never_overwrites_existing_file() {
+        let path = "test_shm_foreign.shm";
+        let foreign = vec![0xAB; DATA_OFFSET + 4096];
+        fs::write(path, &foreign).unwrap();
+
+        // File asing dengan ukuran cocok: ditolak, isinya tidak disentuh
+        let err = ShmProducer::create(path, 4096).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(path).unwrap(), foreign);
+
+        // Versi lebih baru juga ditolak
+        fs::remove_file(path).ok();
+        drop(ShmProducer::create(path, 4096).unwrap());
+        let mut bytes = fs::read(path).unwrap();
+        bytes[8..12].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        fs::write(path, &bytes).unwrap();
+        let err = ShmProducer::create(path, 4096).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(fs::read(path).unwrap(), bytes);
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_shm_concurrent_transfer() {
+        const FRAMES: usize = 10_000;
+        let path = "test_shm_concurrent.shm";
+        fs::remove_file(path).ok();
+
+        {
+            let mut producer = ShmProducer::create(path, 4096).unwrap();
+            let mut consumer = ShmConsumer::attach(path).unwrap();
+
+            let reader = thread::spawn(move || {
+                let mut i = 0;
+                while i < FRAMES {
+                    match consumer.read().unwrap() {
+                        Some(frame) => {
+                            assert_eq!(frame.len(), 1 + i % 100);
+                            assert!(frame.iter().all(|&b| b == i as u8));
+                            i += 1;
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+            });
+
+            for i in 0..FRAMES {
+                let frame = vec![i as u8; 1 + i % 100];
+                while !producer.push(&frame) {
+                    thread::yield_now();
+                }
+            }
+
+            reader.join().unwrap();
+        }
+
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,293 @@
+//! Tuning mmap untuk storage: prefault, huge pages, madvise dan mlock
+//!
+//! Page fault saat sebuah page pertama kali disentuh terlihat di tail latency
+//! (P99.9). Opsi di sini memindahkan biaya itu ke saat region dibuka:
+//! - `populate`: `MAP_POPULATE`, kernel membaca file dan mengisi page table.
+//!   Untuk mapping shared, write pertama ke sebuah page tetap kena fault
+//!   (dirty tracking)
+//! - `prefault`: fault setiap page sebagai writable tanpa mengubah isinya
+//!   (`MADV_POPULATE_WRITE`, Linux 5.14+); fallback: read-touch setiap page.
+//!   Aman untuk file yang sedang ditulis proses lain
+//! - `huge_pages`: `Transparent` = `madvise(MADV_HUGEPAGE)`, best effort (untuk
+//!   file biasanya hanya efektif di tmpfs); `Explicit` = file harus berada di
+//!   hugetlbfs (mis. `/dev/hugepages`)
+//! - `sequential` / `will_need`: hint `madvise` untuk readahead
+//! - `lock`: `mlock` region panas supaya tidak pernah di-evict (dibatasi
+//!   `RLIMIT_MEMLOCK`)
+//!
+//! Semua opsi default mati. Huge pages hanya didukung di Linux.
+
+use memmap2::{Advice, MmapMut, MmapOptions};
+use std::fs::File;
+use std::io;
+
+/// Huge page mode untuk mapping storage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HugePages {
+    /// Page biasa
+    #[default]
+    Off,
+    /// Transparent huge pages via `madvise`
+    Transparent,
+    /// File di hugetlbfs; panjang mapping dibulatkan ke ukuran huge page
+    Explicit,
+}
+
+/// Builder opsi mmap untuk `MmapStorage` dan `SegmentedLog`
+///
+/// ```no_run
+/// use hermes::core::{HugePages, MmapStorage, StorageOptions};
+///
+/// let options = StorageOptions::new()
+///     .prefault(true)
+///     .huge_pages(HugePages::Transparent)
+///     .lock(true);
+/// let storage = MmapStorage::open_with("hermes_data.dat", 64 * 1024 * 1024, &options)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StorageOptions {
+    populate: bool,
+    prefault: bool,
+    huge_pages: HugePages,
+    sequential: bool,
+    will_need: bool,
+    lock: bool,
+}
+
+impl StorageOptions {
+    /// Semua opsi mati (sama dengan `Default`)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `MAP_POPULATE` saat mapping dibuat (Linux)
+    pub fn populate(mut self, enabled: bool) -> Self {
+        self.populate = enabled;
+        self
+    }
+
+    /// Fault setiap page saat region dibuka (isi page tidak ditulis)
+    pub fn prefault(mut self, enabled: bool) -> Self {
+        self.prefault = enabled;
+        self
+    }
+
+    /// Huge pages untuk mapping (default `HugePages::Off`)
+    ///
+    /// `Transparent` gagal di luar Linux; `Explicit` gagal jika file tidak
+    /// berada di hugetlbfs.
+    pub fn huge_pages(mut self, mode: HugePages) -> Self {
+        self.huge_pages = mode;
+        self
+    }
+
+    /// `madvise(MADV_SEQUENTIAL)`: readahead agresif, page lama cepat dibuang
+    pub fn sequential(mut self, enabled: bool) -> Self {
+        self.sequential = enabled;
+        self
+    }
+
+    /// `madvise(MADV_WILLNEED)`: mulai readahead seluruh region sekarang
+    pub fn will_need(mut self, enabled: bool) -> Self {
+        self.will_need = enabled;
+        self
+    }
+
+    /// `mlock` region panas
+    pub fn lock(mut self, enabled: bool) -> Self {
+        self.lock = enabled;
+        self
+    }
+
+    /// Returns `true` jika `lock` aktif
+    #[inline(always)]
+    pub(crate) fn locks(&self) -> bool {
+        self.lock
+    }
+
+    /// Returns `true` jika membuat region baru mahal (populate/prefault/mlock)
+    #[inline(always)]
+    pub(crate) fn warms(&self) -> bool {
+        self.populate || self.prefault || self.lock
+    }
+
+    /// Panjang file/mapping untuk region `len` bytes
+    ///
+    /// Untuk `HugePages::Explicit` dibulatkan ke ukuran huge page (hugetlbfs
+    /// menolak ukuran lain); gagal jika `file` tidak berada di hugetlbfs.
+    pub(crate) fn mapping_len(&self, file: &File, len: usize) -> io::Result<usize> {
+        if self.huge_pages != HugePages::Explicit {
+            return Ok(len);
+        }
+        let page = hugetlbfs_page_size(file)?;
+        Ok((len + page - 1) & !(page - 1))
+    }
+
+    /// Map `len` bytes pertama `file` (read/write) dengan opsi ini
+    ///
+    /// Hanya opsi mapping + hint yang diterapkan; `warm` untuk prefault/mlock.
+    pub(crate) fn map_mut(&self, file: &File, len: usize) -> io::Result<MmapMut> {
+        let mut options = MmapOptions::new();
+        options.len(len);
+        if self.populate {
+            options.populate();
+        }
+        // SAFETY: Caller membuka file dengan read/write permission
+        let mmap = unsafe { options.map_mut(file)? };
+
+        // Harus sebelum page disentuh supaya fault pertama sudah memakai huge page
+        if self.huge_pages == HugePages::Transparent {
+            advise_huge_pages(&mmap)?;
+        }
+        if self.sequential {
+            mmap.advise(Advice::Sequential)?;
+        }
+        if self.will_need {
+            mmap.advise(Advice::WillNeed)?;
+        }
+        Ok(mmap)
+    }
+
+    /// Prefault + mlock region yang akan dipakai di hot path
+    pub(crate) fn warm(&self, mmap: &mut MmapMut) -> io::Result<()> {
+        if self.prefault {
+            prefault(mmap);
+        }
+        if self.lock {
+            mmap.lock().map_err(|e| {
+                io::Error::new(e.kind(), format!("mlock failed: {} (check `ulimit -l`)", e))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    // SAFETY: sysconf tanpa side effect
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+        size as usize
+    } else {
+        4096
+    }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> usize {
+    4096
+}
+
+/// Fault setiap page `mmap` tanpa menulis ke mapping
+///
+/// Menulis ulang byte yang sama (read + write) bisa menimpa write proses lain
+/// di antara keduanya pada mapping shared, jadi tidak dipakai.
+fn prefault(mmap: &MmapMut) {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: range persis mapping milik `mmap` (page-aligned); kernel hanya
+        // mengisi page table sebagai writable, isi page tidak berubah
+        let rc = unsafe {
+            libc::madvise(
+                mmap.as_ptr() as *mut libc::c_void,
+                mmap.len(),
+                libc::MADV_POPULATE_WRITE,
+            )
+        };
+        if rc == 0 {
+            return;
+        }
+        // Kernel lama (EINVAL): read-touch, write pertama tetap kena minor fault
+    }
+
+    let ptr = mmap.as_ptr();
+    for offset in (0..mmap.len()).step_by(page_size()) {
+        // SAFETY: offset < len, hanya membaca
+        unsafe { ptr.add(offset).read_volatile() };
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn advise_huge_pages(mmap: &MmapMut) -> io::Result<()> {
+    mmap.advise(Advice::HugePage)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn advise_huge_pages(_mmap: &MmapMut) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "transparent huge pages are only supported on Linux",
+    ))
+}
+
+/// Ukuran huge page dari filesystem `file`; error jika bukan hugetlbfs
+#[cfg(target_os = "linux")]
+fn hugetlbfs_page_size(file: &File) -> io::Result<usize> {
+    use std::os::unix::io::AsRawFd;
+
+    const HUGETLBFS_MAGIC: u32 = 0x958458f6;
+
+    // SAFETY: statfs adalah plain data, diisi kernel
+    let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::fstatfs(file.as_raw_fd(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if stat.f_type as u32 != HUGETLBFS_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "explicit huge pages need a storage file on hugetlbfs (e.g. /dev/hugepages)",
+        ));
+    }
+    Ok(stat.f_bsize as usize)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn hugetlbfs_page_size(_file: &File) -> io::Result<usize> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "explicit huge pages are only supported on Linux",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_storage_options_map_and_warm() {
+        let path = "test_storage_options.dat";
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .unwrap();
+        file.set_len(64 * 1024).unwrap();
+
+        let options = StorageOptions::new()
+            .populate(true)
+            .prefault(true)
+            .sequential(true)
+            .will_need(true);
+        assert_eq!(options.mapping_len(&file, 64 * 1024).unwrap(), 64 * 1024);
+        let mut mmap = options.map_mut(&file, 64 * 1024).unwrap();
+        mmap[100] = 7;
+        options.warm(&mut mmap).unwrap();
+        // Prefault tidak mengubah isi
+        assert_eq!(mmap[100], 7);
+        assert!(mmap
+            .iter()
+            .enumerate()
+            .all(|(i, &b)| b == (i == 100) as u8 * 7));
+
+        // Bukan hugetlbfs: explicit huge pages ditolak dengan jelas
+        let explicit = StorageOptions::new().huge_pages(HugePages::Explicit);
+        assert!(explicit.mapping_len(&file, 4096).is_err());
+
+        drop(mmap);
+        fs::remove_file(path).ok();
+    }
+}
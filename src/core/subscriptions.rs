@@ -0,0 +1,378 @@
+//! Subscription topic hierarkis dengan wildcard (`*`, `>`)
+//!
+//! - `SubscriptionTrie`: pattern dikompilasi menjadi trie per token saat
+//!   subscribe; matching satu nama topic hanya menelusuri cabang literal,
+//!   `*` dan `>` yang relevan (tanpa alokasi selain hasil).
+//! - `TopicRouter`: cache hasil matching per topic id untuk jalur fan-out.
+//!   Cache dikosongkan saat subscription berubah dan diisi ulang pada frame
+//!   pertama untuk topic tersebut.
+//!
+//! Semantik wildcard sama dengan NATS, lihat `protocol::topic`.
+
+use std::collections::HashMap;
+use std::io;
+use std::str::Split;
+
+use super::topics::TopicRegistry;
+use crate::protocol::{is_valid_topic_pattern, TopicId, NO_TOPIC};
+
+#[derive(Debug)]
+struct Node<S> {
+    literal: HashMap<Box<str>, Node<S>>,
+    any: Option<Box<Node<S>>>,
+    // Pattern yang berakhir di node ini (terurut, unik)
+    exact: Vec<S>,
+    // Pattern `prefix.>`: cocok dengan satu token atau lebih setelah node ini
+    tail: Vec<S>,
+}
+
+impl<S> Default for Node<S> {
+    fn default() -> Self {
+        Self {
+            literal: HashMap::new(),
+            any: None,
+            exact: Vec::new(),
+            tail: Vec::new(),
+        }
+    }
+}
+
+impl<S: Copy + Ord> Node<S> {
+    fn is_empty(&self) -> bool {
+        self.literal.is_empty()
+            && self.any.is_none()
+            && self.exact.is_empty()
+            && self.tail.is_empty()
+    }
+
+    fn collect(&self, mut tokens: Split<'_, char>, out: &mut Vec<S>) {
+        let Some(token) = tokens.next() else {
+            out.extend_from_slice(&self.exact);
+            return;
+        };
+        out.extend_from_slice(&self.tail);
+        if let Some(child) = self.literal.get(token) {
+            child.collect(tokens.clone(), out);
+        }
+        if let Some(child) = &self.any {
+            child.collect(tokens, out);
+        }
+    }
+
+    fn remove(&mut self, tokens: &[&str], subscriber: S) -> bool {
+        let (token, rest) = match tokens.split_first() {
+            None => return remove_sorted(&mut self.exact, subscriber),
+            Some((&">", [])) => return remove_sorted(&mut self.tail, subscriber),
+            Some((token, rest)) => (*token, rest),
+        };
+
+        if token == "*" {
+            let Some(child) = self.any.as_mut() else {
+                return false;
+            };
+            let removed = child.remove(rest, subscriber);
+            if child.is_empty() {
+                self.any = None;
+            }
+            removed
+        } else {
+            let Some(child) = self.literal.get_mut(token) else {
+                return false;
+            };
+            let removed = child.remove(rest, subscriber);
+            if child.is_empty() {
+                self.literal.remove(token);
+            }
+            removed
+        }
+    }
+
+    fn remove_all(&mut self, subscriber: S) -> usize {
+        let mut removed = remove_sorted(&mut self.exact, subscriber) as usize
+            + remove_sorted(&mut self.tail, subscriber) as usize;
+        self.literal.retain(|_, child| {
+            removed += child.remove_all(subscriber);
+            !child.is_empty()
+        });
+        if let Some(child) = self.any.as_mut() {
+            removed += child.remove_all(subscriber);
+            if child.is_empty() {
+                self.any = None;
+            }
+        }
+        removed
+    }
+}
+
+fn insert_sorted<S: Ord>(set: &mut Vec<S>, subscriber: S) -> bool {
+    match set.binary_search(&subscriber) {
+        Ok(_) => false,
+        Err(index) => {
+            set.insert(index, subscriber);
+            true
+        }
+    }
+}
+
+fn remove_sorted<S: Ord>(set: &mut Vec<S>, subscriber: S) -> bool {
+    match set.binary_search(&subscriber) {
+        Ok(index) => {
+            set.remove(index);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Trie pattern topic -> subscriber
+///
+/// ```
+/// use hermes::core::SubscriptionTrie;
+///
+/// let mut trie = SubscriptionTrie::new();
+/// trie.insert("analysis.*.honeypot", 1).unwrap();
+/// trie.insert("analysis.>", 2).unwrap();
+/// assert_eq!(trie.matches("analysis.base.honeypot"), vec![1, 2]);
+/// assert_eq!(trie.matches("analysis.base"), vec![2]);
+/// ```
+#[derive(Debug)]
+pub struct SubscriptionTrie<S> {
+    root: Node<S>,
+    len: usize,
+}
+
+impl<S> Default for SubscriptionTrie<S> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<S: Copy + Ord> SubscriptionTrie<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tambah subscription `pattern` untuk `subscriber`
+    ///
+    /// Returns `false` jika sudah ada; `InvalidInput` jika pattern tidak valid.
+    pub fn insert(&mut self, pattern: &str, subscriber: S) -> io::Result<bool> {
+        if !is_valid_topic_pattern(pattern) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid topic pattern: {:?}", pattern),
+            ));
+        }
+
+        let mut node = &mut self.root;
+        let mut tokens = pattern.split('.').peekable();
+        while let Some(token) = tokens.next() {
+            if token == ">" && tokens.peek().is_none() {
+                let added = insert_sorted(&mut node.tail, subscriber);
+                self.len += added as usize;
+                return Ok(added);
+            }
+            node = if token == "*" {
+                node.any.get_or_insert_with(Default::default)
+            } else {
+                node.literal.entry(token.into()).or_default()
+            };
+        }
+        let added = insert_sorted(&mut node.exact, subscriber);
+        self.len += added as usize;
+        Ok(added)
+    }
+
+    /// Hapus subscription `pattern` milik `subscriber`
+    ///
+    /// Returns `true` jika subscription ada. Node yang kosong ikut dibuang.
+    pub fn remove(&mut self, pattern: &str, subscriber: S) -> bool {
+        let tokens: Vec<&str> = pattern.split('.').collect();
+        let removed = self.root.remove(&tokens, subscriber);
+        self.len -= removed as usize;
+        removed
+    }
+
+    /// Hapus semua subscription milik `subscriber` (mis. saat disconnect)
+    pub fn remove_subscriber(&mut self, subscriber: S) -> usize {
+        let removed = self.root.remove_all(subscriber);
+        self.len -= removed;
+        removed
+    }
+
+    /// Subscriber yang pattern-nya cocok dengan nama topic `topic` (terurut, unik)
+    pub fn matches(&self, topic: &str) -> Vec<S> {
+        let mut out = Vec::new();
+        self.root.collect(topic.split('.'), &mut out);
+        out.sort_unstable();
+        out.dedup();
+        out
+    }
+
+    /// Jumlah pasangan (pattern, subscriber)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` jika tidak ada subscription
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Routing topic id -> subscriber untuk fan-out
+///
+/// Nama topic diambil dari `TopicRegistry`; frame tanpa topic (`NO_TOPIC`)
+/// atau dengan id tak terdaftar tidak cocok dengan subscription apa pun.
+#[derive(Debug)]
+pub struct TopicRouter<S> {
+    trie: SubscriptionTrie<S>,
+    // routes[id]: hasil matching untuk topic id (None = belum dihitung)
+    routes: Vec<Option<Box<[S]>>>,
+}
+
+impl<S> Default for TopicRouter<S> {
+    fn default() -> Self {
+        Self {
+            trie: SubscriptionTrie::default(),
+            routes: Vec::new(),
+        }
+    }
+}
+
+impl<S: Copy + Ord> TopicRouter<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lihat `SubscriptionTrie::insert`
+    pub fn subscribe(&mut self, pattern: &str, subscriber: S) -> io::Result<bool> {
+        let added = self.trie.insert(pattern, subscriber)?;
+        if added {
+            self.routes.clear();
+        }
+        Ok(added)
+    }
+
+    /// Lihat `SubscriptionTrie::remove`
+    pub fn unsubscribe(&mut self, pattern: &str, subscriber: S) -> bool {
+        let removed = self.trie.remove(pattern, subscriber);
+        if removed {
+            self.routes.clear();
+        }
+        removed
+    }
+
+    /// Lihat `SubscriptionTrie::remove_subscriber`
+    pub fn remove_subscriber(&mut self, subscriber: S) -> usize {
+        let removed = self.trie.remove_subscriber(subscriber);
+        if removed > 0 {
+            self.routes.clear();
+        }
+        removed
+    }
+
+    /// Subscriber untuk frame dengan `topic` (terurut, bisa `binary_search`)
+    #[inline]
+    pub fn route(&mut self, topic: TopicId, registry: &TopicRegistry) -> &[S] {
+        let index = topic as usize;
+        if !matches!(self.routes.get(index), Some(Some(_))) {
+            let name = match registry.name(topic) {
+                Some(name) if topic != NO_TOPIC => name,
+                _ => return &[],
+            };
+            if self.routes.len() <= index {
+                self.routes.resize_with(index + 1, || None);
+            }
+            self.routes[index] = Some(self.trie.matches(name).into_boxed_slice());
+        }
+        self.routes[index].as_deref().unwrap_or(&[])
+    }
+
+    /// Subscription yang terdaftar
+    pub fn subscriptions(&self) -> &SubscriptionTrie<S> {
+        &self.trie
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trie_wildcard_semantics() {
+        let mut trie = SubscriptionTrie::new();
+        trie.insert("analysis.base.honeypot", 1).unwrap();
+        trie.insert("analysis.*.honeypot", 2).unwrap();
+        trie.insert("analysis.>", 3).unwrap();
+        trie.insert("*.base.*", 4).unwrap();
+        trie.insert(">", 5).unwrap();
+        trie.insert("analysis", 6).unwrap();
+        assert!(!trie.insert("analysis.>", 3).unwrap());
+        assert!(trie.insert("analysis.>.x", 7).is_err());
+        assert_eq!(trie.len(), 6);
+
+        assert_eq!(trie.matches("analysis.base.honeypot"), vec![1, 2, 3, 4, 5]);
+        assert_eq!(trie.matches("analysis.eth.honeypot"), vec![2, 3, 5]);
+        // `*` tepat satu token, `>` minimal satu token
+        assert_eq!(trie.matches("analysis.base"), vec![3, 5]);
+        assert_eq!(trie.matches("analysis"), vec![5, 6]);
+        assert_eq!(trie.matches("analysis.base.honeypot.v2"), vec![3, 5]);
+        assert_eq!(trie.matches("prices.base.eth"), vec![4, 5]);
+        assert_eq!(trie.matches("prices"), vec![5]);
+
+        // Subscriber yang sama lewat beberapa pattern hanya muncul sekali
+        trie.insert("analysis.base.*", 1).unwrap();
+        assert_eq!(trie.matches("analysis.base.honeypot"), vec![1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_trie_unsubscribe_prunes_nodes() {
+        let mut trie = SubscriptionTrie::new();
+        trie.insert("a.*.c", 1).unwrap();
+        trie.insert("a.*.c", 2).unwrap();
+        trie.insert("a.b.>", 1).unwrap();
+        trie.insert("x.y", 1).unwrap();
+
+        assert!(trie.remove("a.*.c", 1));
+        assert!(!trie.remove("a.*.c", 1));
+        assert!(!trie.remove("a.b.c", 2));
+        assert_eq!(trie.matches("a.b.c"), vec![1, 2]);
+
+        assert_eq!(trie.remove_subscriber(1), 2);
+        assert_eq!(trie.matches("a.b.c"), vec![2]);
+        assert!(trie.matches("x.y").is_empty());
+
+        assert!(trie.remove("a.*.c", 2));
+        assert!(trie.is_empty());
+        assert!(trie.root.is_empty());
+    }
+
+    #[test]
+    fn test_router_caches_and_invalidates_routes() {
+        let mut registry = TopicRegistry::new();
+        let honeypot = registry.register("analysis.base.honeypot").unwrap();
+        let prices = registry.register("prices.eth").unwrap();
+
+        let mut router = TopicRouter::new();
+        router.subscribe("analysis.>", 10usize).unwrap();
+        router.subscribe("*.eth", 20).unwrap();
+        assert_eq!(router.route(honeypot, &registry), &[10]);
+        assert_eq!(router.route(prices, &registry), &[20]);
+        assert!(router.route(NO_TOPIC, &registry).is_empty());
+        assert!(router.route(999, &registry).is_empty());
+
+        // Topic baru setelah subscribe tetap cocok
+        let tx = registry.register("analysis.eth").unwrap();
+        assert_eq!(router.route(tx, &registry), &[10, 20]);
+
+        router.subscribe("analysis.base.honeypot", 20).unwrap();
+        assert_eq!(router.route(honeypot, &registry), &[10, 20]);
+        assert!(router.unsubscribe("analysis.>", 10));
+        assert_eq!(router.route(honeypot, &registry), &[20]);
+        assert_eq!(router.remove_subscriber(20), 2);
+        assert!(router.route(prices, &registry).is_empty());
+    }
+}
@@ -0,0 +1,43 @@
+//! Sync primitives shim untuk model checking
+//!
+//! Build normal memakai `std`. Dengan `RUSTFLAGS="--cfg loom"` semua atomic
+//! dan `UnsafeCell` di modul yang memakai shim ini diganti versi loom,
+//! sehingga loom bisa mengeksplorasi semua interleaving producer/consumer:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --lib loom_
+//! ```
+
+#[cfg(loom)]
+pub(crate) use loom::cell::UnsafeCell;
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::AtomicUsize;
+
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::AtomicUsize;
+
+/// `UnsafeCell` dengan API akses ala loom (`with` / `with_mut`)
+///
+/// Di build normal ini zero-cost wrapper di atas `std::cell::UnsafeCell`.
+#[cfg(not(loom))]
+#[derive(Debug)]
+#[repr(transparent)]
+pub(crate) struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+#[cfg(not(loom))]
+impl<T> UnsafeCell<T> {
+    #[inline(always)]
+    pub(crate) const fn new(value: T) -> Self {
+        Self(std::cell::UnsafeCell::new(value))
+    }
+
+    #[inline(always)]
+    pub(crate) fn with<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        f(self.0.get())
+    }
+
+    #[inline(always)]
+    pub(crate) fn with_mut<R>(&self, f: impl FnOnce(*mut T) -> R) -> R {
+        f(self.0.get())
+    }
+}
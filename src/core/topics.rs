@@ -0,0 +1,264 @@
+//! Registry nama topic -> topic id
+//!
+//! Id dibagikan berurutan mulai 1 (`NO_TOPIC` = 0 untuk frame tanpa topic).
+//! Registry persistent menyimpan satu nama per baris (id = nomor baris) dan
+//! hanya pernah di-append, sehingga id frame yang sudah tersimpan di log
+//! tetap berarti sama setelah restart.
+//!
+//! Setiap pendaftaran nama baru di registry persistent memerlukan `fsync`;
+//! `register_all` mendaftarkan banyak nama dengan satu `fsync`, dan
+//! `set_limit` membatasi jumlah topic (nama yang sudah ada tetap bisa dipakai).
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::protocol::{is_valid_topic_name, TopicId};
+
+/// Peta nama topic ke id ringkas (dan sebaliknya)
+#[derive(Debug, Default)]
+pub struct TopicRegistry {
+    ids: HashMap<Box<str>, TopicId>,
+    // names[id - 1]
+    names: Vec<Box<str>>,
+    file: Option<File>,
+    // Jumlah topic maksimum (None = tanpa batas)
+    limit: Option<usize>,
+}
+
+impl TopicRegistry {
+    /// Registry in-memory (id tidak bertahan setelah proses selesai)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Membuka (atau membuat) registry persistent di `path`
+    ///
+    /// Baris terakhir tanpa newline (append yang terpotong) diabaikan.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+
+        let mut registry = Self::new();
+        let complete = contents.rfind('\n').map_or(0, |end| end + 1);
+        for name in contents[..complete].lines() {
+            if !is_valid_topic_name(name) || registry.ids.contains_key(name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid topic registry entry: {:?}", name),
+                ));
+            }
+            registry.insert(name);
+        }
+
+        // Buang tail yang terpotong supaya append berikutnya mulai di baris baru
+        if complete < contents.len() {
+            file.set_len(complete as u64)?;
+        }
+
+        registry.file = Some(file);
+        Ok(registry)
+    }
+
+    /// Batasi jumlah topic terdaftar (default: tanpa batas)
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = Some(limit);
+    }
+
+    /// Jumlah topic baru yang masih bisa didaftarkan
+    pub fn remaining(&self) -> usize {
+        self.limit
+            .map_or(usize::MAX, |limit| limit.saturating_sub(self.len()))
+    }
+
+    /// Id untuk `name`, didaftarkan jika belum ada
+    ///
+    /// Gagal dengan `InvalidInput` jika nama tidak valid atau registry penuh.
+    /// Untuk registry persistent, nama baru sudah di disk sebelum id
+    /// dikembalikan.
+    pub fn register(&mut self, name: &str) -> io::Result<TopicId> {
+        Ok(self.register_all(&[name])?[0])
+    }
+
+    /// Seperti `register` untuk banyak nama sekaligus, dengan satu `fsync`
+    ///
+    /// Semua atau tidak satu pun nama didaftarkan: nama tidak valid, registry
+    /// penuh, atau write gagal membuat registry tidak berubah.
+    pub fn register_all(&mut self, names: &[&str]) -> io::Result<Vec<TopicId>> {
+        let mut fresh: Vec<&str> = Vec::new();
+        for &name in names {
+            if self.ids.contains_key(name) || fresh.contains(&name) {
+                continue;
+            }
+            if !is_valid_topic_name(name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid topic name: {:?}", name),
+                ));
+            }
+            fresh.push(name);
+        }
+        if fresh.len() > self.remaining() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("topic registry is full ({} topics)", self.len()),
+            ));
+        }
+
+        if !fresh.is_empty() {
+            if let Some(file) = self.file.as_mut() {
+                let mut lines = String::new();
+                for name in &fresh {
+                    lines.push_str(name);
+                    lines.push('\n');
+                }
+                Self::append(file, lines.as_bytes())?;
+            }
+            for name in fresh {
+                self.insert(name);
+            }
+        }
+        Ok(names.iter().map(|&name| self.ids[name]).collect())
+    }
+
+    /// Append + `fsync`; saat gagal file dikembalikan ke panjang semula agar
+    /// nama yang tidak terdaftar di memori tidak mendapat id setelah restart
+    fn append(file: &mut File, bytes: &[u8]) -> io::Result<()> {
+        let len = file.seek(SeekFrom::End(0))?;
+        let result = file.write_all(bytes).and_then(|_| file.sync_data());
+        if result.is_err() {
+            file.set_len(len).ok();
+        }
+        result
+    }
+
+    fn insert(&mut self, name: &str) -> TopicId {
+        self.names.push(name.into());
+        let id = self.names.len() as TopicId;
+        self.ids.insert(name.into(), id);
+        id
+    }
+
+    /// Id untuk `name` jika sudah terdaftar
+    #[inline]
+    pub fn id(&self, name: &str) -> Option<TopicId> {
+        self.ids.get(name).copied()
+    }
+
+    /// Nama untuk `id` jika terdaftar
+    #[inline]
+    pub fn name(&self, id: TopicId) -> Option<&str> {
+        let index = (id as usize).checked_sub(1)?;
+        self.names.get(index).map(|name| &**name)
+    }
+
+    /// Jumlah topic terdaftar
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns `true` jika belum ada topic terdaftar
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Iterasi `(id, nama)` urut id
+    pub fn iter(&self) -> impl Iterator<Item = (TopicId, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (i as TopicId + 1, &**name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_registry_assigns_compact_ids() {
+        let mut registry = TopicRegistry::new();
+        assert_eq!(registry.register("prices").unwrap(), 1);
+        assert_eq!(registry.register("trades").unwrap(), 2);
+        assert_eq!(registry.register("prices").unwrap(), 1);
+        assert_eq!(registry.id("trades"), Some(2));
+        assert_eq!(registry.name(2), Some("trades"));
+        assert_eq!(registry.name(0), None);
+        assert_eq!(registry.name(3), None);
+        assert!(registry.register("bad name").is_err());
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn test_registry_batch_and_limit() {
+        let path = "test_topics_limit.registry";
+        fs::remove_file(path).ok();
+
+        {
+            let mut registry = TopicRegistry::open(path).unwrap();
+            registry.set_limit(3);
+            assert_eq!(registry.remaining(), 3);
+            assert_eq!(
+                registry.register_all(&["a", "b", "a"]).unwrap(),
+                vec![1, 2, 1]
+            );
+            assert_eq!(registry.remaining(), 1);
+
+            // Semua atau tidak sama sekali
+            assert!(registry.register_all(&["c", "bad name"]).is_err());
+            assert!(registry.register_all(&["c", "d"]).is_err());
+            assert_eq!(registry.len(), 2);
+
+            // Nama lama tetap bisa dipakai saat registry penuh
+            assert_eq!(registry.register("c").unwrap(), 3);
+            assert!(registry.register("d").is_err());
+            assert_eq!(registry.register_all(&["b", "c"]).unwrap(), vec![2, 3]);
+        }
+        assert_eq!(fs::read_to_string(path).unwrap(), "a\nb\nc\n");
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_registry_persists_ids() {
+        let path = "test_topics.registry";
+        fs::remove_file(path).ok();
+
+        {
+            let mut registry = TopicRegistry::open(path).unwrap();
+            registry.register("prices").unwrap();
+            registry.register("trades").unwrap();
+        }
+
+        // Append terpotong: baris tanpa newline dibuang
+        let mut file = OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(b"half").unwrap();
+        drop(file);
+
+        {
+            let mut registry = TopicRegistry::open(path).unwrap();
+            assert_eq!(
+                registry.iter().collect::<Vec<_>>(),
+                vec![(1, "prices"), (2, "trades")]
+            );
+            assert_eq!(registry.register("analysis").unwrap(), 3);
+        }
+        assert_eq!(
+            fs::read_to_string(path).unwrap(),
+            "prices\ntrades\nanalysis\n"
+        );
+
+        fs::write(path, "ok\nnot valid\n").unwrap();
+        assert!(TopicRegistry::open(path).is_err());
+
+        fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,287 @@
+//! Wait Strategies untuk consumer/producer ring buffer
+//!
+//! Trade-off latency vs CPU bisa dipilih per deployment:
+//! - `BusySpin`: latency minimum, 100% CPU
+//! - `SpinThenYield`: spin singkat lalu `yield_now()`
+//! - `SpinThenPark`: spin singkat lalu tidur di condvar sampai `signal()`
+//! - `Timeout`: membungkus strategy lain dengan batas waktu
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Strategi menunggu saat ring buffer kosong (consumer) atau penuh (producer)
+pub trait WaitStrategy {
+    /// Satu langkah backoff setelah `attempt` percobaan gagal berturut-turut
+    fn idle(&self, attempt: u32);
+
+    /// Ulangi `poll` sampai mengembalikan `Some`
+    ///
+    /// Returns `None` jika strategy menyerah (misalnya `Timeout`).
+    fn wait_until<R, F>(&self, mut poll: F) -> Option<R>
+    where
+        Self: Sized,
+        F: FnMut() -> Option<R>,
+    {
+        let mut attempt = 0u32;
+        loop {
+            if let Some(value) = poll() {
+                return Some(value);
+            }
+            self.idle(attempt);
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Dipanggil sisi lawan setelah membuat progress (push/pop)
+    ///
+    /// Hanya strategy yang tidur (`SpinThenPark`) yang memerlukannya.
+    #[inline(always)]
+    fn signal(&self) {}
+}
+
+/// Busy-spin murni - latency terendah, satu core penuh
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BusySpin;
+
+impl WaitStrategy for BusySpin {
+    #[inline(always)]
+    fn idle(&self, _attempt: u32) {
+        std::hint::spin_loop();
+    }
+}
+
+/// Spin sebanyak `spins` kali, lalu `thread::yield_now()`
+#[derive(Debug, Clone, Copy)]
+pub struct SpinThenYield {
+    spins: u32,
+}
+
+impl SpinThenYield {
+    pub const fn new(spins: u32) -> Self {
+        Self { spins }
+    }
+}
+
+impl Default for SpinThenYield {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl WaitStrategy for SpinThenYield {
+    #[inline(always)]
+    fn idle(&self, attempt: u32) {
+        if attempt < self.spins {
+            std::hint::spin_loop();
+        } else {
+            std::thread::yield_now();
+        }
+    }
+}
+
+/// Spin sebanyak `spins` kali, lalu park di condvar
+///
+/// Thread dibangunkan oleh `signal()` dari sisi lawan, atau setelah
+/// `park_timeout` sebagai jaring pengaman jika sisi lawan tidak memanggil `signal()`.
+pub struct SpinThenPark {
+    spins: u32,
+    park_timeout: Duration,
+    // Naik setiap signal(); waiter hanya tidur jika belum ada signal baru
+    generation: AtomicU64,
+    sleepers: AtomicUsize,
+    lock: Mutex<()>,
+    cond: Condvar,
+}
+
+impl SpinThenPark {
+    pub fn new(spins: u32, park_timeout: Duration) -> Self {
+        Self {
+            spins,
+            park_timeout,
+            generation: AtomicU64::new(0),
+            sleepers: AtomicUsize::new(0),
+            lock: Mutex::new(()),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// Tidur sampai generation berubah dari `seen` atau timeout
+    #[cold]
+    fn park(&self, seen: u64) {
+        let guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.sleepers.fetch_add(1, Ordering::SeqCst);
+
+        // Cek ulang di bawah lock: signal() setelah titik ini pasti
+        // menunggu lock kita dilepas oleh wait_timeout, jadi tidak hilang.
+        if self.generation.load(Ordering::SeqCst) == seen {
+            let _ = self
+                .cond
+                .wait_timeout(guard, self.park_timeout)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Default for SpinThenPark {
+    fn default() -> Self {
+        Self::new(100, Duration::from_millis(1))
+    }
+}
+
+impl WaitStrategy for SpinThenPark {
+    fn idle(&self, attempt: u32) {
+        if attempt < self.spins {
+            std::hint::spin_loop();
+        } else {
+            self.park(self.generation.load(Ordering::SeqCst));
+        }
+    }
+
+    fn wait_until<R, F>(&self, mut poll: F) -> Option<R>
+    where
+        F: FnMut() -> Option<R>,
+    {
+        let mut attempt = 0u32;
+        loop {
+            // Generation dibaca SEBELUM poll agar signal di antaranya tidak terlewat
+            let seen = self.generation.load(Ordering::SeqCst);
+            if let Some(value) = poll() {
+                return Some(value);
+            }
+
+            if attempt < self.spins {
+                std::hint::spin_loop();
+            } else {
+                self.park(seen);
+            }
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    #[inline(always)]
+    fn signal(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        // Fast path: tidak ada yang tidur, tidak perlu menyentuh lock
+        if self.sleepers.load(Ordering::SeqCst) > 0 {
+            let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+            self.cond.notify_all();
+        }
+    }
+}
+
+/// Membungkus strategy lain dengan batas waktu
+#[derive(Debug, Clone, Copy)]
+pub struct Timeout<W> {
+    inner: W,
+    timeout: Duration,
+}
+
+impl<W: WaitStrategy> Timeout<W> {
+    pub const fn new(inner: W, timeout: Duration) -> Self {
+        Self { inner, timeout }
+    }
+}
+
+impl<W: WaitStrategy> WaitStrategy for Timeout<W> {
+    #[inline(always)]
+    fn idle(&self, attempt: u32) {
+        self.inner.idle(attempt);
+    }
+
+    fn wait_until<R, F>(&self, mut poll: F) -> Option<R>
+    where
+        F: FnMut() -> Option<R>,
+    {
+        let deadline = Instant::now() + self.timeout;
+        self.inner
+            .wait_until(|| match poll() {
+                Some(value) => Some(Some(value)),
+                None if Instant::now() >= deadline => Some(None),
+                None => None,
+            })
+            .flatten()
+    }
+
+    #[inline(always)]
+    fn signal(&self) {
+        self.inner.signal();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::RingBuffer;
+    use std::thread;
+
+    fn transfer<W: WaitStrategy + Sync>(wait: &W) {
+        const MESSAGES: u64 = 10_000;
+        let mut rb: RingBuffer<u64, 64> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
+
+        thread::scope(|s| {
+            s.spawn(move || {
+                for i in 0..MESSAGES {
+                    assert!(tx.push_blocking(i, wait));
+                }
+            });
+
+            for i in 0..MESSAGES {
+                assert_eq!(rx.pop_blocking(wait), Some(i));
+            }
+        });
+    }
+
+    #[test]
+    fn test_busy_spin_transfer() {
+        transfer(&BusySpin);
+    }
+
+    #[test]
+    fn test_spin_then_yield_transfer() {
+        transfer(&SpinThenYield::default());
+    }
+
+    #[test]
+    fn test_spin_then_park_transfer() {
+        // Park timeout panjang: transfer hanya selesai cepat jika signal bekerja
+        transfer(&SpinThenPark::new(10, Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_park_wakes_on_signal() {
+        let wait = SpinThenPark::new(0, Duration::from_secs(10));
+        let mut rb: RingBuffer<u64, 4> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
+
+        thread::scope(|s| {
+            s.spawn(|| {
+                thread::sleep(Duration::from_millis(50));
+                assert!(tx.push_blocking(7, &wait));
+            });
+
+            let start = Instant::now();
+            assert_eq!(rx.pop_blocking(&wait), Some(7));
+            assert!(start.elapsed() < Duration::from_secs(5));
+        });
+    }
+
+    #[test]
+    fn test_timeout_gives_up() {
+        let mut rb: RingBuffer<u64, 2> = RingBuffer::new();
+        let (mut tx, mut rx) = rb.split();
+        let wait = Timeout::new(SpinThenYield::default(), Duration::from_millis(20));
+
+        let start = Instant::now();
+        assert_eq!(rx.pop_blocking(&wait), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        assert!(tx.push_blocking(1, &wait));
+        assert!(tx.push_blocking(2, &wait));
+        assert!(!tx.push_blocking(3, &wait)); // Full - timeout
+        assert_eq!(rx.pop_blocking(&wait), Some(1));
+    }
+}
@@ -16,7 +16,7 @@ use mio::{Events, Interest, Poll, Token};
 
 use super::Connection;
 use crate::core::RingBuffer;
-use crate::protocol::{Decoder, Encoder, MessageType, HEADER_SIZE};
+use crate::protocol::{DecodeError, Decoder, Encoder, ErrorCode, MessageType, HEADER_SIZE};
 
 const SERVER_TOKEN: Token = Token(0);
 const MAX_CONNECTIONS: usize = 1024;
@@ -171,8 +171,19 @@ impl Server {
         let mut decoder = Decoder::new(&readable_data);
         let mut consumed = 0;
         let mut responses: Vec<(u64, Vec<u8>)> = Vec::new();
+        // Data rusak yang dilewati, dilaporkan dengan frame Error
+        let mut decode_errors: Vec<DecodeError> = Vec::new();
 
-        while let Some((header, payload)) = decoder.next() {
+        loop {
+            let (header, payload) = match decoder.next() {
+                Ok(frame) => frame,
+                Err(DecodeError::Incomplete) => break,
+                Err(e) => {
+                    consumed += decoder.resync();
+                    decode_errors.push(e);
+                    continue;
+                }
+            };
             consumed += HEADER_SIZE + payload.len();
 
             match MessageType::from_u8(header.msg_type) {
@@ -184,7 +195,7 @@ impl Server {
                         payload_offset: 0,
                         payload_len: payload.len(),
                     };
-                    let _ = self.message_queue.push(msg);
+                    let _ = self.message_queue.split().0.push(msg);
                 }
                 Some(MessageType::Heartbeat) => {
                     // Queue heartbeat response
@@ -204,6 +215,18 @@ impl Server {
                     let _ = conn.queue_write(response);
                 }
             }
+
+            for error in decode_errors {
+                self.encoder.reset();
+                let code = error.code().unwrap_or(ErrorCode::Malformed);
+                let reason = error.to_string();
+                if let Some(response) =
+                    self.encoder
+                        .encode_error(MessageType::Error, 0, code, &reason)
+                {
+                    let _ = conn.queue_write(response);
+                }
+            }
         }
 
         Ok(())
@@ -219,7 +242,8 @@ impl Server {
 
     /// Process message queue
     fn process_queue(&mut self) -> io::Result<()> {
-        while let Some(_msg) = self.message_queue.pop() {
+        let (_, mut queue) = self.message_queue.split();
+        while let Some(_msg) = queue.pop() {
             // In real implementation: broadcast to subscribers
             // For PoC, just drain the queue
         }
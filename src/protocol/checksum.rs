@@ -0,0 +1,177 @@
+//! Checksum payload
+//!
+//! - [`crc32c`]: CRC-32C (Castagnoli, polynomial `0x1EDC6F41`), sama dengan
+//!   iSCSI / ext4 / library `crc32c` di bahasa lain. Memakai instruksi SSE4.2
+//!   (x86_64) atau ARMv8 CRC (aarch64) jika CPU mendukung, selain itu
+//!   table-driven (slicing-by-8).
+//! - `legacy_checksum`: checksum Adler-like dari versi awal, dipakai frame
+//!   tanpa `FLAG_CRC32C` serta record segment log dan snapshot compaction.
+
+/// Polynomial CRC-32C dalam bentuk reflected
+const POLY: u32 = 0x82F6_3B78;
+
+static TABLE: [[u32; 256]; 8] = make_table();
+
+/// CRC-32C dari `data` (init `!0`, final XOR `!0`)
+#[inline]
+pub fn crc32c(data: &[u8]) -> u32 {
+    !crc32c_update(!0, data)
+}
+
+#[inline]
+fn crc32c_update(crc: u32, data: &[u8]) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if std::is_x86_feature_detected!("sse4.2") {
+            // SAFETY: Fitur CPU sudah dicek
+            return unsafe { crc32c_sse42(crc, data) };
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("crc") {
+            // SAFETY: Fitur CPU sudah dicek
+            return unsafe { crc32c_armv8(crc, data) };
+        }
+    }
+    crc32c_table(crc, data)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.2")]
+unsafe fn crc32c_sse42(crc: u32, data: &[u8]) -> u32 {
+    use std::arch::x86_64::{_mm_crc32_u64, _mm_crc32_u8};
+
+    let mut chunks = data.chunks_exact(8);
+    let mut crc = crc as u64;
+    for chunk in &mut chunks {
+        crc = _mm_crc32_u64(crc, u64::from_le_bytes(chunk.try_into().unwrap()));
+    }
+    let mut crc = crc as u32;
+    for &byte in chunks.remainder() {
+        crc = _mm_crc32_u8(crc, byte);
+    }
+    crc
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "crc")]
+unsafe fn crc32c_armv8(mut crc: u32, data: &[u8]) -> u32 {
+    use std::arch::asm;
+
+    // Intrinsic `__crc32c*` baru stabil di Rust 1.80 (MSRV 1.70)
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let value = u64::from_le_bytes(chunk.try_into().unwrap());
+        asm!(
+            "crc32cx {crc:w}, {crc:w}, {value:x}",
+            crc = inout(reg) crc,
+            value = in(reg) value,
+            options(pure, nomem, nostack)
+        );
+    }
+    for &byte in chunks.remainder() {
+        asm!(
+            "crc32cb {crc:w}, {crc:w}, {byte:w}",
+            crc = inout(reg) crc,
+            byte = in(reg) byte as u32,
+            options(pure, nomem, nostack)
+        );
+    }
+    crc
+}
+
+/// Fallback slicing-by-8: 8 byte per iterasi
+fn crc32c_table(mut crc: u32, data: &[u8]) -> u32 {
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        let lo = crc ^ u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        let hi = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+        crc = TABLE[7][(lo & 0xFF) as usize]
+            ^ TABLE[6][((lo >> 8) & 0xFF) as usize]
+            ^ TABLE[5][((lo >> 16) & 0xFF) as usize]
+            ^ TABLE[4][(lo >> 24) as usize]
+            ^ TABLE[3][(hi & 0xFF) as usize]
+            ^ TABLE[2][((hi >> 8) & 0xFF) as usize]
+            ^ TABLE[1][((hi >> 16) & 0xFF) as usize]
+            ^ TABLE[0][(hi >> 24) as usize];
+    }
+    for &byte in chunks.remainder() {
+        crc = (crc >> 8) ^ TABLE[0][((crc ^ byte as u32) & 0xFF) as usize];
+    }
+    crc
+}
+
+const fn make_table() -> [[u32; 256]; 8] {
+    let mut table = [[0u32; 256]; 8];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[0][i] = crc;
+        i += 1;
+    }
+
+    // table[k][i] = CRC byte i diikuti k byte nol
+    let mut i = 0;
+    while i < 256 {
+        let mut k = 1;
+        while k < 8 {
+            let prev = table[k - 1][i];
+            table[k][i] = (prev >> 8) ^ table[0][(prev & 0xFF) as usize];
+            k += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+/// Checksum lama (Adler-like tanpa modulo), untuk kompatibilitas format
+#[inline(always)]
+pub(crate) fn legacy_checksum(data: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+
+    for &byte in data {
+        a = a.wrapping_add(byte as u32);
+        b = b.wrapping_add(a);
+    }
+
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32c_known_vectors() {
+        // RFC 3720 (iSCSI) Appendix B.4
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c(&[0u8; 32]), 0x8A91_36AA);
+        assert_eq!(crc32c(&[0xFFu8; 32]), 0x62A8_AB43);
+        let ascending: Vec<u8> = (0..32).collect();
+        assert_eq!(crc32c(&ascending), 0x46DD_794E);
+        assert_eq!(crc32c(b""), 0);
+    }
+
+    #[test]
+    fn test_crc32c_hardware_matches_table() {
+        let data: Vec<u8> = (0..1024u32).map(|i| (i * 31 + 7) as u8).collect();
+        for len in [0, 1, 7, 8, 9, 63, 64, 65, 1000, 1024] {
+            let expected = !crc32c_table(!0, &data[..len]);
+            assert_eq!(crc32c(&data[..len]), expected, "len {}", len);
+        }
+        // Incremental == sekali jalan
+        let split = crc32c_update(crc32c_update(!0, &data[..100]), &data[100..]);
+        assert_eq!(!split, crc32c(&data));
+    }
+}
@@ -5,7 +5,16 @@
 
 #![allow(dead_code)] // Batch encoding is part of the public API
 
-use super::message::{crc32_fast, MessageHeader, MessageType, HEADER_SIZE, MAX_PAYLOAD_SIZE};
+use std::fmt;
+
+use super::error::{ErrorCode, ERROR_CODE_SIZE};
+use super::message::{
+    MessageHeader, MessageType, FLAG_TOPIC, HEADER_SIZE, MAGIC, MAX_PAYLOAD_SIZE, VERSION,
+};
+use super::topic::{TopicId, TOPIC_ID_SIZE};
+
+/// `MAGIC` seperti di wire (little-endian)
+const MAGIC_BYTES: [u8; 4] = MAGIC.to_le_bytes();
 
 /// Pre-allocated encoder buffer
 ///
@@ -53,9 +62,8 @@ impl Encoder {
         let start = self.write_pos;
 
         // Buat header dengan checksum
-        let checksum = crc32_fast(payload);
         let mut header = MessageHeader::new(msg_type, sequence, payload.len() as u32);
-        header.checksum = checksum;
+        header.set_checksum(payload);
 
         // Copy header (zero-copy cast)
         self.buffer[start..start + HEADER_SIZE].copy_from_slice(header.as_bytes());
@@ -68,6 +76,79 @@ impl Encoder {
         Some(&self.buffer[start..self.write_pos])
     }
 
+    /// Encode message dengan topic id (`FLAG_TOPIC` + prefix di payload)
+    ///
+    /// Returns slice ke encoded data, atau None jika buffer penuh.
+    #[inline(always)]
+    pub fn encode_topic(
+        &mut self,
+        msg_type: MessageType,
+        topic: TopicId,
+        sequence: u64,
+        payload: &[u8],
+    ) -> Option<&[u8]> {
+        let payload_len = TOPIC_ID_SIZE + payload.len();
+        if payload_len > MAX_PAYLOAD_SIZE {
+            return None;
+        }
+
+        let total_size = HEADER_SIZE + payload_len;
+        if self.write_pos + total_size > self.buffer.len() {
+            return None;
+        }
+
+        let start = self.write_pos;
+        let body = start + HEADER_SIZE;
+
+        // Payload dulu: checksum dihitung atas prefix + data di buffer
+        self.buffer[body..body + TOPIC_ID_SIZE].copy_from_slice(&topic.to_le_bytes());
+        self.buffer[body + TOPIC_ID_SIZE..start + total_size].copy_from_slice(payload);
+
+        let mut header = MessageHeader::new(msg_type, sequence, payload_len as u32);
+        header.flags |= FLAG_TOPIC;
+        header.set_checksum(&self.buffer[body..start + total_size]);
+        self.buffer[start..body].copy_from_slice(header.as_bytes());
+
+        self.write_pos += total_size;
+
+        Some(&self.buffer[start..self.write_pos])
+    }
+
+    /// Encode frame `Nack` / `Error` berisi `code` dan `reason`
+    ///
+    /// Returns slice ke encoded data, atau None jika buffer penuh.
+    pub fn encode_error(
+        &mut self,
+        msg_type: MessageType,
+        sequence: u64,
+        code: ErrorCode,
+        reason: &str,
+    ) -> Option<&[u8]> {
+        let payload_len = ERROR_CODE_SIZE + reason.len();
+        if payload_len > MAX_PAYLOAD_SIZE {
+            return None;
+        }
+
+        let total_size = HEADER_SIZE + payload_len;
+        if self.write_pos + total_size > self.buffer.len() {
+            return None;
+        }
+
+        let start = self.write_pos;
+        let body = start + HEADER_SIZE;
+
+        self.buffer[body..body + ERROR_CODE_SIZE].copy_from_slice(&(code as u16).to_le_bytes());
+        self.buffer[body + ERROR_CODE_SIZE..start + total_size].copy_from_slice(reason.as_bytes());
+
+        let mut header = MessageHeader::new(msg_type, sequence, payload_len as u32);
+        header.set_checksum(&self.buffer[body..start + total_size]);
+        self.buffer[start..body].copy_from_slice(header.as_bytes());
+
+        self.write_pos += total_size;
+
+        Some(&self.buffer[start..self.write_pos])
+    }
+
     /// Encode batch of messages
     ///
     /// Format batch:
@@ -107,10 +188,9 @@ impl Encoder {
 
         // Write individual messages
         for (payload, sequence) in messages {
-            let checksum = crc32_fast(payload);
             let mut header =
                 MessageHeader::new(MessageType::Publish, *sequence, payload.len() as u32);
-            header.checksum = checksum;
+            header.set_checksum(payload);
 
             self.buffer[self.write_pos..self.write_pos + HEADER_SIZE]
                 .copy_from_slice(header.as_bytes());
@@ -136,6 +216,47 @@ impl Encoder {
     }
 }
 
+/// Alasan `Decoder::next` tidak menghasilkan frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Frame belum lengkap; tunggu bytes berikutnya
+    Incomplete,
+    /// Bukan awal frame Hermes
+    BadMagic,
+    /// Versi protokol tidak didukung
+    BadVersion,
+    /// `payload_len` melebihi `MAX_PAYLOAD_SIZE`
+    Oversize,
+    /// Checksum tidak cocok dengan payload
+    ChecksumMismatch,
+}
+
+impl DecodeError {
+    /// Kode untuk frame `Error`; `None` untuk `Incomplete` (bukan error)
+    pub fn code(self) -> Option<ErrorCode> {
+        match self {
+            Self::Incomplete => None,
+            Self::BadMagic | Self::BadVersion => Some(ErrorCode::Malformed),
+            Self::Oversize => Some(ErrorCode::Oversize),
+            Self::ChecksumMismatch => Some(ErrorCode::ChecksumMismatch),
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Incomplete => "incomplete frame",
+            Self::BadMagic => "bad magic",
+            Self::BadVersion => "unsupported protocol version",
+            Self::Oversize => "payload exceeds MAX_PAYLOAD_SIZE",
+            Self::ChecksumMismatch => "checksum does not match payload",
+        })
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
 /// Zero-copy decoder
 pub struct Decoder<'a> {
     buffer: &'a [u8],
@@ -153,42 +274,90 @@ impl<'a> Decoder<'a> {
     }
 
     /// Decode next message (zero-copy)
+    ///
+    /// Saat error posisi tidak maju: `Incomplete` berarti tunggu data,
+    /// error lain berarti data rusak dan perlu `resync`.
     #[inline(always)]
     #[allow(clippy::should_implement_trait)]
-    pub fn next(&mut self) -> Option<(MessageHeader, &'a [u8])> {
+    pub fn next(&mut self) -> Result<(MessageHeader, &'a [u8]), DecodeError> {
         if self.read_pos + HEADER_SIZE > self.buffer.len() {
-            return None;
+            return Err(DecodeError::Incomplete);
         }
 
         // Zero-copy header read
         let header = unsafe { *(self.buffer.as_ptr().add(self.read_pos) as *const MessageHeader) };
 
-        if !header.is_valid() {
-            return None;
+        if header.magic != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        if header.version != VERSION {
+            return Err(DecodeError::BadVersion);
+        }
+        if header.payload_len as usize > MAX_PAYLOAD_SIZE {
+            return Err(DecodeError::Oversize);
         }
 
         let payload_start = self.read_pos + HEADER_SIZE;
         let payload_end = payload_start + header.payload_len as usize;
 
         if payload_end > self.buffer.len() {
-            return None;
+            return Err(DecodeError::Incomplete);
         }
 
         // Verify checksum
         let payload = &self.buffer[payload_start..payload_end];
-        if header.checksum != 0 && crc32_fast(payload) != header.checksum {
-            return None; // Checksum mismatch
+        if !header.checksum_matches(payload) {
+            return Err(DecodeError::ChecksumMismatch);
         }
 
         self.read_pos = payload_end;
 
-        Some((header, payload))
+        Ok((header, payload))
+    }
+
+    /// Lewati data rusak sampai magic berikutnya dengan header yang masuk akal
+    ///
+    /// Minimal satu byte dilewati. Kandidat di akhir buffer yang belum lengkap
+    /// (termasuk potongan magic) dipertahankan supaya bisa divalidasi setelah
+    /// data berikutnya datang. Returns jumlah byte yang dilewati.
+    pub fn resync(&mut self) -> usize {
+        let start = self.read_pos.min(self.buffer.len());
+        let mut pos = (start + 1).min(self.buffer.len());
+        loop {
+            match self.buffer[pos..].iter().position(|&b| b == MAGIC_BYTES[0]) {
+                Some(offset) if self.is_frame_start(pos + offset) => {
+                    pos += offset;
+                    break;
+                }
+                Some(offset) => pos += offset + 1,
+                None => {
+                    pos = self.buffer.len();
+                    break;
+                }
+            }
+        }
+        self.read_pos = pos;
+        pos - start
+    }
+
+    /// Returns `true` jika `pos` bisa menjadi awal frame (sejauh data yang ada)
+    fn is_frame_start(&self, pos: usize) -> bool {
+        let rest = &self.buffer[pos..];
+        let n = rest.len().min(MAGIC_BYTES.len());
+        if rest[..n] != MAGIC_BYTES[..n] {
+            return false;
+        }
+        if rest.len() < HEADER_SIZE {
+            return true;
+        }
+        let header = unsafe { *(rest.as_ptr() as *const MessageHeader) };
+        header.version == VERSION && header.payload_len as usize <= MAX_PAYLOAD_SIZE
     }
 
     /// Decode batch messages
     #[inline(always)]
     pub fn decode_batch(&mut self) -> Option<BatchIterator<'a>> {
-        let (header, batch_payload) = self.next()?;
+        let (header, batch_payload) = self.next().ok()?;
 
         if header.msg_type != MessageType::Batch as u8 {
             return None;
@@ -215,7 +384,7 @@ impl<'a> Iterator for BatchIterator<'a> {
     type Item = (MessageHeader, &'a [u8]);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.decoder.next()
+        self.decoder.next().ok()
     }
 }
 
@@ -273,4 +442,123 @@ mod tests {
         let seq = header.sequence;
         assert_eq!(seq, 2); // Should be second message after reset
     }
+
+    #[test]
+    fn test_checksum_flag_and_legacy_frames() {
+        use crate::protocol::{crc32c, legacy_checksum, FLAG_CRC32C};
+
+        let mut encoder = Encoder::new(4096);
+        encoder.encode(MessageType::Publish, 1, b"crc32c").unwrap();
+        let (header, _) = Decoder::new(encoder.as_bytes()).next().unwrap();
+        let (flags, checksum) = (header.flags, header.checksum);
+        assert_eq!(flags & FLAG_CRC32C, FLAG_CRC32C);
+        assert_eq!(checksum, crc32c(b"crc32c"));
+
+        // Burst error 2 byte: terdeteksi
+        let mut corrupted = encoder.as_bytes().to_vec();
+        corrupted[HEADER_SIZE] ^= 0x01;
+        corrupted[HEADER_SIZE + 1] ^= 0x80;
+        assert_eq!(
+            Decoder::new(&corrupted).next().err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+
+        // Frame lama (tanpa flag, checksum legacy) tetap bisa di-decode
+        let payload = b"legacy frame";
+        let mut header = MessageHeader::new(MessageType::Publish, 7, payload.len() as u32);
+        header.checksum = legacy_checksum(payload);
+        let mut frame = header.as_bytes().to_vec();
+        frame.extend_from_slice(payload);
+        let (decoded, decoded_payload) = Decoder::new(&frame).next().unwrap();
+        let seq = decoded.sequence;
+        assert_eq!((seq, decoded_payload), (7, &payload[..]));
+
+        frame[HEADER_SIZE] ^= 0xFF;
+        assert_eq!(
+            Decoder::new(&frame).next().err(),
+            Some(DecodeError::ChecksumMismatch)
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut encoder = Encoder::new(4096);
+        encoder.encode(MessageType::Publish, 1, b"payload").unwrap();
+        let frame = encoder.as_bytes().to_vec();
+        let decode = |data: &[u8]| Decoder::new(data).next().err();
+
+        assert_eq!(decode(&frame), None);
+        assert_eq!(decode(&frame[..5]), Some(DecodeError::Incomplete));
+        assert_eq!(
+            decode(&frame[..HEADER_SIZE + 3]),
+            Some(DecodeError::Incomplete)
+        );
+
+        let mut bad = frame.clone();
+        bad[0] ^= 0xFF;
+        assert_eq!(decode(&bad), Some(DecodeError::BadMagic));
+        let mut bad = frame.clone();
+        bad[4] = VERSION + 1;
+        assert_eq!(decode(&bad), Some(DecodeError::BadVersion));
+        let mut bad = frame.clone();
+        bad[24..28].copy_from_slice(&(MAX_PAYLOAD_SIZE as u32 + 1).to_le_bytes());
+        assert_eq!(decode(&bad[..HEADER_SIZE]), Some(DecodeError::Oversize));
+        let mut bad = frame.clone();
+        bad[HEADER_SIZE] ^= 0x01;
+        assert_eq!(decode(&bad), Some(DecodeError::ChecksumMismatch));
+        assert_eq!(
+            DecodeError::ChecksumMismatch.code(),
+            Some(ErrorCode::ChecksumMismatch)
+        );
+        assert_eq!(DecodeError::Incomplete.code(), None);
+
+        // Error tidak memajukan posisi
+        let mut stream = frame.clone();
+        stream.extend_from_slice(&bad);
+        let mut decoder = Decoder::new(&stream);
+        assert!(decoder.next().is_ok());
+        assert_eq!(decoder.next().err(), Some(DecodeError::ChecksumMismatch));
+        assert_eq!(decoder.remaining(), bad.len());
+    }
+
+    #[test]
+    fn test_decoder_resync() {
+        let mut encoder = Encoder::new(4096);
+        encoder.encode(MessageType::Publish, 1, b"first").unwrap();
+        encoder.encode(MessageType::Publish, 2, b"second").unwrap();
+        let frames = encoder.as_bytes().to_vec();
+        let first_len = HEADER_SIZE + 5;
+
+        // Sampah (termasuk magic palsu dengan versi salah) di depan frame kedua
+        let mut stream = frames[..first_len].to_vec();
+        stream.extend_from_slice(b"garbage");
+        stream.extend_from_slice(&MAGIC_BYTES);
+        stream.extend_from_slice(&[0xEE; HEADER_SIZE]);
+        let garbage = stream.len() - first_len;
+        stream.extend_from_slice(&frames[first_len..]);
+
+        let mut decoder = Decoder::new(&stream);
+        assert!(decoder.next().is_ok());
+        assert_eq!(decoder.next().err(), Some(DecodeError::BadMagic));
+        assert_eq!(decoder.resync(), garbage);
+        let (header, payload) = decoder.next().unwrap();
+        assert_eq!(({ header.sequence }, payload), (2, &b"second"[..]));
+
+        // Checksum rusak: resync melompat ke frame berikutnya
+        let mut stream = frames.clone();
+        stream[HEADER_SIZE] ^= 0x01;
+        let mut decoder = Decoder::new(&stream);
+        assert_eq!(decoder.next().err(), Some(DecodeError::ChecksumMismatch));
+        assert_eq!(decoder.resync(), first_len);
+        assert!(decoder.next().is_ok());
+
+        // Tanpa kandidat: potongan magic di akhir dipertahankan
+        let mut stream = vec![0xAA; 40];
+        stream.extend_from_slice(&MAGIC_BYTES[..2]);
+        let mut decoder = Decoder::new(&stream);
+        assert_eq!(decoder.next().err(), Some(DecodeError::BadMagic));
+        assert_eq!(decoder.resync(), 40);
+        assert_eq!(decoder.next().err(), Some(DecodeError::Incomplete));
+        assert_eq!(Decoder::new(&[0xAA; 40]).resync(), 40);
+    }
 }
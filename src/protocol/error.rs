@@ -0,0 +1,108 @@
+//! Payload frame `Nack` / `Error`
+//!
+//! - `Nack`: frame dengan `sequence` yang sama ditolak (tidak disimpan dan
+//!   tidak di-forward); koneksi tetap jalan.
+//! - `Error`: stream dari client tidak bisa di-decode; server melewati data
+//!   sampai header valid berikutnya (`Decoder::resync`), koneksi tetap jalan.
+//!
+//! Payload: `code: u16` (LE, lihat [`ErrorCode`]) diikuti alasan UTF-8 yang
+//! ditujukan untuk manusia (tidak untuk di-parse).
+
+/// Ukuran field `code` di awal payload
+pub const ERROR_CODE_SIZE: usize = 2;
+
+/// Kode error pada frame `Nack` / `Error`
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Header atau payload tidak bisa di-decode
+    Malformed = 1,
+    /// `payload_len` melebihi `MAX_PAYLOAD_SIZE`
+    Oversize = 2,
+    /// Checksum tidak cocok dengan payload
+    ChecksumMismatch = 3,
+    /// Tipe pesan tidak dikenal atau tidak diterima server
+    UnsupportedType = 4,
+    /// Nama atau pattern topic tidak valid
+    InvalidTopic = 5,
+    /// Topic id belum terdaftar (`Register` dulu)
+    UnknownTopic = 6,
+    /// Aksi tidak diizinkan untuk client
+    Unauthorized = 7,
+    /// Frame tidak bisa disimpan ke log
+    Storage = 8,
+    /// Batas server tercapai (mis. jumlah topic per client)
+    LimitExceeded = 9,
+}
+
+impl ErrorCode {
+    #[inline(always)]
+    pub fn from_u16(v: u16) -> Option<Self> {
+        match v {
+            1 => Some(Self::Malformed),
+            2 => Some(Self::Oversize),
+            3 => Some(Self::ChecksumMismatch),
+            4 => Some(Self::UnsupportedType),
+            5 => Some(Self::InvalidTopic),
+            6 => Some(Self::UnknownTopic),
+            7 => Some(Self::Unauthorized),
+            8 => Some(Self::Storage),
+            9 => Some(Self::LimitExceeded),
+            _ => None,
+        }
+    }
+}
+
+/// `(code, reason)` dari payload `Nack` / `Error`
+///
+/// `code` dibiarkan mentah supaya kode dari server yang lebih baru tetap
+/// terbaca; pakai `ErrorCode::from_u16`. Returns `None` jika payload terlalu
+/// pendek atau alasan bukan UTF-8.
+pub fn parse_error(payload: &[u8]) -> Option<(u16, &str)> {
+    if payload.len() < ERROR_CODE_SIZE {
+        return None;
+    }
+    let (code, reason) = payload.split_at(ERROR_CODE_SIZE);
+    let reason = std::str::from_utf8(reason).ok()?;
+    Some((u16::from_le_bytes([code[0], code[1]]), reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Decoder, Encoder, MessageType};
+
+    #[test]
+    fn test_error_frame_roundtrip() {
+        let mut encoder = Encoder::new(4096);
+        encoder
+            .encode_error(
+                MessageType::Nack,
+                42,
+                ErrorCode::UnknownTopic,
+                "not registered",
+            )
+            .unwrap();
+        encoder
+            .encode_error(MessageType::Error, 0, ErrorCode::Oversize, "")
+            .unwrap();
+
+        let mut decoder = Decoder::new(encoder.as_bytes());
+        let (header, payload) = decoder.next().unwrap();
+        assert_eq!(header.msg_type, MessageType::Nack as u8);
+        assert_eq!({ header.sequence }, 42);
+        let (code, reason) = parse_error(payload).unwrap();
+        assert_eq!(ErrorCode::from_u16(code), Some(ErrorCode::UnknownTopic));
+        assert_eq!(reason, "not registered");
+
+        let (header, payload) = decoder.next().unwrap();
+        assert_eq!(header.msg_type, MessageType::Error as u8);
+        assert_eq!(parse_error(payload), Some((ErrorCode::Oversize as u16, "")));
+
+        assert_eq!(parse_error(b"\x01"), None);
+        assert_eq!(
+            parse_error(b"\x63\x00ok").map(|(c, _)| ErrorCode::from_u16(c)),
+            Some(None)
+        );
+    }
+}
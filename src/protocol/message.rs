@@ -13,6 +13,8 @@
 
 use std::mem;
 
+use super::checksum::{crc32c, legacy_checksum};
+
 /// Tipe pesan dalam Hermes
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -27,6 +29,14 @@ pub enum MessageType {
     Heartbeat = 4,
     /// Batch of messages
     Batch = 5,
+    /// Minta topic id untuk daftar nama (tanpa subscribe)
+    Register = 6,
+    /// Hentikan subscription (payload: pattern, kosong = semua)
+    Unsubscribe = 7,
+    /// Frame dengan sequence yang sama ditolak (payload: lihat `protocol::error`)
+    Nack = 8,
+    /// Error level stream; server melewati data rusak sampai header valid berikutnya
+    Error = 9,
 }
 
 impl MessageType {
@@ -38,6 +48,10 @@ impl MessageType {
             3 => Some(Self::Ack),
             4 => Some(Self::Heartbeat),
             5 => Some(Self::Batch),
+            6 => Some(Self::Register),
+            7 => Some(Self::Unsubscribe),
+            8 => Some(Self::Nack),
+            9 => Some(Self::Error),
             _ => None,
         }
     }
@@ -55,7 +69,7 @@ pub struct MessageHeader {
     pub version: u8,
     /// Tipe pesan
     pub msg_type: u8,
-    /// Flags (reserved untuk future use)
+    /// Flags, lihat `FLAG_*`
     pub flags: u16,
     /// Sequence number untuk ordering
     pub sequence: u64,
@@ -63,7 +77,7 @@ pub struct MessageHeader {
     pub timestamp_ns: u64,
     /// Panjang payload dalam bytes
     pub payload_len: u32,
-    /// CRC32 checksum payload
+    /// Checksum payload (CRC-32C jika `FLAG_CRC32C`, selain itu legacy)
     pub checksum: u32,
 }
 
@@ -72,6 +86,11 @@ pub const MAGIC: u32 = 0x48524D53; // "HRMS"
 pub const VERSION: u8 = 1;
 pub const MAX_PAYLOAD_SIZE: usize = 65536; // 64KB max payload
 
+/// `checksum` berisi CRC-32C; frame tanpa flag ini memakai checksum legacy
+pub const FLAG_CRC32C: u16 = 1 << 0;
+/// Payload diawali topic id `u32` (lihat `protocol::topic`)
+pub const FLAG_TOPIC: u16 = 1 << 1;
+
 impl MessageHeader {
     /// Membuat header baru
     #[inline(always)]
@@ -106,6 +125,27 @@ impl MessageHeader {
             && self.payload_len as usize <= MAX_PAYLOAD_SIZE
     }
 
+    /// Isi `checksum` dengan CRC-32C dari `payload`
+    #[inline(always)]
+    pub fn set_checksum(&mut self, payload: &[u8]) {
+        self.checksum = crc32c(payload);
+        self.flags |= FLAG_CRC32C;
+    }
+
+    /// Returns `true` jika `checksum` cocok dengan `payload`
+    ///
+    /// Algoritma dipilih dari `FLAG_CRC32C`; frame legacy dengan checksum 0
+    /// tidak divalidasi.
+    #[inline(always)]
+    pub fn checksum_matches(&self, payload: &[u8]) -> bool {
+        let checksum = self.checksum;
+        if self.flags & FLAG_CRC32C != 0 {
+            crc32c(payload) == checksum
+        } else {
+            checksum == 0 || legacy_checksum(payload) == checksum
+        }
+    }
+
     /// Cast dari raw bytes (ZERO-COPY!)
     ///
     /// # Safety
@@ -171,21 +211,6 @@ impl<'a> Message<'a> {
     }
 }
 
-/// CRC32 checksum (simple, fast)
-#[inline(always)]
-pub fn crc32_fast(data: &[u8]) -> u32 {
-    // Simple Adler-32 variant untuk speed
-    let mut a: u32 = 1;
-    let mut b: u32 = 0;
-
-    for &byte in data {
-        a = a.wrapping_add(byte as u32);
-        b = b.wrapping_add(a);
-    }
-
-    (b << 16) | a
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,8 +5,23 @@
 //! - Fixed-size headers: Predictable memory layout
 //! - No allocation: Encode/decode langsung ke/dari buffer
 
+mod checksum;
 mod encoder;
+mod error;
 mod message;
+mod topic;
 
-pub use encoder::{Decoder, Encoder};
-pub use message::{MessageType, HEADER_SIZE};
+pub use checksum::crc32c;
+pub use encoder::{DecodeError, Decoder, Encoder};
+pub use error::{parse_error, ErrorCode, ERROR_CODE_SIZE};
+pub use message::{
+    MessageHeader, MessageType, FLAG_CRC32C, FLAG_TOPIC, HEADER_SIZE, MAX_PAYLOAD_SIZE,
+};
+pub use topic::{
+    encode_topic_ids, encode_topic_names, frame_topic, is_valid_topic_name, is_valid_topic_pattern,
+    is_wildcard_pattern, parse_topic_ids, parse_topic_names, parse_topic_patterns, split_topic,
+    TopicId, MAX_TOPIC_NAME, NO_TOPIC, TOPIC_ID_SIZE, WILDCARD_TAIL, WILDCARD_TOKEN,
+};
+
+pub(crate) use checksum::legacy_checksum;
+pub(crate) use message::MAGIC;
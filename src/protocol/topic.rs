@@ -0,0 +1,225 @@
+//! Topic di level wire protocol
+//!
+//! - Frame dengan `FLAG_TOPIC`: payload diawali topic id `u32` (LE) dan
+//!   checksum mencakup prefix tersebut. Frame tanpa flag = `NO_TOPIC`.
+//! - Payload `Subscribe` / `Register`: nama topic dipisah `\n`. `Subscribe`
+//!   boleh berisi pattern wildcard; `Subscribe` kosong = semua topic
+//!   (perilaku lama).
+//! - Server membalas `Subscribe` / `Register` yang tidak kosong dengan `Ack`
+//!   (sequence sama) berisi id `u32` (LE) untuk setiap nama, urut sesuai
+//!   request (`NO_TOPIC` untuk pattern wildcard).
+//!
+//! Nama topic hierarkis: token dipisah `.` (mis. `analysis.base.honeypot`),
+//! total 1..=`MAX_TOPIC_NAME` byte ASCII tercetak tanpa spasi. Pattern
+//! subscribe memakai wildcard gaya NATS sebagai token utuh:
+//! - `*` cocok dengan tepat satu token (`analysis.*.honeypot`)
+//! - `>` cocok dengan satu token atau lebih, hanya di akhir (`analysis.>`)
+
+use super::message::{MessageHeader, FLAG_TOPIC, HEADER_SIZE};
+
+/// Id topic yang ringkas, dipetakan dari nama oleh registry di server
+pub type TopicId = u32;
+
+/// Topic untuk frame tanpa `FLAG_TOPIC`
+pub const NO_TOPIC: TopicId = 0;
+/// Ukuran prefix topic id di payload
+pub const TOPIC_ID_SIZE: usize = 4;
+/// Panjang maksimum nama topic (bytes)
+pub const MAX_TOPIC_NAME: usize = 255;
+
+/// Wildcard satu token
+pub const WILDCARD_TOKEN: &str = "*";
+/// Wildcard sisa token (hanya di akhir pattern)
+pub const WILDCARD_TAIL: &str = ">";
+
+/// Returns `true` jika `name` boleh dipakai sebagai nama topic (tanpa wildcard)
+pub fn is_valid_topic_name(name: &str) -> bool {
+    is_valid_topic_pattern(name) && !is_wildcard_pattern(name)
+}
+
+/// Returns `true` jika `pattern` valid untuk `Subscribe`
+pub fn is_valid_topic_pattern(pattern: &str) -> bool {
+    if pattern.is_empty() || pattern.len() > MAX_TOPIC_NAME {
+        return false;
+    }
+    let mut tokens = pattern.split('.').peekable();
+    while let Some(token) = tokens.next() {
+        let valid = match token {
+            WILDCARD_TOKEN => true,
+            WILDCARD_TAIL => tokens.peek().is_none(),
+            _ => {
+                !token.is_empty()
+                    && token
+                        .bytes()
+                        .all(|b| b.is_ascii_graphic() && b != b'*' && b != b'>')
+            }
+        };
+        if !valid {
+            return false;
+        }
+    }
+    true
+}
+
+/// Returns `true` jika `pattern` mengandung token wildcard
+pub fn is_wildcard_pattern(pattern: &str) -> bool {
+    pattern
+        .split('.')
+        .any(|token| token == WILDCARD_TOKEN || token == WILDCARD_TAIL)
+}
+
+/// Pisahkan topic id dari payload frame
+///
+/// Returns `None` jika `FLAG_TOPIC` di-set tapi payload lebih pendek dari prefix.
+#[inline(always)]
+pub fn split_topic<'a>(header: &MessageHeader, payload: &'a [u8]) -> Option<(TopicId, &'a [u8])> {
+    if header.flags & FLAG_TOPIC == 0 {
+        return Some((NO_TOPIC, payload));
+    }
+    if payload.len() < TOPIC_ID_SIZE {
+        return None;
+    }
+    let (id, data) = payload.split_at(TOPIC_ID_SIZE);
+    Some((u32::from_le_bytes([id[0], id[1], id[2], id[3]]), data))
+}
+
+/// Topic id dari frame lengkap (header + payload) tanpa decode penuh
+///
+/// Dipakai di jalur fan-out; frame harus sudah divalidasi `Decoder`.
+#[inline(always)]
+pub fn frame_topic(frame: &[u8]) -> TopicId {
+    let flags = u16::from_le_bytes([frame[6], frame[7]]);
+    match frame.get(HEADER_SIZE..HEADER_SIZE + TOPIC_ID_SIZE) {
+        Some(id) if flags & FLAG_TOPIC != 0 => u32::from_le_bytes([id[0], id[1], id[2], id[3]]),
+        _ => NO_TOPIC,
+    }
+}
+
+/// Payload `Subscribe` / `Register` dari daftar nama
+pub fn encode_topic_names<'a, I>(names: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut payload = Vec::new();
+    for name in names {
+        if !payload.is_empty() {
+            payload.push(b'\n');
+        }
+        payload.extend_from_slice(name.as_bytes());
+    }
+    payload
+}
+
+/// Nama topic dari payload `Register`
+///
+/// Returns `None` jika ada nama yang tidak valid.
+pub fn parse_topic_names(payload: &[u8]) -> Option<Vec<&str>> {
+    parse_topic_list(payload, is_valid_topic_name)
+}
+
+/// Pattern dari payload `Subscribe` (nama biasa atau wildcard)
+///
+/// Returns `None` jika ada pattern yang tidak valid.
+pub fn parse_topic_patterns(payload: &[u8]) -> Option<Vec<&str>> {
+    parse_topic_list(payload, is_valid_topic_pattern)
+}
+
+fn parse_topic_list(payload: &[u8], valid: fn(&str) -> bool) -> Option<Vec<&str>> {
+    if payload.is_empty() {
+        return Some(Vec::new());
+    }
+    payload
+        .split(|&b| b == b'\n')
+        .map(|name| std::str::from_utf8(name).ok().filter(|n| valid(n)))
+        .collect()
+}
+
+/// Payload `Ack` berisi topic id
+pub fn encode_topic_ids(ids: &[TopicId]) -> Vec<u8> {
+    ids.iter().flat_map(|id| id.to_le_bytes()).collect()
+}
+
+/// Topic id dari payload `Ack` balasan `Subscribe` / `Register`
+pub fn parse_topic_ids(payload: &[u8]) -> impl Iterator<Item = TopicId> + '_ {
+    payload
+        .chunks_exact(TOPIC_ID_SIZE)
+        .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Decoder, Encoder, MessageType};
+
+    #[test]
+    fn test_topic_frame_roundtrip() {
+        let mut encoder = Encoder::new(4096);
+        encoder
+            .encode_topic(MessageType::Publish, 7, 1, b"on topic")
+            .unwrap();
+        encoder
+            .encode(MessageType::Publish, 2, b"untagged")
+            .unwrap();
+
+        let mut decoder = Decoder::new(encoder.as_bytes());
+        let (header, payload) = decoder.next().unwrap();
+        assert_eq!(split_topic(&header, payload), Some((7, &b"on topic"[..])));
+        assert_eq!(frame_topic(encoder.as_bytes()), 7);
+
+        let (header, payload) = decoder.next().unwrap();
+        assert_eq!(
+            split_topic(&header, payload),
+            Some((NO_TOPIC, &b"untagged"[..]))
+        );
+        assert_eq!(
+            frame_topic(&encoder.as_bytes()[HEADER_SIZE + 12..]),
+            NO_TOPIC
+        );
+
+        // Flag tanpa prefix: malformed
+        let mut header = header;
+        header.flags |= FLAG_TOPIC;
+        assert_eq!(split_topic(&header, b"ab"), None);
+    }
+
+    #[test]
+    fn test_topic_names_and_ids() {
+        let payload = encode_topic_names(["prices", "analysis.base"]);
+        assert_eq!(payload, b"prices\nanalysis.base");
+        assert_eq!(
+            parse_topic_names(&payload),
+            Some(vec!["prices", "analysis.base"])
+        );
+        assert_eq!(parse_topic_names(b""), Some(vec![]));
+        assert_eq!(parse_topic_names(b"ok\n\nempty"), None);
+        assert_eq!(parse_topic_names(b"has space"), None);
+        assert!(!is_valid_topic_name(&"x".repeat(MAX_TOPIC_NAME + 1)));
+        assert_eq!(parse_topic_names(b"analysis.>"), None);
+        assert_eq!(
+            parse_topic_patterns(b"analysis.>\nprices"),
+            Some(vec!["analysis.>", "prices"])
+        );
+
+        let ids = encode_topic_ids(&[1, 2, 300]);
+        assert_eq!(parse_topic_ids(&ids).collect::<Vec<_>>(), vec![1, 2, 300]);
+    }
+
+    #[test]
+    fn test_topic_name_and_pattern_syntax() {
+        for name in ["prices", "analysis.base.honeypot", "a-b_c.d1"] {
+            assert!(is_valid_topic_name(name), "{}", name);
+            assert!(is_valid_topic_pattern(name), "{}", name);
+        }
+        for pattern in ["*", ">", "analysis.*", "analysis.>", "*.base.*", "a.*.>"] {
+            assert!(is_valid_topic_pattern(pattern), "{}", pattern);
+            assert!(is_wildcard_pattern(pattern), "{}", pattern);
+            assert!(!is_valid_topic_name(pattern), "{}", pattern);
+        }
+        for bad in [
+            "", ".", "a.", ".a", "a..b", "a.>.b", "> ", "a*", "a.b>", "x y",
+        ] {
+            assert!(!is_valid_topic_pattern(bad), "{:?}", bad);
+            assert!(!is_valid_topic_name(bad), "{:?}", bad);
+        }
+    }
+}
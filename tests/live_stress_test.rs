@@ -166,7 +166,7 @@ impl StressStats {
         let min_latency = self.min_latency_ns.load(Ordering::Relaxed);
         let max_latency = self.max_latency_ns.load(Ordering::Relaxed);
 
-        let avg_latency = if sent > 0 { total_latency / sent } else { 0 };
+        let avg_latency = total_latency.checked_div(sent).unwrap_or(0);
         let rate = sent as f64 / duration.as_secs_f64();
 
         println!("\n📊 STRESS TEST RESULTS");